@@ -0,0 +1,12 @@
+-- Migration 004: Per-disk file type breakdown
+-- Rebuilt from the files table in the same transaction as each disk scan.
+
+CREATE TABLE IF NOT EXISTS file_type_stats (
+    disk_id     INTEGER NOT NULL REFERENCES disks(id),
+    extension   TEXT NOT NULL,
+    file_count  INTEGER NOT NULL,
+    total_bytes INTEGER NOT NULL,
+    PRIMARY KEY (disk_id, extension)
+);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (4);
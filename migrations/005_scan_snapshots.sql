@@ -0,0 +1,15 @@
+-- Migration 005: Per-disk scan snapshots for growth reports
+-- One row per completed disk scan; extension_bytes is a JSON object of
+-- extension -> bytes copied from file_type_stats at scan time.
+
+CREATE TABLE IF NOT EXISTS scan_snapshots (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    disk_id         INTEGER NOT NULL REFERENCES disks(id),
+    scanned_at      TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    total_files     INTEGER NOT NULL,
+    total_bytes     INTEGER NOT NULL,
+    extension_bytes TEXT NOT NULL DEFAULT '{}'
+);
+CREATE INDEX IF NOT EXISTS idx_scan_snapshots_disk ON scan_snapshots(disk_id, id DESC);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (5);
@@ -0,0 +1,7 @@
+-- Migration 006: Track hardlinks in the file catalog
+-- A file with nlink > 1 shares its data with another path; moving one link
+-- alone would duplicate the data, so the planner leaves these files in place.
+
+ALTER TABLE files ADD COLUMN inode INTEGER;
+ALTER TABLE files ADD COLUMN nlink INTEGER NOT NULL DEFAULT 1;
+INSERT OR IGNORE INTO schema_version (version) VALUES (6);
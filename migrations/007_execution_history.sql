@@ -0,0 +1,13 @@
+-- Migration 007: Throughput of finished executions
+-- Feeds the plan ETA with a rolling average of real transfer rates.
+
+CREATE TABLE IF NOT EXISTS execution_history (
+    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_id          INTEGER NOT NULL,
+    finished_at      TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    moves_completed  INTEGER NOT NULL,
+    bytes_moved      INTEGER NOT NULL,
+    duration_seconds REAL NOT NULL
+);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (7);
@@ -0,0 +1,5 @@
+-- Migration 008: Record why a plan was cancelled
+-- e.g. "cancelled by user: watching a movie" or "daemon shutting down".
+
+ALTER TABLE balance_plans ADD COLUMN cancel_reason TEXT;
+INSERT OR IGNORE INTO schema_version (version) VALUES (8);
@@ -0,0 +1,15 @@
+-- Migration 009: Reverse metadata for completed moves
+-- Lets a single move be undone even after its plan is gone.
+
+CREATE TABLE IF NOT EXISTS executed_moves (
+    move_id      INTEGER PRIMARY KEY,
+    source_mount TEXT NOT NULL,
+    target_mount TEXT NOT NULL,
+    file_path    TEXT NOT NULL,
+    file_size    INTEGER NOT NULL,
+    target_mtime INTEGER,
+    executed_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    undone_at    TEXT
+);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (9);
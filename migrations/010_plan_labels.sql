@@ -0,0 +1,14 @@
+-- Migration 010: Optional plan names and tags
+-- Lets a plan be called e.g. "pre-disk5-retirement" and found again by tag.
+
+ALTER TABLE balance_plans ADD COLUMN name TEXT;
+
+CREATE TABLE IF NOT EXISTS plan_tags (
+    plan_id INTEGER NOT NULL REFERENCES balance_plans(id) ON DELETE CASCADE,
+    tag     TEXT NOT NULL,
+    PRIMARY KEY (plan_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_plan_tags_tag ON plan_tags(tag);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (10);
@@ -0,0 +1,8 @@
+-- Migration 011: Target paths rewritten by PATH_REMAP
+-- NULL means the file kept its relative path on the target disk.
+
+ALTER TABLE planned_moves ADD COLUMN target_path TEXT;
+
+ALTER TABLE executed_moves ADD COLUMN target_path TEXT;
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (11);
@@ -0,0 +1,6 @@
+-- Migration 012: Mark links cataloged under SYMLINK_POLICY=follow
+-- Such an entry carries its target's size, but a move would carry the link,
+-- so the planner leaves these entries in place.
+
+ALTER TABLE files ADD COLUMN followed_link INTEGER NOT NULL DEFAULT 0;
+INSERT OR IGNORE INTO schema_version (version) VALUES (12);
@@ -0,0 +1,85 @@
+use crate::api::responses::ApiResponse;
+use crate::AppState;
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Reject mutating requests that lack the configured bearer token.
+///
+/// Read-only methods stay open so status polling and the SSE stream keep
+/// working; with no `api_token` configured every request passes through.
+pub(crate) async fn require_token(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let config = state.config();
+    let Some(token) = config.api_token.as_deref() else {
+        return next.run(req).await;
+    };
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+
+    let provided = req.headers().get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+    if bearer_matches(provided, token) {
+        next.run(req).await
+    } else {
+        unauthorized()
+    }
+}
+
+/// Require the configured token before upgrading `/api/ws`, whose sockets
+/// accept control commands even though the upgrade itself is a GET.
+///
+/// Browsers can't set headers on a WebSocket, so `?token=` is accepted as
+/// well as the `Authorization` header.
+pub(crate) async fn require_ws_token(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let config = state.config();
+    let Some(token) = config.api_token.as_deref() else {
+        return next.run(req).await;
+    };
+    let header_value = req.headers().get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+    let query_token = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+        .ok()
+        .and_then(|Query(mut params)| params.remove("token"));
+    if bearer_matches(header_value, token)
+        || query_token.is_some_and(|provided| token_matches(&provided, token))
+    {
+        next.run(req).await
+    } else {
+        unauthorized()
+    }
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, Json(ApiResponse::<&str>::err("Missing or invalid API token")))
+        .into_response()
+}
+
+/// Check an `Authorization` header value against the expected token.
+///
+/// The comparison doesn't short-circuit on the first differing byte, so response
+/// timing doesn't reveal how much of a guessed token was right.
+pub(crate) fn bearer_matches(header_value: Option<&str>, token: &str) -> bool {
+    header_value
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|provided| token_matches(provided, token))
+}
+
+/// Constant-time comparison of a provided token against the expected one.
+fn token_matches(provided: &str, token: &str) -> bool {
+    let (provided, expected) = (provided.trim().as_bytes(), token.as_bytes());
+    provided.len() == expected.len()
+        && provided.iter().zip(expected).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
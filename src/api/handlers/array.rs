@@ -0,0 +1,99 @@
+use crate::api::responses::{
+    ApiResponse, ArraySummary, DiskClassCounts, DiskUtilization, ImbalanceResponse,
+};
+use crate::balancer::analysis::{
+    balance_score, balance_target, classify, effective_tolerance, max_imbalance,
+    target_utilization, BalanceTarget,
+};
+use crate::balancer::types::DiskClass;
+use crate::balancer::BalanceMetric;
+use crate::db::Disk;
+use crate::AppState;
+use axum::{extract::State, response::IntoResponse, Json};
+use std::sync::Arc;
+
+pub(crate) async fn get_summary(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.db.get_all_disks() {
+        Ok(disks) => Json(ApiResponse::ok(build_summary(&state, &disks))),
+        Err(e) => Json(ApiResponse::<ArraySummary>::err(format!("Failed to get disks: {e}"))),
+    }
+}
+
+pub(crate) async fn get_imbalance(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.db.get_all_disks() {
+        Ok(disks) => Json(ApiResponse::ok(build_imbalance(&disks, state.config().balance_metric))),
+        Err(e) => Json(ApiResponse::<ImbalanceResponse>::err(format!("Failed to get disks: {e}"))),
+    }
+}
+
+/// Target and per-disk deviation over included disks, using the planner's math.
+fn build_imbalance(disks: &[Disk], metric: BalanceMetric) -> ImbalanceResponse {
+    let included: Vec<Disk> = disks.iter().filter(|d| d.included).cloned().collect();
+    let target = balance_target(&included, metric);
+    let deviation = |d: &Disk, t: BalanceTarget| d.utilization() - t.utilization_for(d.total_bytes);
+
+    ImbalanceResponse {
+        target_utilization: target_utilization(&included),
+        balance_metric: metric,
+        target_free_bytes: match target {
+            Some(BalanceTarget::FreeBytes(free)) => Some(free),
+            _ => None,
+        },
+        max_imbalance: target.map(|t| max_imbalance(&included, t)),
+        balance_score: target.map(|t| {
+            balance_score(
+                included.iter().map(|d| (d.utilization(), t.utilization_for(d.total_bytes))),
+            )
+        }),
+        disks: included
+            .iter()
+            .map(|d| DiskUtilization {
+                disk_id: d.id,
+                disk_name: d.disk_name.clone(),
+                utilization: d.utilization(),
+                deviation: target.map(|t| deviation(d, t)),
+            })
+            .collect(),
+    }
+}
+
+/// Aggregate capacity over every known disk; balance figures over included disks only.
+fn build_summary(state: &AppState, disks: &[Disk]) -> ArraySummary {
+    let total_capacity: u64 = disks.iter().map(|d| d.total_bytes).sum();
+    let total_used: u64 = disks.iter().map(|d| d.used_bytes).sum();
+    let total_free: u64 = disks.iter().map(|d| d.free_bytes).sum();
+
+    let included: Vec<Disk> = disks.iter().filter(|d| d.included).cloned().collect();
+    let config = state.config();
+    let target = balance_target(&included, config.balance_metric);
+    let tolerance = effective_tolerance(config.max_tolerance, config.slider_alpha);
+
+    let mut class_counts = DiskClassCounts::default();
+    if let Some(target) = target {
+        for disk in &included {
+            match classify(disk.utilization(), target.utilization_for(disk.total_bytes), tolerance)
+            {
+                DiskClass::OverUtilized => class_counts.over_utilized += 1,
+                DiskClass::AboveAverage => class_counts.above_average += 1,
+                DiskClass::BelowAverage => class_counts.below_average += 1,
+                DiskClass::UnderUtilized => class_counts.under_utilized += 1,
+            }
+        }
+    }
+
+    ArraySummary {
+        total_capacity,
+        total_used,
+        total_free,
+        utilization: if total_capacity == 0 {
+            0.0
+        } else {
+            total_used as f64 / total_capacity as f64
+        },
+        included_disks: included.len(),
+        excluded_disks: disks.len() - included.len(),
+        target_utilization: target_utilization(&included),
+        max_imbalance: target.map(|t| max_imbalance(&included, t)),
+        class_counts,
+    }
+}
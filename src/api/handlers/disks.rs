@@ -1,4 +1,5 @@
-use crate::api::responses::ApiResponse;
+use crate::api::responses::{codes, ApiResponse, BulkDiskRequest, BulkDiskResult, DiskGrowth};
+use crate::db::FileTypeStat;
 use crate::AppState;
 use axum::{
     extract::{Path, State},
@@ -35,3 +36,65 @@ pub(crate) async fn set_disk_excluded(
         Err(e) => Json(ApiResponse::<&str>::err(format!("{e}"))),
     }
 }
+
+/// Apply an include/exclude selection to many disks at once.
+///
+/// Unknown ids don't fail the batch; they come back in `unknown_ids`. An id in
+/// both lists is rejected since there's no telling which was meant.
+pub(crate) async fn set_disks_included_bulk(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BulkDiskRequest>,
+) -> impl IntoResponse {
+    if let Some(id) = req.included.iter().find(|id| req.excluded.contains(id)) {
+        return Json(ApiResponse::err_with_code(
+            codes::VALIDATION,
+            format!("Disk {id} is in both included and excluded"),
+        ));
+    }
+    let result =
+        state.db.set_disks_included_bulk(&req.included, &req.excluded).and_then(|unknown_ids| {
+            Ok(BulkDiskResult { disks: state.db.get_all_disks()?, unknown_ids })
+        });
+    match result {
+        Ok(result) => Json(ApiResponse::ok(result)),
+        Err(e) => Json(ApiResponse::err(format!("Failed to update disks: {e}"))),
+    }
+}
+
+pub(crate) async fn get_disk_file_types(
+    State(state): State<Arc<AppState>>,
+    Path(disk_id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.get_disk(disk_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Json(ApiResponse::<Vec<FileTypeStat>>::err_with_code(
+                codes::DISK_NOT_FOUND,
+                "Disk not found",
+            ));
+        }
+        Err(e) => return Json(ApiResponse::<Vec<FileTypeStat>>::err(format!("{e}"))),
+    }
+    match state.db.get_file_type_stats(disk_id) {
+        Ok(stats) => Json(ApiResponse::ok(stats)),
+        Err(e) => Json(ApiResponse::<Vec<FileTypeStat>>::err(format!(
+            "Failed to get file type stats: {e}"
+        ))),
+    }
+}
+
+pub(crate) async fn get_disk_growth(
+    State(state): State<Arc<AppState>>,
+    Path(disk_id): Path<i64>,
+) -> impl IntoResponse {
+    let snapshots = match state.db.get_recent_snapshots(disk_id, 2) {
+        Ok(s) => s,
+        Err(e) => {
+            return Json(ApiResponse::<DiskGrowth>::err(format!("Failed to get snapshots: {e}")));
+        }
+    };
+    match snapshots.first() {
+        Some(current) => Json(ApiResponse::ok(DiskGrowth::between(current, snapshots.get(1)))),
+        None => Json(ApiResponse::<DiskGrowth>::err("Disk has not been scanned yet")),
+    }
+}
@@ -1,7 +1,12 @@
-use crate::api::responses::ApiResponse;
+use crate::api::responses::{
+    codes, ApiError, ApiResponse, CancelRequest, InvalidMove, PlanValidation,
+};
 use crate::db::{MoveStatus, PlanStatus};
 use crate::events::EventHub;
-use crate::{AppState, DaemonState, DaemonStatus};
+use crate::executor::audit::AuditLog;
+use crate::executor::notify::{notify_execution, ExecutionReport};
+use crate::executor::{disk_path, OnConflict, OpenFilePolicy};
+use crate::{AppState, DaemonState, DaemonStatus, ExecutionProgress};
 use axum::{
     extract::{Path, State},
     response::IntoResponse,
@@ -9,54 +14,91 @@ use axum::{
 };
 use futures::FutureExt;
 use std::panic::AssertUnwindSafe;
-use std::sync::{Arc, LazyLock};
-use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use std::sync::Arc;
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{error, info, Instrument};
 
-/// Pre-compiled regex for parsing rsync `--info=progress2` output.
-#[allow(clippy::unwrap_used)] // Compile-time constant regex, provably valid
-static PROGRESS_RE: LazyLock<regex::Regex> =
-    LazyLock::new(|| regex::Regex::new(r"(\d+)%\s+([\d.]+\w+/s)?\s*([\d:]+)?").unwrap());
+/// Skip reason for a move whose source and target mounts are one device.
+const SAME_DEVICE: &str = "Source and target are the same device";
 
 /// All the context needed to execute a single rsync file move.
 struct RsyncJob<'a> {
     move_id: i64,
     file_path: &'a str,
+    /// Relative path on the target disk; differs from `file_path` under `PATH_REMAP`.
+    target_path: &'a str,
     source_mount: &'a str,
     target_mount: &'a str,
     file_size: u64,
-    use_progress2: bool,
+    rsync: &'a crate::executor::RsyncOptions,
+    /// Remove the partial target immediately on cancel instead of leaving it for a resume.
+    cleanup_on_cancel: bool,
+    /// Let a cancel wait for this transfer to finish rather than killing it.
+    graceful_cancel: bool,
+    /// Also compare the copy against the source's pre-copy size; the planned
+    /// size is always checked.
+    verify_size: bool,
+    /// Compare extended attributes (and ACLs when copied) before removing the source.
+    verify_xattrs: bool,
+    /// Kill rsync when it goes this long without printing progress.
+    move_timeout: Option<std::time::Duration>,
     event_hub: &'a EventHub,
     cancel: &'a CancellationToken,
+    /// Abandons just this move, see `POST /api/plan/{id}/skip-current`.
+    skip: &'a CancellationToken,
     rsync_child_slot: &'a tokio::sync::Mutex<Option<tokio::process::Child>>,
+    /// Receives this move's transfer percent while it is the current move.
+    progress: &'a tokio::sync::RwLock<Option<ExecutionProgress>>,
 }
 
 pub(crate) async fn execute_plan(
     State(state): State<Arc<AppState>>,
     Path(plan_id): Path<i64>,
 ) -> impl IntoResponse {
+    match begin_execution(&state, plan_id).await {
+        Ok(()) => Json(ApiResponse::ok("Execution started")),
+        Err(e) => Json(ApiResponse::<&str>::from(e)),
+    }
+}
+
+/// Fail fast when rsync is missing, instead of failing every move of a plan.
+async fn require_rsync(state: &AppState) -> Result<(), ApiError> {
+    if state.rsync_info().await.version.is_some() {
+        return Ok(());
+    }
+    Err(ApiError::new(
+        codes::RSYNC_MISSING,
+        "rsync is not installed or not on PATH; install it and restart the daemon",
+    ))
+}
+
+/// Validate a plan and start executing it as the background task.
+pub(crate) async fn begin_execution(state: &Arc<AppState>, plan_id: i64) -> Result<(), ApiError> {
     // Validate plan exists and is executable (before acquiring status lock)
     match state.db.get_plan(plan_id) {
         Ok(Some(plan)) if plan.status == PlanStatus::Planned => {}
         Ok(Some(plan)) => {
-            return Json(ApiResponse::<&str>::err(format!(
-                "Plan is in '{}' status, can only execute 'planned' plans",
-                plan.status
-            )));
+            return Err(ApiError::new(
+                codes::WRONG_STATUS,
+                format!("Plan is in '{}' status, can only execute 'planned' plans", plan.status),
+            ));
         }
         Ok(None) => {
-            return Json(ApiResponse::<&str>::err("Plan not found"));
+            return Err(ApiError::new(codes::PLAN_NOT_FOUND, "Plan not found"));
         }
         Err(e) => {
-            return Json(ApiResponse::<&str>::err(format!("{e}")));
+            return Err(format!("{e}").into());
         }
     }
 
+    require_rsync(state).await?;
+
     // Check parity (before acquiring status lock)
-    if state.config.warn_parity_check {
+    if state.config().warn_parity_check {
         match crate::executor::is_parity_check_running().await {
             Ok(true) => {
-                return Json(ApiResponse::<&str>::err(
+                return Err(ApiError::new(
+                    codes::PARITY_RUNNING,
                     "A parity check is currently running. \
                      Stop it first or disable the warning in settings.",
                 ));
@@ -73,52 +115,249 @@ pub(crate) async fn execute_plan(
     {
         let mut status = state.status.write().await;
         if status.state != DaemonState::Idle {
-            return Json(ApiResponse::<&str>::err(format!(
-                "Cannot execute: daemon is currently {:?}",
-                status.state
-            )));
+            return Err(ApiError::busy("execute", status.state));
         }
         *status = DaemonStatus::executing("Starting plan execution...");
     }
 
     let token = state.new_operation_token().await;
 
-    let state_clone = Arc::clone(&state);
-    let handle = tokio::spawn(async move {
-        let result = AssertUnwindSafe(async {
-            match process_plan_moves(&state_clone, plan_id, &token).await {
-                Ok(()) => {
-                    info!("Plan {} execution task completed", plan_id);
+    let state_clone = Arc::clone(state);
+    let handle = tokio::spawn(
+        async move {
+            let result = AssertUnwindSafe(async {
+                match process_plan_moves(&state_clone, plan_id, &token).await {
+                    Ok(()) => {
+                        info!("Plan {} execution task completed", plan_id);
+                    }
+                    Err(e) => {
+                        error!("Plan {} execution failed: {}", plan_id, e);
+                        let _ = state_clone.event_hub.publish(crate::events::Event::DaemonError {
+                            message: format!("Execution failed: {e}"),
+                        });
+                        notify_execution(
+                            state_clone.config().notify_webhook_url.as_deref(),
+                            ExecutionReport {
+                                plan_id,
+                                status: PlanStatus::Failed,
+                                moves_completed: 0,
+                                moves_failed: 0,
+                                moves_skipped: 0,
+                                duration_seconds: 0.0,
+                                error: Some(format!("{e:#}")),
+                                cancel_reason: None,
+                            },
+                        );
+                    }
                 }
-                Err(e) => {
-                    error!("Plan {} execution failed: {}", plan_id, e);
-                    let _ = state_clone.event_hub.publish(crate::events::Event::DaemonError {
-                        message: format!("Execution failed: {e}"),
+            })
+            .catch_unwind()
+            .await;
+
+            if result.is_err() {
+                error!("Plan {} execution panicked!", plan_id);
+                // Best-effort panic recovery
+                let _ = state_clone.db.update_plan_status(plan_id, PlanStatus::Failed);
+                let _ = state_clone.db.fail_in_progress_moves(plan_id);
+                let _ = state_clone.event_hub.publish(crate::events::Event::DaemonError {
+                    message: format!("Execution panicked for plan {plan_id}"),
+                });
+            }
+
+            // ALWAYS reset to idle — both normal and panic paths
+            *state_clone.progress.write().await = None;
+            *state_clone.status.write().await = DaemonStatus::idle();
+            *state_clone.background_task.lock().await = None;
+
+            // Only once idle: the rescan is an ordinary scan task and replaces this one.
+            if state_clone.config().auto_rescan_after_execution && !token.is_cancelled() {
+                rescan_touched_disks(&state_clone, plan_id).await;
+            }
+        }
+        .instrument(tracing::info_span!("execution", plan_id)),
+    );
+
+    *state.background_task.lock().await = Some(handle);
+
+    Ok(())
+}
+
+/// Rescan the disks that this plan's completed moves read from or wrote to.
+///
+/// Disks excluded from scans are left alone. If something else already left
+/// the idle state, the rescan is skipped with a warning.
+async fn rescan_touched_disks(state: &Arc<AppState>, plan_id: i64) {
+    let config = state.config();
+    let touched = state.db.get_plan_moves(plan_id).and_then(|moves| {
+        let ids: std::collections::BTreeSet<i64> = moves
+            .iter()
+            .map(|d| &d.move_info)
+            .filter(|m| m.status == MoveStatus::Completed)
+            .flat_map(|m| [m.source_disk_id, m.target_disk_id])
+            .collect();
+        let mut disks = Vec::new();
+        for id in ids {
+            if let Some(disk) = state.db.get_disk(id)? {
+                if !config.excluded_disks.contains(&disk.disk_name) {
+                    disks.push(crate::scanner::DiscoveredDisk {
+                        name: disk.disk_name,
+                        mount_path: disk.mount_path,
                     });
                 }
             }
-        })
-        .catch_unwind()
-        .await;
-
-        if result.is_err() {
-            error!("Plan {} execution panicked!", plan_id);
-            // Best-effort panic recovery
-            let _ = state_clone.db.update_plan_status(plan_id, PlanStatus::Failed);
-            let _ = state_clone.db.fail_in_progress_moves(plan_id);
-            let _ = state_clone.event_hub.publish(crate::events::Event::DaemonError {
-                message: format!("Execution panicked for plan {plan_id}"),
+        }
+        Ok(disks)
+    });
+    match touched {
+        Ok(disks) if disks.is_empty() => {}
+        Ok(disks) => {
+            info!("Rescanning {} disks touched by plan {}", disks.len(), plan_id);
+            if let Err(e) = super::begin_disks_scan(state, config.scan_threads, disks).await {
+                tracing::warn!("Post-execution rescan skipped: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Post-execution rescan skipped: {:#}", e),
+    }
+}
+
+/// IDs of disks failing their SMART health check, announced as daemon errors.
+/// Empty, with a warning, when health can't be checked at all.
+pub(super) async fn unhealthy_disks(
+    state: &AppState,
+    disks: &[crate::db::Disk],
+) -> std::collections::HashSet<i64> {
+    let names: Vec<&str> = disks.iter().map(|d| d.disk_name.as_str()).collect();
+    // Health checks are a safeguard, not a dependency: without disks.ini or
+    // smartctl every disk stays usable.
+    let failing = match crate::executor::smart_failing_disks(&names).await {
+        Ok(failing) => failing,
+        Err(e) => {
+            tracing::warn!("SMART health not checked: {:#}", e);
+            let _ = state.event_hub.publish(crate::events::Event::DaemonError {
+                message: format!("SMART health not checked: {e:#}"),
             });
+            return std::collections::HashSet::new();
         }
+    };
+    disks
+        .iter()
+        .filter_map(|d| {
+            let reason = failing.get(&d.disk_name)?;
+            tracing::warn!("Not using {} as a move target: {}", d.disk_name, reason);
+            let _ = state.event_hub.publish(crate::events::Event::DaemonError {
+                message: format!("{} excluded as a move target: {reason}", d.disk_name),
+            });
+            Some(d.id)
+        })
+        .collect()
+}
 
-        // ALWAYS reset to idle — both normal and panic paths
-        *state_clone.status.write().await = DaemonStatus::idle();
-        *state_clone.background_task.lock().await = None;
-    });
+/// Disks this plan reads from or writes to that are unmounted, read-only or
+/// refuse a probe write, with the reason; each is announced as a daemon error.
+async fn unusable_disks(
+    state: &AppState,
+    plan_id: i64,
+    disks: &[crate::db::Disk],
+) -> anyhow::Result<std::collections::HashMap<i64, String>> {
+    let involved: std::collections::HashSet<i64> = state
+        .db
+        .get_plan_moves(plan_id)?
+        .iter()
+        .filter(|m| m.move_info.status == MoveStatus::Pending)
+        .flat_map(|m| [m.move_info.source_disk_id, m.move_info.target_disk_id])
+        .collect();
+    let mounts: Vec<(i64, String)> = disks
+        .iter()
+        .filter(|d| involved.contains(&d.id))
+        .map(|d| (d.id, d.mount_path.clone()))
+        .collect();
+    // The probe write can wait on a disk spinning up, so keep it off the runtime.
+    let problems = tokio::task::spawn_blocking(move || {
+        let table = crate::scanner::parse_mount_table();
+        mounts
+            .into_iter()
+            .filter_map(|(id, mount)| {
+                Some((id, crate::scanner::mount_problem(&mount, &table, true)?))
+            })
+            .collect::<std::collections::HashMap<i64, String>>()
+    })
+    .await?;
+    for problem in problems.values() {
+        tracing::warn!("Skipping moves on unusable disk: {}", problem);
+        let _ = state.event_hub.publish(crate::events::Event::DaemonError {
+            message: format!("Skipping moves to and from {problem}"),
+        });
+    }
+    Ok(problems)
+}
 
-    *state.background_task.lock().await = Some(handle);
+/// With `prune_empty_dirs`, remove the source directories that this plan's
+/// completed moves left empty.
+async fn prune_emptied_dirs(
+    state: &AppState,
+    plan_id: i64,
+    disk_map: &std::collections::HashMap<i64, String>,
+) {
+    if !state.config().prune_empty_dirs {
+        return;
+    }
+    let moves = match state.db.get_plan_moves(plan_id) {
+        Ok(moves) => moves,
+        Err(e) => {
+            tracing::warn!("Skipping empty directory cleanup: {}", e);
+            return;
+        }
+    };
+    let mut removed = 0;
+    for m in moves.iter().map(|d| &d.move_info).filter(|m| m.status == MoveStatus::Completed) {
+        if let Some(mount) = disk_map.get(&m.source_disk_id) {
+            removed += crate::executor::prune_empty_parents(mount, &m.file_path).await;
+        }
+    }
+    if removed > 0 {
+        info!("Removed {} empty source directories after plan {}", removed, plan_id);
+    }
+}
 
-    Json(ApiResponse::ok("Execution started"))
+/// Publish an [`ExecutionHeartbeat`](crate::events::Event::ExecutionHeartbeat)
+/// every `every` from the live progress until the returned guard is dropped.
+fn spawn_heartbeat(state: Arc<AppState>, every: std::time::Duration) -> DropGuard {
+    let stop = CancellationToken::new();
+    let guard = stop.clone().drop_guard();
+    let started = std::time::Instant::now();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        ticker.tick().await; // the first tick is immediate
+        loop {
+            tokio::select! {
+                () = stop.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            let Some(p) = state.progress.read().await.clone() else {
+                continue;
+            };
+            let in_flight = (p.current_size as f64 * p.current_percent / 100.0) as u64;
+            let bytes_done = (p.bytes_done + in_flight).min(p.bytes_total);
+            let elapsed = started.elapsed().as_secs_f64();
+            let eta =
+                crate::executor::estimate::remaining_seconds(bytes_done, p.bytes_total, elapsed);
+            let expected_completion = eta.map(|secs| {
+                let finish = chrono::Utc::now() + chrono::Duration::seconds(secs.round() as i64);
+                finish.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            });
+            let _ = state.event_hub.publish(crate::events::Event::ExecutionHeartbeat {
+                plan_id: p.plan_id,
+                moves_done: p.moves_done,
+                moves_total: p.moves_total,
+                bytes_done,
+                bytes_total: p.bytes_total,
+                elapsed_seconds: elapsed,
+                eta_seconds: eta,
+                expected_completion,
+            });
+        }
+    });
+    guard
 }
 
 async fn process_plan_moves(
@@ -127,24 +366,58 @@ async fn process_plan_moves(
     cancel: &CancellationToken,
 ) -> anyhow::Result<()> {
     let start = std::time::Instant::now();
+    let config = state.config();
 
     let disks = state.db.get_all_disks()?;
     let disk_map: std::collections::HashMap<i64, String> =
         disks.iter().map(|d| (d.id, d.mount_path.clone())).collect();
 
+    let unhealthy = if config.check_smart {
+        unhealthy_disks(state, &disks).await
+    } else {
+        std::collections::HashSet::new()
+    };
+    let unusable = unusable_disks(state, plan_id, &disks).await?;
+
     state.db.update_plan_status(plan_id, PlanStatus::Executing)?;
 
-    let use_progress2 = crate::executor::rsync_supports_progress2().await;
+    let pending: Vec<u64> = state
+        .db
+        .get_plan_moves(plan_id)?
+        .iter()
+        .filter(|m| m.move_info.status == MoveStatus::Pending)
+        .map(|m| m.move_info.file_size)
+        .collect();
+    *state.progress.write().await = Some(ExecutionProgress {
+        plan_id,
+        moves_total: pending.len() as u32,
+        bytes_total: pending.iter().sum(),
+        ..ExecutionProgress::default()
+    });
+    // Stops the heartbeat however this function returns.
+    let _heartbeat = (config.heartbeat_secs > 0).then(|| {
+        spawn_heartbeat(Arc::clone(state), std::time::Duration::from_secs(config.heartbeat_secs))
+    });
+
+    let rsync = crate::executor::RsyncOptions::from_config(
+        &config,
+        state.rsync_info().await.supports_progress2,
+    );
     let max_phase = state.db.get_max_phase(plan_id)?;
 
     let mut completed = 0u32;
     let mut failed = 0u32;
     let mut skipped = 0u32;
+    let mut bytes_moved = 0u64;
+    let mut audit = AuditLog::open(config.audit_log_path.as_deref(), disk_map.clone()).await;
+    // Targets that ran out of space this run; their pending moves are already skipped.
+    let mut full_targets = std::collections::HashSet::new();
+    let move_delay = std::time::Duration::from_millis(config.move_delay_ms);
+    let mut transferred = false;
 
     for phase in 1..=max_phase {
         if cancel.is_cancelled() {
-            state.db.update_plan_status(plan_id, PlanStatus::Cancelled)?;
-            return Ok(());
+            break;
         }
 
         let moves = state.db.get_pending_moves_for_phase(plan_id, phase)?;
@@ -153,45 +426,63 @@ async fn process_plan_moves(
             if cancel.is_cancelled() {
                 break;
             }
+            update_progress(state, |p| {
+                p.moves_done = completed + failed + skipped;
+                p.bytes_done = bytes_moved;
+                p.current_move_id = None;
+                p.current_file = None;
+                p.current_size = 0;
+                p.current_percent = 0.0;
+            })
+            .await;
 
             let m = &move_detail.move_info;
+            if full_targets.contains(&m.target_disk_id) {
+                continue;
+            }
+            let target_path = config.path_remap.target_for(m);
             let source_mount = if let Some(p) = disk_map.get(&m.source_disk_id) {
                 p.clone()
             } else {
-                state.db.update_move_status(
-                    m.id,
-                    MoveStatus::Failed,
-                    Some("Unknown source disk"),
-                )?;
+                fail_move(state, &mut audit, m, &target_path, "Unknown source disk".to_string())
+                    .await?;
                 failed += 1;
                 continue;
             };
             let target_mount = if let Some(p) = disk_map.get(&m.target_disk_id) {
                 p.clone()
             } else {
-                state.db.update_move_status(
-                    m.id,
-                    MoveStatus::Failed,
-                    Some("Unknown target disk"),
-                )?;
+                fail_move(state, &mut audit, m, &target_path, "Unknown target disk".to_string())
+                    .await?;
                 failed += 1;
                 continue;
             };
-
-            let source_full = format!("{}/{}", source_mount, m.file_path);
-
-            if !std::path::Path::new(&source_full).exists() {
-                state.db.update_move_status(
-                    m.id,
-                    MoveStatus::Skipped,
-                    Some("Source file not found"),
-                )?;
+            if unhealthy.contains(&m.target_disk_id) {
+                let reason = "Target disk failed its SMART health check".to_string();
+                skip_move(state, &mut audit, m, &target_path, reason).await?;
                 skipped += 1;
                 continue;
             }
+            if let Some(problem) =
+                unusable.get(&m.source_disk_id).or_else(|| unusable.get(&m.target_disk_id))
+            {
+                let reason = format!("Disk unusable: {problem}");
+                skip_move(state, &mut audit, m, &target_path, reason).await?;
+                skipped += 1;
+                continue;
+            }
+            if crate::executor::same_device(&source_mount, &target_mount) {
+                let reason = SAME_DEVICE.to_string();
+                skip_move(state, &mut audit, m, &target_path, reason).await?;
+                skipped += 1;
+                continue;
+            }
+
+            let source_full = disk_path(&source_mount, &m.file_path);
 
-            // Fix 5: Pre-move file size validation
-            match tokio::fs::metadata(&source_full).await {
+            // Fix 5: Pre-move file size validation. A link is moved as a link,
+            // so stat it rather than what it points to.
+            let source_meta = match tokio::fs::symlink_metadata(&source_full).await {
                 Ok(meta) => {
                     let current_size = meta.len();
                     if current_size != m.file_size {
@@ -200,99 +491,113 @@ async fn process_plan_moves(
                             m.file_size, current_size
                         );
                         tracing::warn!("Skipping move {}: {}", m.id, msg);
-                        state.db.update_move_status(m.id, MoveStatus::Skipped, Some(&msg))?;
+                        skip_move(state, &mut audit, m, &target_path, msg).await?;
                         skipped += 1;
-                        let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
-                            move_id: m.id,
-                            status: "skipped".to_string(),
-                            verified: false,
-                            error: Some(msg),
-                        });
                         continue;
                     }
+                    meta
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    let reason = "Source file not found".to_string();
+                    skip_move(state, &mut audit, m, &target_path, reason).await?;
+                    skipped += 1;
+                    continue;
                 }
                 Err(e) => {
                     let msg = format!("Failed to stat source file: {e}");
-                    state.db.update_move_status(m.id, MoveStatus::Failed, Some(&msg))?;
+                    fail_move(state, &mut audit, m, &target_path, msg).await?;
                     failed += 1;
                     continue;
                 }
-            }
+            };
 
-            // Fix 4: Pre-move free space check
-            {
-                let target_mount_path = &target_mount;
-                match crate::scanner::get_disk_space(target_mount_path) {
-                    Ok(space) => {
-                        let required = m.file_size.saturating_add(state.config.min_free_headroom);
-                        if space.free < required {
-                            let msg = format!(
-                                "Insufficient space on target disk (need {} bytes, have {} free)",
-                                required, space.free
-                            );
-                            tracing::warn!("Skipping move {}: {}", m.id, msg);
-                            state.db.update_move_status(
-                                m.id,
-                                MoveStatus::Skipped,
-                                Some(&msg),
-                            )?;
-                            skipped += 1;
-                            let _ =
-                                state.event_hub.publish(crate::events::Event::MoveComplete {
-                                    move_id: m.id,
-                                    status: "skipped".to_string(),
-                                    verified: false,
-                                    error: Some(msg),
-                                });
-                            continue;
-                        }
+            // Never let rsync clobber a different file that shares the relative
+            // path on the target. Decided before any copy, so the source is intact.
+            let target_full = disk_path(&target_mount, &target_path);
+            if let Some(msg) = crate::executor::target_conflict(&source_meta, &target_full) {
+                match config.on_conflict {
+                    OnConflict::Overwrite => {
+                        tracing::warn!("Overwriting target for move {}: {}", m.id, msg);
                     }
-                    Err(e) => {
-                        let msg = format!("Failed to check target disk space: {e}");
-                        state.db.update_move_status(m.id, MoveStatus::Failed, Some(&msg))?;
+                    OnConflict::Skip => {
+                        tracing::warn!("Skipping move {}: {}", m.id, msg);
+                        skip_move(state, &mut audit, m, &target_path, msg).await?;
+                        skipped += 1;
+                        continue;
+                    }
+                    OnConflict::Fail => {
+                        fail_move(state, &mut audit, m, &target_path, msg).await?;
                         failed += 1;
                         continue;
                     }
                 }
             }
 
-            match crate::executor::is_file_open(&source_full).await {
+            // Pre-move free space recheck: something else may have filled the target
+            // since planning. statvfs is a single syscall, so re-reading it for every
+            // move keeps this exact without measurable cost next to the copy itself.
+            match target_space_shortfall(
+                &target_mount,
+                m.file_size,
+                config.min_free_headroom,
+                config.absolute_min_free,
+            ) {
+                Ok(None) => {}
+                Ok(Some(msg)) => {
+                    tracing::warn!("Skipping move {}: {}", m.id, msg);
+                    skip_move(state, &mut audit, m, &target_path, msg).await?;
+                    skipped += 1;
+                    continue;
+                }
+                Err(e) => {
+                    let msg = format!("Failed to check target disk space: {e}");
+                    fail_move(state, &mut audit, m, &target_path, msg).await?;
+                    failed += 1;
+                    continue;
+                }
+            }
+
+            // The cooldown comes before the open-file check so that check is fresh.
+            if transferred && !move_delay.is_zero() {
+                tokio::select! {
+                    () = cancel.cancelled() => break,
+                    () = tokio::time::sleep(move_delay) => {}
+                }
+            }
+
+            match still_open(&source_full, config.open_file_policy, cancel).await {
+                Ok(true) if cancel.is_cancelled() => break,
                 Ok(true) => {
                     tracing::warn!("File is open, skipping: {}", source_full);
-                    state.db.update_move_status(
-                        m.id,
-                        MoveStatus::Skipped,
-                        Some("File is currently open"),
-                    )?;
+                    let reason = "File is currently open".to_string();
+                    skip_move(state, &mut audit, m, &target_path, reason).await?;
                     skipped += 1;
-                    let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
-                        move_id: m.id,
-                        status: "skipped".to_string(),
-                        verified: false,
-                        error: Some("File is currently open".to_string()),
-                    });
                     continue;
                 }
                 Ok(false) => {} // file not open, proceed
                 Err(e) => {
                     tracing::error!("Cannot verify file safety: {}", e);
-                    state.db.update_move_status(
-                        m.id,
-                        MoveStatus::Failed,
-                        Some(&format!("Cannot verify file safety: {e}")),
-                    )?;
+                    let reason = format!("Cannot verify file safety: {e}");
+                    fail_move(state, &mut audit, m, &target_path, reason).await?;
                     failed += 1;
-                    let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
-                        move_id: m.id,
-                        status: "failed".to_string(),
-                        verified: false,
-                        error: Some(format!("Cannot verify file safety: {e}")),
-                    });
                     continue;
                 }
             }
 
+            // Recorded before the copy starts, so recovery, undo and a resumed run
+            // look for the file where it lands even if the rules change.
+            if m.target_path.is_none() && target_path != m.file_path {
+                state.db.set_move_target_path(m.id, &target_path)?;
+            }
             state.db.update_move_status(m.id, MoveStatus::InProgress, None)?;
+            // Before the move is published as current, so a skip can't hit the previous token.
+            let skip = state.new_move_skip_token().await;
+            update_progress(state, |p| {
+                p.current_move_id = Some(m.id);
+                p.current_file = Some(m.file_path.clone());
+                p.current_size = m.file_size;
+            })
+            .await;
 
             *state.status.write().await = DaemonStatus::executing(format!(
                 "Moving {} ({}/{})",
@@ -304,47 +609,119 @@ async fn process_plan_moves(
             let job = RsyncJob {
                 move_id: m.id,
                 file_path: &m.file_path,
+                target_path: &target_path,
                 source_mount: &source_mount,
                 target_mount: &target_mount,
                 file_size: m.file_size,
-                use_progress2,
+                rsync: &rsync,
+                cleanup_on_cancel: config.cleanup_partial_on_cancel,
+                graceful_cancel: config.cancel_mode == crate::executor::CancelMode::Graceful,
+                verify_size: config.verify_size,
+                verify_xattrs: config.verify_xattrs,
+                move_timeout: config.move_timeout_secs.map(std::time::Duration::from_secs),
                 event_hub: &state.event_hub,
                 cancel,
+                skip: &skip,
                 rsync_child_slot: &state.rsync_child,
+                progress: &state.progress,
             };
 
-            match execute_single_rsync(&job).await {
-                Ok(()) => {
+            transferred = true;
+            match execute_single_rsync(&job)
+                .instrument(tracing::info_span!("move", move_id = m.id))
+                .await
+            {
+                Ok(transfer_time) => {
                     state.db.update_move_status(m.id, MoveStatus::Completed, None)?;
+                    record_for_undo(state, &job, &source_mount, &target_mount);
                     completed += 1;
+                    bytes_moved += m.file_size;
+                    state.metrics.record_move_completed(m.file_size);
+                    audit.record(m, &target_path, MoveStatus::Completed, None).await;
+                    let secs = transfer_time.as_secs_f64();
                     let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
                         move_id: m.id,
                         status: "success".to_string(),
                         verified: true,
                         error: None,
+                        bytes: m.file_size,
+                        avg_speed_bps: if secs > 0.0 { m.file_size as f64 / secs } else { 0.0 },
                     });
                 }
-                Err(_e) if cancel.is_cancelled() => {
+                Err(e) if e.is::<MoveCancelled>() => {
                     state.db.update_move_status(m.id, MoveStatus::Pending, None)?;
                 }
+                Err(e) if e.is::<MoveSkipped>() => {
+                    info!("Move {} skipped by user: {}", m.id, m.file_path);
+                    let reason = "Skipped by user".to_string();
+                    skip_move(state, &mut audit, m, &target_path, reason).await?;
+                    skipped += 1;
+                }
+                Err(e) if e.is::<TargetFull>() => {
+                    tracing::warn!("Move {} ran the target out of space: {:#}", m.id, e);
+                    skip_move(state, &mut audit, m, &target_path, format!("{e:#}")).await?;
+                    skipped += 1;
+                    full_targets.insert(m.target_disk_id);
+                    let stranded: Vec<_> = state
+                        .db
+                        .get_plan_moves(plan_id)?
+                        .into_iter()
+                        .map(|d| d.move_info)
+                        .filter(|p| {
+                            p.status == MoveStatus::Pending && p.target_disk_id == m.target_disk_id
+                        })
+                        .collect();
+                    let rest = state.db.skip_pending_moves_to_disk(
+                        plan_id,
+                        m.target_disk_id,
+                        "Target full",
+                    )?;
+                    skipped += rest as u32;
+                    for _ in 0..rest {
+                        state.metrics.record_move_skipped();
+                    }
+                    for p in &stranded {
+                        let path = config.path_remap.target_for(p);
+                        audit.record(p, &path, MoveStatus::Skipped, Some("Target full")).await;
+                    }
+                    let disk = &move_detail.target_disk_name;
+                    let _ = state.event_hub.publish(crate::events::Event::DaemonError {
+                        message: format!(
+                            "{disk} is full; skipped its {rest} remaining moves in plan {plan_id}"
+                        ),
+                    });
+                }
                 Err(e) => {
                     let msg = format!("{e:#}");
-                    state.db.update_move_status(m.id, MoveStatus::Failed, Some(&msg))?;
+                    tracing::warn!("Move {} failed: {}", m.id, msg);
+                    fail_move(state, &mut audit, m, &target_path, msg).await?;
                     failed += 1;
-                    let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
-                        move_id: m.id,
-                        status: "failed".to_string(),
-                        verified: false,
-                        error: Some(msg.clone()),
-                    });
                 }
             }
         }
     }
 
+    update_progress(state, |p| {
+        p.moves_done = completed + failed + skipped;
+        p.bytes_done = bytes_moved;
+        p.current_move_id = None;
+        p.current_file = None;
+    })
+    .await;
+
     let duration = start.elapsed().as_secs_f64();
-    let status = if cancel.is_cancelled() { PlanStatus::Cancelled } else { PlanStatus::Completed };
-    state.db.update_plan_status(plan_id, status)?;
+    prune_emptied_dirs(state, plan_id, &disk_map).await;
+    let (status, cancel_reason) = if cancel.is_cancelled() {
+        let reason = state.cancel_reason().await;
+        state.db.cancel_plan(plan_id, reason.as_deref())?;
+        (PlanStatus::Cancelled, reason)
+    } else {
+        state.db.update_plan_status(plan_id, PlanStatus::Completed)?;
+        (PlanStatus::Completed, None)
+    };
+    if let Err(e) = state.db.record_execution(plan_id, completed, bytes_moved, duration) {
+        tracing::warn!("Failed to record execution throughput: {}", e);
+    }
 
     let _ = state.event_hub.publish(crate::events::Event::ExecutionComplete {
         plan_id,
@@ -352,17 +729,473 @@ async fn process_plan_moves(
         moves_failed: failed,
         moves_skipped: skipped,
         duration_seconds: duration,
+        cancel_reason: cancel_reason.clone(),
     });
 
+    notify_execution(
+        config.notify_webhook_url.as_deref(),
+        ExecutionReport {
+            plan_id,
+            status,
+            moves_completed: completed,
+            moves_failed: failed,
+            moves_skipped: skipped,
+            duration_seconds: duration,
+            error: None,
+            cancel_reason,
+        },
+    );
+
     Ok(())
 }
 
-async fn execute_single_rsync(job: &RsyncJob<'_>) -> anyhow::Result<()> {
-    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
-    const STDERR_CAP: usize = 64 * 1024;
+/// Apply `f` to the live execution progress, if an execution is tracking one.
+async fn update_progress(state: &AppState, f: impl FnOnce(&mut ExecutionProgress)) {
+    if let Some(progress) = state.progress.write().await.as_mut() {
+        f(progress);
+    }
+}
+
+/// Log a completed move's reverse metadata; failure only costs the undo option.
+fn record_for_undo(state: &AppState, job: &RsyncJob<'_>, source_mount: &str, target_mount: &str) {
+    let target = disk_path(target_mount, job.target_path);
+    let entry = crate::db::ExecutedMove {
+        move_id: job.move_id,
+        source_mount: source_mount.to_string(),
+        target_mount: target_mount.to_string(),
+        file_path: job.file_path.to_string(),
+        target_path: job.target_path.to_string(),
+        file_size: job.file_size,
+        target_mtime: std::fs::symlink_metadata(&target)
+            .ok()
+            .and_then(|meta| crate::executor::mtime_secs(&meta)),
+        executed_at: None,
+        undone_at: None,
+    };
+    if let Err(e) = state.db.record_executed_move(&entry) {
+        tracing::warn!("Failed to record move {} for undo: {}", job.move_id, e);
+    }
+}
+
+/// Reverse one completed move by copying the file back target → source.
+///
+/// Refuses unless the daemon is idle and the file is untouched since the move
+/// (see [`crate::executor::undo_blocker`]). The copy runs in the background
+/// through the same rsync path as plan execution.
+pub(crate) async fn undo_move(
+    State(state): State<Arc<AppState>>,
+    Path(move_id): Path<i64>,
+) -> impl IntoResponse {
+    let entry = match state.db.get_executed_move(move_id) {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            return Json(ApiResponse::<&str>::err_with_code(
+                codes::MOVE_NOT_FOUND,
+                "No completed move with that ID",
+            ));
+        }
+        Err(e) => return Json(ApiResponse::<&str>::err(format!("{e}"))),
+    };
+    if let Some(reason) = crate::executor::undo_blocker(&entry) {
+        return Json(ApiResponse::<&str>::err_with_code(
+            codes::WRONG_STATUS,
+            format!("Cannot undo move: {reason}"),
+        ));
+    }
 
-    let source = format!("{}/{}", job.source_mount, job.file_path);
-    let target = format!("{}/{}", job.target_mount, job.file_path);
+    if let Err(e) = require_rsync(&state).await {
+        return Json(ApiResponse::<&str>::from(e));
+    }
+
+    {
+        let mut status = state.status.write().await;
+        if status.state != DaemonState::Idle {
+            return Json(ApiResponse::<&str>::from(ApiError::busy("undo", status.state)));
+        }
+        *status = DaemonStatus::executing(format!("Undoing move {move_id}"));
+    }
+
+    let token = state.new_operation_token().await;
+    let state_clone = Arc::clone(&state);
+    let handle = tokio::spawn(
+        async move {
+            let result =
+                AssertUnwindSafe(run_undo(&state_clone, &entry, &token)).catch_unwind().await;
+            if result.is_err() {
+                error!("Undo of move {} panicked!", move_id);
+                let _ = state_clone.event_hub.publish(crate::events::Event::DaemonError {
+                    message: format!("Undo panicked for move {move_id}"),
+                });
+            }
+
+            // ALWAYS reset to idle — both normal and panic paths
+            *state_clone.status.write().await = DaemonStatus::idle();
+            *state_clone.background_task.lock().await = None;
+        }
+        .instrument(tracing::info_span!("undo", move_id)),
+    );
+    *state.background_task.lock().await = Some(handle);
+
+    Json(ApiResponse::ok("Undo started"))
+}
+
+/// Copy a completed move's file back to its source disk and announce the result.
+async fn run_undo(state: &AppState, entry: &crate::db::ExecutedMove, cancel: &CancellationToken) {
+    let move_id = entry.move_id;
+    let config = state.config();
+    let rsync = crate::executor::RsyncOptions::from_config(
+        &config,
+        state.rsync_info().await.supports_progress2,
+    );
+    let job = RsyncJob {
+        move_id,
+        file_path: &entry.target_path,
+        target_path: &entry.file_path,
+        source_mount: &entry.target_mount,
+        target_mount: &entry.source_mount,
+        file_size: entry.file_size,
+        rsync: &rsync,
+        cleanup_on_cancel: config.cleanup_partial_on_cancel,
+        graceful_cancel: config.cancel_mode == crate::executor::CancelMode::Graceful,
+        verify_size: config.verify_size,
+        verify_xattrs: config.verify_xattrs,
+        move_timeout: config.move_timeout_secs.map(std::time::Duration::from_secs),
+        event_hub: &state.event_hub,
+        cancel,
+        skip: &CancellationToken::new(),
+        rsync_child_slot: &state.rsync_child,
+        progress: &state.progress,
+    };
+    let outcome = match undo_blocked_by(&job, &config, cancel).await {
+        Some(reason) => Err(reason),
+        None => execute_single_rsync(&job).await.map_err(|e| format!("{e:#}")),
+    };
+    let (status, error) = match outcome {
+        Ok(_) => {
+            info!("Move {} undone: {}", move_id, entry.file_path);
+            if let Err(e) = state.db.mark_move_undone(move_id) {
+                tracing::warn!("Failed to mark move {} undone: {}", move_id, e);
+            }
+            ("undone", None)
+        }
+        Err(e) => {
+            tracing::warn!("Undo of move {} failed: {}", move_id, e);
+            ("failed", Some(format!("Undo failed: {e}")))
+        }
+    };
+    let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
+        move_id,
+        status: status.to_string(),
+        verified: error.is_none(),
+        error,
+        bytes: 0,
+        avg_speed_bps: 0.0,
+    });
+}
+
+/// The per-move checks a forward move gets, applied to an undo's copy back:
+/// distinct devices, room on the original disk, and the file not in use.
+async fn undo_blocked_by(
+    job: &RsyncJob<'_>,
+    config: &crate::config::AppConfig,
+    cancel: &CancellationToken,
+) -> Option<String> {
+    if crate::executor::same_device(job.source_mount, job.target_mount) {
+        return Some(SAME_DEVICE.to_string());
+    }
+    match target_space_shortfall(
+        job.target_mount,
+        job.file_size,
+        config.min_free_headroom,
+        config.absolute_min_free,
+    ) {
+        Ok(None) => {}
+        Ok(Some(msg)) => return Some(msg),
+        Err(e) => return Some(format!("Failed to check target disk space: {e}")),
+    }
+    let path = disk_path(job.source_mount, job.file_path);
+    match still_open(&path, config.open_file_policy, cancel).await {
+        Ok(false) => None,
+        Ok(true) if cancel.is_cancelled() => Some("Undo cancelled".to_string()),
+        Ok(true) => Some("File is currently open".to_string()),
+        Err(e) => Some(format!("Cannot verify file safety: {e}")),
+    }
+}
+
+/// Check whether a source file is open, re-checking per `policy` while it is.
+///
+/// Returns `Ok(true)` if the file is still open when the policy gives up, or
+/// as soon as `cancel` fires during a wait.
+async fn still_open(
+    path: &str,
+    policy: OpenFilePolicy,
+    cancel: &CancellationToken,
+) -> anyhow::Result<bool> {
+    let (retries, delay) = match policy {
+        OpenFilePolicy::Skip => (0, std::time::Duration::ZERO),
+        OpenFilePolicy::Wait { retries, delay_secs } => {
+            (retries, std::time::Duration::from_secs(delay_secs))
+        }
+    };
+    for attempt in 1..=retries {
+        if !crate::executor::is_file_open(path).await? {
+            return Ok(false);
+        }
+        info!(
+            "File is open, re-checking in {}s ({}/{}): {}",
+            delay.as_secs(),
+            attempt,
+            retries,
+            path
+        );
+        tokio::select! {
+            () = cancel.cancelled() => return Ok(true),
+            () = tokio::time::sleep(delay) => {}
+        }
+    }
+    crate::executor::is_file_open(path).await
+}
+
+/// Mark a move skipped, count it, audit it, and tell SSE clients why.
+async fn skip_move(
+    state: &AppState,
+    audit: &mut AuditLog,
+    m: &crate::db::PlannedMove,
+    target_path: &str,
+    reason: String,
+) -> anyhow::Result<()> {
+    state.db.update_move_status(m.id, MoveStatus::Skipped, Some(&reason))?;
+    state.metrics.record_move_skipped();
+    audit.record(m, target_path, MoveStatus::Skipped, Some(&reason)).await;
+    let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
+        move_id: m.id,
+        status: "skipped".to_string(),
+        verified: false,
+        error: Some(reason),
+        bytes: 0,
+        avg_speed_bps: 0.0,
+    });
+    Ok(())
+}
+
+/// Mark a move failed, count it, audit it, and tell SSE clients why.
+async fn fail_move(
+    state: &AppState,
+    audit: &mut AuditLog,
+    m: &crate::db::PlannedMove,
+    target_path: &str,
+    reason: String,
+) -> anyhow::Result<()> {
+    state.db.update_move_status(m.id, MoveStatus::Failed, Some(&reason))?;
+    state.metrics.record_move_failed();
+    audit.record(m, target_path, MoveStatus::Failed, Some(&reason)).await;
+    let _ = state.event_hub.publish(crate::events::Event::MoveComplete {
+        move_id: m.id,
+        status: "failed".to_string(),
+        verified: false,
+        error: Some(reason),
+        bytes: 0,
+        avg_speed_bps: 0.0,
+    });
+    Ok(())
+}
+
+/// Check the target's current free space against a move's needs.
+///
+/// Returns a skip reason when the move would take the target below
+/// `absolute_min_free`, or the file plus `min_free_headroom` no longer fits.
+fn target_space_shortfall(
+    target_mount: &str,
+    file_size: u64,
+    min_free_headroom: u64,
+    absolute_min_free: u64,
+) -> anyhow::Result<Option<String>> {
+    let space = crate::scanner::get_disk_space(target_mount)?;
+    Ok(space_shortfall(space.free, file_size, min_free_headroom, absolute_min_free))
+}
+
+/// Why `free` bytes can't take a `file_size` move, or `None` when it fits.
+fn space_shortfall(
+    free: u64,
+    file_size: u64,
+    min_free_headroom: u64,
+    absolute_min_free: u64,
+) -> Option<String> {
+    if free < file_size.saturating_add(absolute_min_free) {
+        return Some(format!(
+            "Move would leave target below the absolute free-space floor \
+             ({free} bytes free, need {file_size} + {absolute_min_free} bytes floor)"
+        ));
+    }
+    let available = free.saturating_sub(min_free_headroom);
+    if available >= file_size {
+        return None;
+    }
+    Some(format!(
+        "Target full at execution time (need {file_size} bytes, {available} available \
+         after {min_free_headroom} bytes headroom)"
+    ))
+}
+
+/// Dry-run a plan's pending moves against the filesystem as it is now.
+///
+/// Applies the executor's per-move preconditions without touching any move
+/// status: mounted disks, an unchanged source, target conflicts under
+/// `on_conflict`, and free space. Target space is tracked across the pending
+/// moves in order, as each earlier valid move uses or frees room. Open files
+/// and SMART health are left to execution time.
+pub(crate) async fn validate_plan(
+    State(state): State<Arc<AppState>>,
+    Path(plan_id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.get_plan(plan_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Json(ApiResponse::<PlanValidation>::err_with_code(
+                codes::PLAN_NOT_FOUND,
+                "Plan not found",
+            ));
+        }
+        Err(e) => return Json(ApiResponse::<PlanValidation>::err(format!("{e}"))),
+    }
+    let state_clone = Arc::clone(&state);
+    match tokio::task::spawn_blocking(move || check_pending_moves(&state_clone, plan_id)).await {
+        Ok(Ok(report)) => Json(ApiResponse::ok(report)),
+        Ok(Err(e)) => Json(ApiResponse::err(format!("{e}"))),
+        Err(e) => Json(ApiResponse::err(format!("Plan validation task failed: {e}"))),
+    }
+}
+
+/// Blocking body of [`validate_plan`].
+fn check_pending_moves(state: &AppState, plan_id: i64) -> anyhow::Result<PlanValidation> {
+    let config = state.config();
+    let disks = state.db.get_all_disks()?;
+    let table = crate::scanner::parse_mount_table();
+    let mounts: std::collections::HashMap<i64, &str> =
+        disks.iter().map(|d| (d.id, d.mount_path.as_str())).collect();
+    let unusable: std::collections::HashMap<i64, String> = disks
+        .iter()
+        .filter_map(|d| Some((d.id, crate::scanner::mount_problem(&d.mount_path, &table, false)?)))
+        .collect();
+    let mut free: std::collections::HashMap<i64, u64> = std::collections::HashMap::new();
+
+    let pending: Vec<crate::db::PlannedMove> = state
+        .db
+        .get_plan_moves(plan_id)?
+        .into_iter()
+        .map(|d| d.move_info)
+        .filter(|m| m.status == MoveStatus::Pending)
+        .collect();
+    let mut report = PlanValidation {
+        plan_id,
+        pending_moves: pending.len(),
+        valid_moves: 0,
+        valid_bytes: 0,
+        invalid: Vec::new(),
+    };
+    for m in &pending {
+        let invalid = |outcome, reason: String| InvalidMove {
+            move_id: m.id,
+            file_path: m.file_path.clone(),
+            outcome,
+            reason,
+        };
+        let (Some(source_mount), Some(target_mount)) =
+            (mounts.get(&m.source_disk_id), mounts.get(&m.target_disk_id))
+        else {
+            report
+                .invalid
+                .push(invalid(MoveStatus::Failed, "Unknown source or target disk".into()));
+            continue;
+        };
+        if let Some(problem) =
+            unusable.get(&m.source_disk_id).or_else(|| unusable.get(&m.target_disk_id))
+        {
+            report.invalid.push(invalid(MoveStatus::Skipped, format!("Disk unusable: {problem}")));
+            continue;
+        }
+        if crate::executor::same_device(source_mount, target_mount) {
+            report.invalid.push(invalid(MoveStatus::Skipped, SAME_DEVICE.into()));
+            continue;
+        }
+        let source_meta = match std::fs::symlink_metadata(disk_path(source_mount, &m.file_path)) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                report.invalid.push(invalid(MoveStatus::Skipped, "Source file not found".into()));
+                continue;
+            }
+            Err(e) => {
+                let reason = format!("Failed to stat source file: {e}");
+                report.invalid.push(invalid(MoveStatus::Failed, reason));
+                continue;
+            }
+        };
+        if source_meta.len() != m.file_size {
+            let reason = format!(
+                "File size changed since planning (expected {}, now {})",
+                m.file_size,
+                source_meta.len()
+            );
+            report.invalid.push(invalid(MoveStatus::Skipped, reason));
+            continue;
+        }
+        let target_full = disk_path(target_mount, &config.path_remap.target_for(m));
+        if let Some(msg) = crate::executor::target_conflict(&source_meta, &target_full) {
+            match config.on_conflict {
+                OnConflict::Overwrite => {}
+                OnConflict::Skip => {
+                    report.invalid.push(invalid(MoveStatus::Skipped, msg));
+                    continue;
+                }
+                OnConflict::Fail => {
+                    report.invalid.push(invalid(MoveStatus::Failed, msg));
+                    continue;
+                }
+            }
+        }
+        let target_free = match free.entry(m.target_disk_id) {
+            std::collections::hash_map::Entry::Occupied(e) => *e.get(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                match crate::scanner::get_disk_space(target_mount) {
+                    Ok(space) => *e.insert(space.free),
+                    Err(err) => {
+                        let reason = format!("Failed to check target disk space: {err}");
+                        report.invalid.push(invalid(MoveStatus::Failed, reason));
+                        continue;
+                    }
+                }
+            }
+        };
+        if let Some(msg) = space_shortfall(
+            target_free,
+            m.file_size,
+            config.min_free_headroom,
+            config.absolute_min_free,
+        ) {
+            report.invalid.push(invalid(MoveStatus::Skipped, msg));
+            continue;
+        }
+        free.insert(m.target_disk_id, target_free - m.file_size);
+        if let Some(source_free) = free.get_mut(&m.source_disk_id) {
+            *source_free = source_free.saturating_add(m.file_size);
+        }
+        report.valid_moves += 1;
+        report.valid_bytes += m.file_size;
+    }
+    Ok(report)
+}
+
+/// Copy one file with rsync, then verify and remove the source.
+///
+/// rsync writes to the target's [staging path](crate::executor::staging_path);
+/// the copy only appears at the real target once it has been verified, so an
+/// interrupted move never leaves a partial file there. Returns how long the transfer itself took.
+async fn execute_single_rsync(job: &RsyncJob<'_>) -> anyhow::Result<std::time::Duration> {
+    use tokio::io::AsyncBufReadExt;
+
+    let source = disk_path(job.source_mount, job.file_path);
+    let target = disk_path(job.target_mount, job.target_path);
+    let staged = crate::executor::staging_path(&target);
 
     crate::scanner::validation::validate_path(&source)?;
     crate::scanner::validation::validate_path(&target)?;
@@ -371,21 +1204,14 @@ async fn execute_single_rsync(job: &RsyncJob<'_>) -> anyhow::Result<()> {
         tokio::fs::create_dir_all(parent).await?;
     }
 
-    // Record source mtime before rsync starts (for post-copy verification)
-    let pre_rsync_mtime = tokio::fs::metadata(&source)
-        .await?
-        .modified()?;
+    // Record source size and mtime before rsync starts (for post-copy verification)
+    let pre_rsync = tokio::fs::symlink_metadata(&source).await?;
 
-    // Two-phase move: copy only (no --remove-source-files)
-    let mut args = vec!["-avPX"];
-    if job.use_progress2 {
-        args.push("--info=progress2");
-    }
-    args.push(&source);
-    args.push(&target);
+    let command = crate::executor::rsync_command(job.rsync, &source, &staged);
 
-    let mut rsync_proc = tokio::process::Command::new("rsync")
-        .args(&args)
+    let started = std::time::Instant::now();
+    let mut rsync_proc = tokio::process::Command::new(&command[0])
+        .args(&command[1..])
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()?;
@@ -396,45 +1222,69 @@ async fn execute_single_rsync(job: &RsyncJob<'_>) -> anyhow::Result<()> {
     // Store child in the shared slot so shutdown can kill it
     *job.rsync_child_slot.lock().await = Some(rsync_proc);
 
-    // Drain stderr in background to prevent pipe buffer deadlock.
+    // Drain stderr in background to prevent pipe buffer deadlock, keeping the
+    // tail that explains a failure. Split on bytes so a non-UTF-8 file name in
+    // a message can't stop the draining.
     let stderr_task = tokio::spawn(async move {
-        if let Some(mut stderr) = stderr {
-            let mut buf = String::new();
-            match stderr.read_to_string(&mut buf).await {
-                Ok(n) if n > STDERR_CAP => buf.truncate(STDERR_CAP),
-                _ => {}
+        let mut tail = crate::executor::StderrTail::default();
+        if let Some(stderr) = stderr {
+            let mut lines = tokio::io::BufReader::new(stderr).split(b'\n');
+            while let Ok(Some(line)) = lines.next_segment().await {
+                tail.push(&String::from_utf8_lossy(&line));
             }
-            buf
-        } else {
-            String::new()
         }
+        tail
     });
 
     if let Some(stdout) = stdout {
-        let reader = tokio::io::BufReader::new(stdout);
-        let mut lines = reader.lines();
+        // rsync redraws progress with '\r', so split on it to see every update
+        // (and reset the idle timeout on each) rather than only completed lines.
+        let mut segments = tokio::io::BufReader::new(stdout).split(b'\r');
+        let mut throttle = crate::executor::ProgressThrottle::default();
 
-        while let Ok(Some(line)) = lines.next_line().await {
-            if job.cancel.is_cancelled() {
+        loop {
+            let read = tokio::select! {
+                () = job.skip.cancelled() => {
+                    stderr_task.abort();
+                    let child = job.rsync_child_slot.lock().await.take();
+                    return Err(reap_skipped_rsync(child, &staged).await);
+                }
+                read = next_segment_within(&mut segments, job.move_timeout) => read,
+            };
+            let Ok(next) = read else {
+                stderr_task.abort();
+                let child = job.rsync_child_slot.lock().await.take();
+                let limit = job.move_timeout.unwrap_or_default();
+                return Err(reap_stalled_rsync(child, &staged, limit).await);
+            };
+            let Ok(Some(segment)) = next else {
+                break;
+            };
+            let line = String::from_utf8_lossy(&segment);
+            if job.cancel.is_cancelled() && !job.graceful_cancel {
                 let child = job.rsync_child_slot.lock().await.take();
                 if let Some(mut child) = child {
                     child.kill().await.ok();
                     child.wait().await.ok();
                 }
                 stderr_task.abort();
-                cleanup_target(&target).await;
-                anyhow::bail!("rsync cancelled during execution");
+                cleanup_cancelled_target(job, &source, &staged).await;
+                return Err(MoveCancelled("rsync cancelled during execution").into());
             }
-            if let Some(caps) = PROGRESS_RE.captures(&line) {
-                let pct: f64 = caps[1].parse().unwrap_or(0.0);
-                let speed = caps.get(2).map(|m| m.as_str().to_string()).unwrap_or_default();
-                let eta = caps.get(3).map(|m| m.as_str().to_string()).unwrap_or_default();
+            if let Some(progress) = crate::executor::parse_rsync_progress(&line)
+                .filter(|p| throttle.should_publish(p.percent, std::time::Instant::now()))
+            {
+                if let Some(live) = job.progress.write().await.as_mut() {
+                    if live.current_move_id == Some(job.move_id) {
+                        live.current_percent = progress.percent;
+                    }
+                }
                 let _ = job.event_hub.publish(crate::events::Event::MoveProgress {
                     move_id: job.move_id,
                     file_path: job.file_path.to_string(),
-                    percent: pct,
-                    speed,
-                    eta,
+                    percent: progress.percent,
+                    speed: progress.speed,
+                    eta: progress.eta,
                 });
             }
         }
@@ -442,94 +1292,139 @@ async fn execute_single_rsync(job: &RsyncJob<'_>) -> anyhow::Result<()> {
 
     // Cancel check after stdout loop exits: if shutdown killed rsync while we were
     // reading the final bytes, handle it here instead of falling into the wrong branch.
-    if job.cancel.is_cancelled() {
+    // A graceful cancel only lands here when shutdown took the child.
+    if job.cancel.is_cancelled()
+        && (!job.graceful_cancel || job.rsync_child_slot.lock().await.is_none())
+    {
         let child = job.rsync_child_slot.lock().await.take();
         if let Some(mut child) = child {
             child.kill().await.ok();
             child.wait().await.ok();
         }
         stderr_task.abort();
-        cleanup_target(&target).await;
-        anyhow::bail!("rsync cancelled during execution");
+        cleanup_cancelled_target(job, &source, &staged).await;
+        return Err(MoveCancelled("rsync cancelled during execution").into());
+    }
+    if job.skip.is_cancelled() {
+        stderr_task.abort();
+        let child = job.rsync_child_slot.lock().await.take();
+        return Err(reap_skipped_rsync(child, &staged).await);
     }
 
     // Take child back from slot and wait for it
     let child = job.rsync_child_slot.lock().await.take();
     let stderr_output = stderr_task.await.unwrap_or_default();
     if let Some(mut child) = child {
-        let exit = child.wait().await?;
+        let exit = match job.move_timeout {
+            Some(limit) => match tokio::time::timeout(limit, child.wait()).await {
+                Ok(exit) => exit?,
+                Err(_) => return Err(reap_stalled_rsync(Some(child), &staged, limit).await),
+            },
+            None => child.wait().await?,
+        };
         if exit.success() {
+            let transfer_time = started.elapsed();
             // Cancel guard: if cancellation arrived between rsync completing and now,
-            // clean up target instead of proceeding to delete the source.
-            if job.cancel.is_cancelled() {
-                cleanup_target(&target).await;
-                anyhow::bail!("cancelled after rsync completed");
+            // clean up target instead of proceeding to delete the source. A graceful
+            // cancel wants this file finished, so it goes on to verification.
+            if job.cancel.is_cancelled() && !job.graceful_cancel {
+                cleanup_cancelled_target(job, &source, &staged).await;
+                return Err(MoveCancelled("cancelled after rsync completed").into());
             }
             // Phase 2: Verify copy and remove source
-            verify_and_remove_source(&source, &target, job.file_size, pre_rsync_mtime).await
+            let checks = crate::executor::CopyChecks {
+                expected_size: job.file_size,
+                verify_size: job.verify_size,
+                verify_xattrs: job.verify_xattrs,
+                preserve_acls: job.rsync.preserve_acls,
+            };
+            crate::executor::verify_and_remove_source(&source, &target, &pre_rsync, &checks)
+                .await?;
+            Ok(transfer_time)
         } else {
             let code = exit.code().unwrap_or(-1);
-            let stderr_summary = if stderr_output.is_empty() {
-                String::new()
-            } else {
-                format!(": {}", stderr_output.lines().last().unwrap_or(""))
-            };
-            cleanup_target(&target).await;
+            let stderr_output = stderr_output.summary().unwrap_or_default();
+            let stderr_summary =
+                if stderr_output.is_empty() { String::new() } else { format!(": {stderr_output}") };
+            // Measured before the partial is removed, while it still holds the space.
+            let target_free = crate::scanner::get_disk_space(job.target_mount).ok().map(|s| s.free);
+            cleanup_target(&staged).await;
+            if crate::executor::rsync_disk_full(code, &stderr_output, target_free, job.file_size) {
+                return Err(
+                    TargetFull(format!("rsync exited with code {code}{stderr_summary}")).into()
+                );
+            }
             anyhow::bail!("rsync exited with code {code}{stderr_summary}")
         }
     } else {
-        cleanup_target(&target).await;
+        cleanup_target(&staged).await;
         anyhow::bail!("rsync process was killed during shutdown")
     }
 }
 
-/// Verify the target copy is correct, then remove the source.
-///
-/// Safety invariant: the source file is NEVER deleted unless:
-/// 1. The target exists and matches the expected size
-/// 2. The source mtime hasn't changed since rsync started (no concurrent modification)
-async fn verify_and_remove_source(
-    source: &str,
-    target: &str,
-    expected_size: u64,
-    pre_rsync_mtime: std::time::SystemTime,
-) -> anyhow::Result<()> {
-    // Verify target exists and size matches
-    let target_meta = tokio::fs::metadata(target).await.map_err(|e| {
-        anyhow::anyhow!("Post-copy verification failed: target file missing or unreadable: {e}")
-    })?;
-    let target_size = target_meta.len();
-    if target_size != expected_size {
-        anyhow::bail!(
-            "Post-copy verification failed: target size {target_size} != expected {expected_size} \
-             (both copies preserved)"
-        );
+/// Read rsync's next output segment; `Err` when `limit` passes without one.
+async fn next_segment_within<R>(
+    segments: &mut tokio::io::Split<R>,
+    limit: Option<std::time::Duration>,
+) -> Result<std::io::Result<Option<Vec<u8>>>, tokio::time::error::Elapsed>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, segments.next_segment()).await,
+        None => Ok(segments.next_segment().await),
     }
+}
 
-    // Verify source hasn't been modified during the transfer
-    let source_meta = tokio::fs::metadata(source).await.map_err(|e| {
-        anyhow::anyhow!("Post-copy verification failed: cannot stat source: {e}")
-    })?;
-    let current_mtime = source_meta.modified()?;
-    if current_mtime != pre_rsync_mtime {
-        anyhow::bail!(
-            "Post-copy verification failed: source was modified during transfer \
-             (both copies preserved)"
-        );
-    }
+/// Error for a move stopped by a plan cancel; it goes back to pending for a
+/// later run. Any other error during a graceful cancel still settles the move.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct MoveCancelled(&'static str);
 
-    // All checks passed — safe to delete source
-    tokio::fs::remove_file(source).await.map_err(|e| {
-        anyhow::anyhow!(
-            "Copy verified but failed to remove source (both copies exist, manual cleanup needed): {e}"
-        )
-    })?;
+/// Error for a move abandoned via skip-current; the plan carries on.
+#[derive(Debug, thiserror::Error)]
+#[error("move skipped by user")]
+struct MoveSkipped;
 
-    Ok(())
+/// Error for a copy that ran the target out of space; its partial is already gone.
+#[derive(Debug, thiserror::Error)]
+#[error("Target full: {0}")]
+struct TargetFull(String);
+
+/// Kill an rsync whose move was skipped and remove its partial target.
+///
+/// Unlike cancel, a skipped move won't be retried, so nothing is kept for a
+/// resume. The source is untouched at this point.
+async fn reap_skipped_rsync(child: Option<tokio::process::Child>, target: &str) -> anyhow::Error {
+    if let Some(mut child) = child {
+        child.kill().await.ok();
+        child.wait().await.ok();
+    }
+    cleanup_target(target).await;
+    MoveSkipped.into()
 }
 
-/// Best-effort cleanup of a target file and any empty parent directories.
-/// Used after rsync failure, cancellation, or shutdown kill.
+/// Kill an rsync that stopped making progress and remove its partial target.
+///
+/// The source is untouched at this point. Returns the error the move fails with.
+async fn reap_stalled_rsync(
+    child: Option<tokio::process::Child>,
+    target: &str,
+    limit: std::time::Duration,
+) -> anyhow::Error {
+    if let Some(mut child) = child {
+        child.kill().await.ok();
+        child.wait().await.ok();
+    }
+    cleanup_target(target).await;
+    tracing::warn!("rsync stalled for {}s, killed: {}", limit.as_secs(), target);
+    anyhow::anyhow!("rsync timed out after {}s without progress", limit.as_secs())
+}
+
+/// Best-effort cleanup of a staged target file, its partial-dir fragment, and
+/// any empty parent directories. Used after rsync failure, cancellation, or
+/// shutdown kill; the real target path is never touched.
 async fn cleanup_target(target: &str) {
     let path = std::path::Path::new(target);
     if path.exists() {
@@ -539,19 +1434,117 @@ async fn cleanup_target(target: &str) {
             tracing::info!("Cleaned up partial target: {target}");
         }
     }
+    if let Some(fragment) = crate::executor::partial_fragment_path(target) {
+        if fragment.exists() {
+            if let Err(e) = tokio::fs::remove_file(&fragment).await {
+                tracing::warn!("Failed to clean up partial fragment {}: {e}", fragment.display());
+            } else if let Some(dir) = fragment.parent() {
+                let _ = tokio::fs::remove_dir(dir).await;
+            }
+        }
+    }
     crate::executor::recovery::cleanup_empty_parents(target).await;
 }
 
+/// Handle the target left behind by a cancelled transfer.
+///
+/// With `resume_partial` the fragment in the partial dir is always kept so the
+/// next execution continues where this one stopped. Otherwise, with
+/// `cleanup_on_cancel` the partial target is removed right away, but only
+/// while the source still exists — the target may otherwise be the sole copy.
+/// Without either, the partial is kept so rsync's `--partial` can resume next run.
+async fn cleanup_cancelled_target(job: &RsyncJob<'_>, source: &str, target: &str) {
+    if job.rsync.resume_partial {
+        info!("Keeping resumable partial transfer after cancel: {target}");
+        return;
+    }
+    if !job.cleanup_on_cancel {
+        info!("Keeping partial target after cancel: {target}");
+        return;
+    }
+    if !std::path::Path::new(source).exists() {
+        tracing::warn!("Source missing after cancel, keeping target: {target}");
+        return;
+    }
+    cleanup_target(target).await;
+}
+
+/// Abandon the move being transferred and carry on with the rest of the plan.
+///
+/// The move is marked skipped and its partial target removed; the source stays.
+pub(crate) async fn skip_current_move(
+    State(state): State<Arc<AppState>>,
+    Path(plan_id): Path<i64>,
+) -> impl IntoResponse {
+    let current = state
+        .progress
+        .read()
+        .await
+        .as_ref()
+        .filter(|p| p.plan_id == plan_id)
+        .map(|p| p.current_move_id);
+    match current {
+        None => Json(ApiResponse::<String>::err_with_code(
+            codes::NOTHING_RUNNING,
+            "Plan is not executing",
+        )),
+        Some(None) => Json(ApiResponse::<String>::err_with_code(
+            codes::NOTHING_RUNNING,
+            "No move is being transferred right now",
+        )),
+        Some(Some(move_id)) => {
+            state.request_skip().await;
+            info!("Skip requested for move {} of plan {}", move_id, plan_id);
+            Json(ApiResponse::ok(format!("Skipping move {move_id}")))
+        }
+    }
+}
+
 pub(crate) async fn cancel_operation(
     State(state): State<Arc<AppState>>,
     Path(plan_id): Path<i64>,
+    req: Option<Json<CancelRequest>>,
 ) -> impl IntoResponse {
-    let status = state.status.read().await;
+    match cancel_current_operation(&state, req.and_then(|Json(r)| r.reason), None).await {
+        Ok(reason) => {
+            info!("Cancellation requested for plan {}: {}", plan_id, reason);
+            Json(ApiResponse::ok("Cancellation requested"))
+        }
+        Err(e) => Json(ApiResponse::<&str>::from(e)),
+    }
+}
+
+/// Cancel whatever scan or execution is running, on behalf of the user.
+///
+/// With `only`, nothing is cancelled unless the daemon is in that state.
+/// Returns the recorded reason, or an error when the daemon is idle.
+pub(super) async fn cancel_current_operation(
+    state: &AppState,
+    reason: Option<String>,
+    only: Option<DaemonState>,
+) -> Result<String, ApiError> {
+    let mut status = state.status.write().await;
     if status.state == DaemonState::Idle {
-        return Json(ApiResponse::<&str>::err("No operation in progress"));
+        return Err(ApiError::new(codes::NOTHING_RUNNING, "No operation in progress"));
+    }
+    if let Some(expected) = only.filter(|&s| s != status.state) {
+        return Err(ApiError::new(
+            codes::WRONG_STATUS,
+            format!("Not {}; the daemon is {}", expected.as_str(), status.state.as_str()),
+        ));
     }
+    let reason = match reason.filter(|r| !r.trim().is_empty()) {
+        Some(r) => format!("cancelled by user: {}", r.trim()),
+        None => "cancelled by user".to_string(),
+    };
+    status.detail = if status.state == DaemonState::Executing
+        && state.config().cancel_mode == crate::executor::CancelMode::Graceful
+    {
+        Some(format!("Cancelling after the current file ({reason})"))
+    } else {
+        Some(format!("Cancelling ({reason})"))
+    };
     drop(status);
-    state.request_cancel().await;
-    info!("Cancellation requested for plan {}", plan_id);
-    Json(ApiResponse::ok("Cancellation requested"))
+    state.request_cancel(reason.as_str()).await;
+    Ok(reason)
 }
@@ -0,0 +1,139 @@
+use crate::api::responses::{codes, ApiResponse, ExportFormat, ExportQuery};
+use crate::db::FileEntry;
+use crate::AppState;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Catalog rows fetched per query while streaming an export.
+const EXPORT_PAGE_SIZE: usize = 5000;
+
+/// Download every cataloged file on a disk as CSV or a JSON array.
+///
+/// The body is streamed page by page, so large disks are never buffered in
+/// memory. Paths are disk-relative, as stored in the catalog.
+pub(crate) async fn export_disk_files(
+    State(state): State<Arc<AppState>>,
+    Path(disk_id): Path<i64>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let disk = match state.db.get_disk(disk_id) {
+        Ok(Some(d)) => d,
+        Ok(None) => {
+            return Json(ApiResponse::<&str>::err_with_code(
+                codes::DISK_NOT_FOUND,
+                "Disk not found",
+            ))
+            .into_response();
+        }
+        Err(e) => return Json(ApiResponse::<&str>::err(format!("{e}"))).into_response(),
+    };
+
+    let format = query.format;
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Json => ("application/json", "json"),
+    };
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(stream_export(state, disk_id, format, tx));
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}-catalog.{extension}\"", disk.disk_name),
+            ),
+        ],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+/// Feed an export body page by page, stopping early if the client goes away.
+///
+/// A catalog error mid-stream aborts the body, so the client sees a truncated
+/// download rather than a file that looks complete.
+async fn stream_export(
+    state: Arc<AppState>,
+    disk_id: i64,
+    format: ExportFormat,
+    tx: mpsc::Sender<Result<String, std::io::Error>>,
+) {
+    let opening = match format {
+        ExportFormat::Csv => "path,size_bytes,mtime\n",
+        ExportFormat::Json => "[",
+    };
+    if tx.send(Ok(opening.to_string())).await.is_err() {
+        return;
+    }
+
+    let mut after_id = 0;
+    let mut first = true;
+    loop {
+        let page = match state.db.get_files_page(disk_id, after_id, EXPORT_PAGE_SIZE) {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::warn!("Catalog export of disk {} failed: {}", disk_id, e);
+                let _ = tx.send(Err(std::io::Error::other(format!("{e}")))).await;
+                return;
+            }
+        };
+        let Some(last) = page.last() else {
+            break;
+        };
+        after_id = last.id;
+
+        let mut chunk = String::new();
+        for file in &page {
+            match format {
+                ExportFormat::Csv => push_csv_row(&mut chunk, file),
+                ExportFormat::Json => {
+                    if !first {
+                        chunk.push(',');
+                    }
+                    push_json_row(&mut chunk, file);
+                }
+            }
+            first = false;
+        }
+        if tx.send(Ok(chunk)).await.is_err() {
+            return;
+        }
+    }
+
+    if matches!(format, ExportFormat::Json) {
+        let _ = tx.send(Ok("]\n".to_string())).await;
+    }
+}
+
+fn push_csv_row(out: &mut String, file: &FileEntry) {
+    let mtime = file.mtime.map_or_else(String::new, |m| m.to_string());
+    let _ = writeln!(out, "{},{},{mtime}", csv_field(&file.file_path), file.size_bytes);
+}
+
+fn push_json_row(out: &mut String, file: &FileEntry) {
+    let row = serde_json::json!({
+        "path": file.file_path,
+        "size_bytes": file.size_bytes,
+        "mtime": file.mtime,
+    });
+    let _ = write!(out, "{row}");
+}
+
+/// Quote a CSV field per RFC 4180 when it holds a comma, quote or line break.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
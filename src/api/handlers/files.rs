@@ -0,0 +1,192 @@
+use crate::api::responses::{
+    codes, ApiResponse, BrowseQuery, DuplicatesQuery, FolderListing, LargestFilesQuery,
+};
+use crate::db::{DuplicateGroup, FileEntry};
+use crate::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Json,
+};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+/// Rows returned when no `limit` is given.
+const DEFAULT_LARGEST_LIMIT: usize = 100;
+/// Upper bound on `limit` so a typo can't pull the whole catalog.
+const MAX_LARGEST_LIMIT: usize = 1000;
+
+/// Folder entries per page when no `limit` is given.
+const DEFAULT_BROWSE_LIMIT: usize = 200;
+/// Upper bound on a browse page.
+const MAX_BROWSE_LIMIT: usize = 2000;
+
+/// Duplicates smaller than this are ignored unless `min_size` says otherwise,
+/// so cover art and NFO files don't flood the report.
+const DEFAULT_DUPLICATE_MIN_SIZE: u64 = 1024 * 1024;
+/// Duplicate groups returned when no `limit` is given.
+const DEFAULT_DUPLICATE_LIMIT: usize = 100;
+/// Bytes read from each end of a file for its partial hash.
+const PARTIAL_HASH_BYTES: usize = 64 * 1024;
+
+fn effective_limit(query: &LargestFilesQuery) -> usize {
+    query.limit.unwrap_or(DEFAULT_LARGEST_LIMIT).clamp(1, MAX_LARGEST_LIMIT)
+}
+
+/// Normalize a requested folder to the catalog's disk-relative form.
+///
+/// Slashes at either end are dropped and "." means the root. Empty, "." and ".."
+/// components are rejected so the path can't wander outside the disk, and the
+/// full path must pass the usual FUSE/array path checks.
+fn normalize_browse_path(mount_path: &str, raw: Option<&str>) -> Result<String, String> {
+    let trimmed = raw.unwrap_or("").trim().trim_matches('/');
+    if trimmed.is_empty() || trimmed == "." {
+        return Ok(String::new());
+    }
+    if trimmed.split('/').any(|c| c.is_empty() || c == "." || c == "..") {
+        return Err(format!("Invalid folder path: {trimmed}"));
+    }
+    crate::scanner::validation::validate_path(&format!("{mount_path}/{trimmed}"))
+        .map_err(|e| format!("{e}"))?;
+    Ok(trimmed.to_string())
+}
+
+pub(crate) async fn get_largest_files(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LargestFilesQuery>,
+) -> impl IntoResponse {
+    match state.db.get_largest_files(effective_limit(&query)) {
+        Ok(files) => Json(ApiResponse::ok(files)),
+        Err(e) => {
+            Json(ApiResponse::<Vec<FileEntry>>::err(format!("Failed to get largest files: {e}")))
+        }
+    }
+}
+
+pub(crate) async fn get_largest_files_on_disk(
+    State(state): State<Arc<AppState>>,
+    Path(disk_id): Path<i64>,
+    Query(query): Query<LargestFilesQuery>,
+) -> impl IntoResponse {
+    match state.db.get_largest_files_on_disk(disk_id, effective_limit(&query)) {
+        Ok(files) => Json(ApiResponse::ok(files)),
+        Err(e) => {
+            Json(ApiResponse::<Vec<FileEntry>>::err(format!("Failed to get largest files: {e}")))
+        }
+    }
+}
+
+pub(crate) async fn browse_disk_files(
+    State(state): State<Arc<AppState>>,
+    Path(disk_id): Path<i64>,
+    Query(query): Query<BrowseQuery>,
+) -> impl IntoResponse {
+    let disk = match state.db.get_disk(disk_id) {
+        Ok(Some(d)) => d,
+        Ok(None) => {
+            return Json(ApiResponse::<FolderListing>::err_with_code(
+                codes::DISK_NOT_FOUND,
+                "Disk not found",
+            ));
+        }
+        Err(e) => return Json(ApiResponse::<FolderListing>::err(format!("{e}"))),
+    };
+    let path = match normalize_browse_path(&disk.mount_path, query.path.as_deref()) {
+        Ok(p) => p,
+        Err(e) => return Json(ApiResponse::<FolderListing>::err(e)),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_BROWSE_LIMIT).clamp(1, MAX_BROWSE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    match state.db.list_folder(disk_id, &path, limit, offset) {
+        Ok((entries, total_entries)) => {
+            Json(ApiResponse::ok(FolderListing { path, entries, total_entries, limit, offset }))
+        }
+        Err(e) => Json(ApiResponse::<FolderListing>::err(format!("Failed to list folder: {e}"))),
+    }
+}
+
+/// Report files with the same name and size on more than one disk.
+///
+/// Read-only. With `hash=true` the first and last 64 KiB of each copy are
+/// hashed so groups whose contents differ can be told apart.
+pub(crate) async fn get_duplicates(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DuplicatesQuery>,
+) -> impl IntoResponse {
+    let min_size = query.min_size.unwrap_or(DEFAULT_DUPLICATE_MIN_SIZE);
+    let limit = query.limit.unwrap_or(DEFAULT_DUPLICATE_LIMIT).clamp(1, MAX_LARGEST_LIMIT);
+    let groups = match state.db.find_duplicates(min_size, limit) {
+        Ok(g) => g,
+        Err(e) => {
+            return Json(ApiResponse::<Vec<DuplicateGroup>>::err(format!(
+                "Failed to find duplicates: {e}"
+            )));
+        }
+    };
+    if !query.hash {
+        return Json(ApiResponse::ok(groups));
+    }
+
+    let mounts: HashMap<i64, String> = match state.db.get_all_disks() {
+        Ok(disks) => disks.into_iter().map(|d| (d.id, d.mount_path)).collect(),
+        Err(e) => {
+            return Json(ApiResponse::<Vec<DuplicateGroup>>::err(format!(
+                "Failed to get disks: {e}"
+            )));
+        }
+    };
+    match tokio::task::spawn_blocking(move || hash_duplicates(groups, &mounts)).await {
+        Ok(hashed) => Json(ApiResponse::ok(hashed)),
+        Err(e) => Json(ApiResponse::<Vec<DuplicateGroup>>::err(format!("Hashing failed: {e}"))),
+    }
+}
+
+/// Fill in partial hashes and whether each group's copies agree.
+///
+/// A copy that can't be read keeps `partial_hash: None`, which counts as a mismatch.
+fn hash_duplicates(
+    mut groups: Vec<DuplicateGroup>,
+    mounts: &HashMap<i64, String>,
+) -> Vec<DuplicateGroup> {
+    for group in &mut groups {
+        for location in &mut group.locations {
+            let Some(mount) = mounts.get(&location.disk_id) else {
+                continue;
+            };
+            let path = crate::executor::disk_path(mount, &location.file_path);
+            location.partial_hash = partial_hash(&path)
+                .inspect_err(|e| tracing::warn!("Cannot hash {}: {}", path, e))
+                .ok();
+        }
+        let first = group.locations.first().and_then(|l| l.partial_hash.as_ref());
+        group.hashes_match = Some(
+            first.is_some() && group.locations.iter().all(|l| l.partial_hash.as_ref() == first),
+        );
+    }
+    groups
+}
+
+/// Hash a file's length plus its first and last [`PARTIAL_HASH_BYTES`].
+///
+/// Cheap enough to run on large media files; a match is strong evidence, not proof.
+fn partial_hash(path: &str) -> std::io::Result<String> {
+    use std::hash::{Hash, Hasher};
+
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    len.hash(&mut hasher);
+
+    let mut head = Vec::with_capacity(PARTIAL_HASH_BYTES);
+    file.by_ref().take(PARTIAL_HASH_BYTES as u64).read_to_end(&mut head)?;
+    head.hash(&mut hasher);
+    if len > PARTIAL_HASH_BYTES as u64 {
+        let mut tail = vec![0u8; PARTIAL_HASH_BYTES];
+        file.seek(SeekFrom::End(-(PARTIAL_HASH_BYTES as i64)))?;
+        file.read_exact(&mut tail)?;
+        tail.hash(&mut hasher);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
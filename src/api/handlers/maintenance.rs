@@ -0,0 +1,39 @@
+use crate::api::responses::{ApiError, ApiResponse, VacuumResponse};
+use crate::executor::recovery::{cleanup_partial_files, CleanupStats};
+use crate::{AppState, DaemonState};
+use axum::{extract::State, response::IntoResponse, Json};
+use std::sync::Arc;
+
+/// VACUUM the catalog. Holds the status lock throughout so no scan, plan or
+/// execution can start while the database is being rebuilt.
+pub(crate) async fn vacuum_database(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let status = state.status.write().await;
+    if status.state != DaemonState::Idle {
+        return Json(ApiResponse::<VacuumResponse>::from(ApiError::busy("vacuum", status.state)));
+    }
+    match state.db.vacuum() {
+        Ok((size_before, size_after)) => {
+            Json(ApiResponse::ok(VacuumResponse { size_before, size_after }))
+        }
+        Err(e) => Json(ApiResponse::<VacuumResponse>::err(format!("Vacuum failed: {e}"))),
+    }
+}
+
+/// Re-run crash recovery without restarting: fail plans stuck at `executing`,
+/// reset their in-progress moves and settle each against the filesystem.
+/// Holds the status lock throughout, like vacuum, so nothing starts meanwhile.
+pub(crate) async fn recover_partial_files(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let status = state.status.write().await;
+    if status.state != DaemonState::Idle {
+        return Json(ApiResponse::<CleanupStats>::from(ApiError::busy("recover", status.state)));
+    }
+    let result = match state.db.recover_stale_states() {
+        Ok(recovery) => cleanup_partial_files(&state.db, &recovery.recovered_move_ids).await,
+        Err(e) => Err(e),
+    };
+    drop(status);
+    match result {
+        Ok(stats) => Json(ApiResponse::ok(stats)),
+        Err(e) => Json(ApiResponse::<CleanupStats>::err(format!("Recovery failed: {e:#}"))),
+    }
+}
@@ -0,0 +1,30 @@
+use crate::balancer::analysis::{balance_target, max_imbalance};
+use crate::balancer::BalanceMetric;
+use crate::AppState;
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::sync::Arc;
+
+/// Prometheus exposition content type.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub(crate) async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let daemon_state = state.status.read().await.state;
+
+    let imbalance = match state.db.get_included_disks() {
+        Ok(disks) => current_imbalance(&disks, state.config().balance_metric),
+        Err(e) => {
+            tracing::warn!("Metrics: failed to read disks: {}", e);
+            None
+        }
+    };
+
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        state.metrics.render(daemon_state, imbalance),
+    )
+}
+
+/// Max deviation of any included disk from its target under the configured metric.
+fn current_imbalance(disks: &[crate::db::Disk], metric: BalanceMetric) -> Option<f64> {
+    balance_target(disks, metric).map(|target| max_imbalance(disks, target))
+}
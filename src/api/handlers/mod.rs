@@ -1,15 +1,40 @@
+mod array;
 mod disks;
 mod execution;
+mod export;
+mod files;
+mod maintenance;
+mod metrics;
 mod plan;
 mod scan;
 mod settings;
 mod sse;
 mod status;
+mod ws;
 
-pub(super) use disks::{get_disks, set_disk_excluded, set_disk_included};
-pub(super) use execution::{cancel_operation, execute_plan};
-pub(super) use plan::handle_generate_plan;
-pub(super) use scan::start_scan;
-pub(super) use settings::{get_settings, update_settings};
+pub(super) use array::{get_imbalance, get_summary};
+pub(super) use disks::{
+    get_disk_file_types, get_disk_growth, get_disks, set_disk_excluded, set_disk_included,
+    set_disks_included_bulk,
+};
+pub(crate) use execution::begin_execution;
+pub(super) use execution::{
+    cancel_operation, execute_plan, skip_current_move, undo_move, validate_plan,
+};
+pub(super) use export::export_disk_files;
+pub(super) use files::{
+    browse_disk_files, get_duplicates, get_largest_files, get_largest_files_on_disk,
+};
+pub(super) use maintenance::{recover_partial_files, vacuum_database};
+pub(super) use metrics::get_metrics;
+pub(super) use plan::{
+    compare_plans, delete_plan_move, get_plan_script, handle_drain_plan, handle_generate_plan,
+    handle_rollback_plan, list_plan_moves, list_plans, reorder_plan_moves,
+};
+pub(crate) use plan::{plan_from_config, stale_catalog_warning};
+pub(crate) use scan::{begin_disks_scan, begin_scan};
+pub(super) use scan::{cancel_scan, start_disk_scan, start_scan};
+pub(super) use settings::{apply_profile, get_settings, list_profiles, update_settings};
 pub(super) use sse::sse_events;
-pub(super) use status::get_status;
+pub(super) use status::{get_health, get_progress, get_ready, get_status};
+pub(super) use ws::ws_events;
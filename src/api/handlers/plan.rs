@@ -1,79 +1,520 @@
-use crate::api::responses::{ApiResponse, PlanRequest, PlanSummary};
-use crate::db::PlanStatus;
+use crate::api::responses::{
+    codes, ApiError, ApiResponse, CompareQuery, ComparedMove, ComparedPlan, MovePage, MovesQuery,
+    PlanComparison, PlanRequest, PlanSummary, PlansQuery, ReorderMovesRequest,
+};
+use crate::balancer::analysis::balance_score;
+use crate::balancer::filesystems::fs_mismatch_warnings;
+use crate::balancer::types::BalanceResult;
+use crate::balancer::PlanOptions;
+use crate::db::{MoveStatus, PlanStatus};
+use crate::executor::estimate::{estimate_seconds, THROUGHPUT_HISTORY};
+use crate::executor::script::render_plan_script;
 use crate::{AppState, DaemonState, DaemonStatus};
-use axum::{extract::State, response::IntoResponse, Json};
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Moves per page when no `limit` is given.
+const DEFAULT_MOVES_LIMIT: usize = 100;
+/// Upper bound on a page of moves.
+const MAX_MOVES_LIMIT: usize = 1000;
+
 pub(crate) async fn handle_generate_plan(
     State(state): State<Arc<AppState>>,
     Json(req): Json<PlanRequest>,
+) -> impl IntoResponse {
+    let alpha = req.alpha.unwrap_or(state.config().slider_alpha);
+    if let Some(target) = req.target_utilization {
+        if let Err(e) = crate::config::validate_target_utilization(target) {
+            return Json(ApiResponse::<PlanSummary>::err_with_code(
+                codes::VALIDATION,
+                format!("{e}"),
+            ));
+        }
+    }
+    if req.max_moves == Some(0) {
+        return Json(ApiResponse::<PlanSummary>::err_with_code(
+            codes::VALIDATION,
+            "max_moves must be at least 1",
+        ));
+    }
+    let name = req.name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let tags = normalize_tags(&req.tags);
+    let stale_warning = stale_catalog_warning(&state);
+    if let (Some(warning), true) = (&stale_warning, state.config().catalog_age_strict) {
+        return Json(ApiResponse::<PlanSummary>::err(format!("Refusing to plan: {warning}")));
+    }
+    match plan_from_config(&state, alpha, req.target_utilization, req.max_moves).await {
+        Ok(balance_result) => {
+            if name.is_some() || !tags.is_empty() {
+                if let Err(e) = state.db.set_plan_labels(balance_result.plan_id, name, &tags) {
+                    return Json(ApiResponse::<PlanSummary>::err(format!(
+                        "Failed to label plan: {e}"
+                    )));
+                }
+            }
+            let mut response = plan_ready_response(&state, &balance_result, alpha);
+            if let Some(summary) = response.0.data.as_mut() {
+                summary.stale_warning = stale_warning;
+            }
+            response
+        }
+        Err(e) => Json(ApiResponse::<PlanSummary>::from(e)),
+    }
+}
+
+/// Describe how stale the catalog is, if `catalog_max_age_secs` is exceeded.
+///
+/// Judged by the included disk scanned longest ago, since a single-disk
+/// rescan freshens only that disk.
+pub(crate) fn stale_catalog_warning(state: &AppState) -> Option<String> {
+    let max_age = state.config().catalog_max_age_secs?;
+    let disks = state
+        .db
+        .get_included_disks()
+        .inspect_err(|e| tracing::warn!("Catalog age check skipped: {}", e))
+        .ok()?;
+    let now = chrono::Utc::now();
+    let (name, age) = disks
+        .iter()
+        .filter_map(|d| Some((d.disk_name.as_str(), d.age_secs(now)?)))
+        .max_by_key(|(_, age)| *age)?;
+    (age > max_age as i64)
+        .then(|| format!("catalog is stale: {name} was last scanned {age}s ago (limit {max_age}s)"))
+}
+
+/// Trimmed, de-duplicated, non-empty tags in sorted order.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let tags: std::collections::BTreeSet<&str> =
+        tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+    tags.into_iter().map(str::to_string).collect()
+}
+
+/// List plans newest first, optionally only those with a given tag.
+pub(crate) async fn list_plans(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PlansQuery>,
+) -> impl IntoResponse {
+    let tag = query.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    match state.db.list_plans(tag) {
+        Ok(plans) => Json(ApiResponse::ok(plans)),
+        Err(e) => Json(ApiResponse::<Vec<crate::db::BalancePlan>>::err(format!(
+            "Failed to list plans: {e}"
+        ))),
+    }
+}
+
+/// Generate a balance plan from the current config while holding the planning state.
+///
+/// `target_utilization` and `max_moves` override the config for this plan only.
+pub(crate) async fn plan_from_config(
+    state: &AppState,
+    alpha: f64,
+    target_utilization: Option<f64>,
+    max_moves: Option<usize>,
+) -> Result<BalanceResult, ApiError> {
+    {
+        let mut status = state.status.write().await;
+        if status.state != DaemonState::Idle {
+            return Err(ApiError::busy("generate plan", status.state));
+        }
+        *status = DaemonStatus::planning();
+    }
+
+    let config = state.config();
+    let mut opts = PlanOptions::from_config(&config, alpha);
+    if target_utilization.is_some() {
+        opts.target_utilization_override = target_utilization;
+    }
+    if max_moves.is_some() {
+        opts.max_moves = max_moves;
+    }
+    if config.check_smart {
+        match state.db.get_included_disks() {
+            Ok(disks) => {
+                opts.excluded_disk_ids
+                    .extend(super::execution::unhealthy_disks(state, &disks).await);
+            }
+            Err(e) => {
+                *state.status.write().await = DaemonStatus::idle();
+                return Err(format!("SMART check failed: {e:#}").into());
+            }
+        }
+    }
+    if config.prefer_spinning_targets {
+        match crate::executor::spun_down_disks().await {
+            Ok(disks) => opts.spun_down_disks = disks,
+            Err(e) => tracing::warn!("Spin-aware targeting disabled for this plan: {}", e),
+        }
+    }
+    let result = crate::balancer::generate_plan(&state.db, &opts);
+
+    *state.status.write().await = DaemonStatus::idle();
+
+    result.map_err(|e| {
+        if e.is::<crate::balancer::TooFewDisks>() {
+            ApiError::new(codes::TOO_FEW_DISKS, format!("Planning failed: {e}"))
+        } else {
+            format!("Planning failed: {e}").into()
+        }
+    })
+}
+
+/// Generate a plan that reverses the completed moves of an earlier plan.
+pub(crate) async fn handle_rollback_plan(
+    State(state): State<Arc<AppState>>,
+    Path(plan_id): Path<i64>,
 ) -> impl IntoResponse {
     {
         let status = state.status.read().await;
         if status.state != DaemonState::Idle {
-            return Json(ApiResponse::<PlanSummary>::err(format!(
-                "Cannot generate plan: daemon is currently {:?}",
-                status.state
+            return Json(ApiResponse::<PlanSummary>::from(ApiError::busy(
+                "generate rollback plan",
+                status.state,
             )));
         }
     }
 
-    let alpha = req.alpha.unwrap_or(state.config.slider_alpha);
-
     *state.status.write().await = DaemonStatus::planning();
+    let result = crate::balancer::generate_rollback_plan(&state.db, plan_id);
+    *state.status.write().await = DaemonStatus::idle();
 
-    let result = crate::balancer::generate_plan(
-        &state.db,
-        alpha,
-        state.config.max_tolerance,
-        state.config.min_free_headroom,
-        &[],
-    );
+    match result {
+        Ok(balance_result) => {
+            let alpha = state.db.get_plan(plan_id).ok().flatten().map_or(0.0, |p| p.slider_alpha);
+            plan_ready_response(&state, &balance_result, alpha)
+        }
+        Err(e) => Json(ApiResponse::<PlanSummary>::err(format!("Rollback planning failed: {e}"))),
+    }
+}
+
+/// Generate a plan that moves every file off a disk, e.g. before replacing it.
+pub(crate) async fn handle_drain_plan(
+    State(state): State<Arc<AppState>>,
+    Path(disk_id): Path<i64>,
+) -> impl IntoResponse {
+    {
+        let mut status = state.status.write().await;
+        if status.state != DaemonState::Idle {
+            return Json(ApiResponse::<PlanSummary>::from(ApiError::busy(
+                "generate drain plan",
+                status.state,
+            )));
+        }
+        *status = DaemonStatus::planning();
+    }
 
+    let config = state.config();
+    let alpha = config.slider_alpha;
+    let opts = PlanOptions::from_config(&config, alpha);
+    let result = crate::balancer::generate_drain_plan(&state.db, &opts, disk_id);
     *state.status.write().await = DaemonStatus::idle();
 
     match result {
-        Ok(balance_result) => {
-            let moves = match state.db.get_plan_moves(balance_result.plan_id) {
-                Ok(m) => m,
-                Err(e) => {
-                    return Json(ApiResponse::<PlanSummary>::err(format!(
-                        "Failed to fetch plan moves: {e}"
-                    )));
-                }
-            };
-            let plan = match state.db.get_plan(balance_result.plan_id) {
-                Ok(p) => p,
-                Err(e) => {
-                    return Json(ApiResponse::<PlanSummary>::err(format!(
-                        "Failed to fetch plan: {e}"
-                    )));
-                }
-            };
-
-            // Publish PlanReady only after confirming both DB reads succeeded
-            let _ = state.event_hub.publish(crate::events::Event::PlanReady {
-                plan_id: balance_result.plan_id,
-                total_moves: moves.len() as u32,
-                total_bytes: balance_result.total_bytes,
-                projected_imbalance: balance_result.projected_imbalance,
-            });
-
-            Json(ApiResponse::ok(PlanSummary {
-                id: balance_result.plan_id,
-                created_at: plan.as_ref().and_then(|p| p.created_at.clone()),
-                tolerance: plan.as_ref().map_or(0.0, |p| p.tolerance),
-                slider_alpha: alpha,
-                target_utilization: balance_result.target_utilization,
-                initial_imbalance: Some(balance_result.initial_imbalance),
-                projected_imbalance: Some(balance_result.projected_imbalance),
-                total_moves: balance_result.total_moves as i32,
-                total_bytes_to_move: balance_result.total_bytes,
-                status: PlanStatus::Planned,
-                moves,
-            }))
-        }
-        Err(e) => Json(ApiResponse::<PlanSummary>::err(format!("Planning failed: {e}"))),
+        Ok(balance_result) => plan_ready_response(&state, &balance_result, alpha),
+        Err(e) => Json(ApiResponse::<PlanSummary>::err(format!("Drain planning failed: {e}"))),
+    }
+}
+
+/// Load a freshly generated plan, announce it, and build the API summary.
+pub(crate) fn plan_ready_response(
+    state: &AppState,
+    balance_result: &BalanceResult,
+    alpha: f64,
+) -> Json<ApiResponse<PlanSummary>> {
+    let moves = match state.db.get_plan_moves(balance_result.plan_id) {
+        Ok(m) => m,
+        Err(e) => {
+            return Json(ApiResponse::<PlanSummary>::err(format!(
+                "Failed to fetch plan moves: {e}"
+            )));
+        }
+    };
+    let plan = match state.db.get_plan(balance_result.plan_id) {
+        Ok(p) => p,
+        Err(e) => {
+            return Json(ApiResponse::<PlanSummary>::err(format!("Failed to fetch plan: {e}")));
+        }
+    };
+    let flows = match state.db.get_plan_flows(balance_result.plan_id) {
+        Ok(f) => f,
+        Err(e) => {
+            return Json(ApiResponse::<PlanSummary>::err(format!(
+                "Failed to fetch plan flows: {e}"
+            )));
+        }
+    };
+    let fs_mismatch_warnings = match state.db.get_all_disks() {
+        Ok(disks) => fs_mismatch_warnings(&flows, &disks),
+        Err(e) => {
+            return Json(ApiResponse::<PlanSummary>::err(format!("Failed to get disks: {e}")));
+        }
+    };
+    for warning in &fs_mismatch_warnings {
+        tracing::warn!("Plan {} crosses filesystems: {}", balance_result.plan_id, warning);
+    }
+
+    let measured = state
+        .db
+        .recent_throughput(THROUGHPUT_HISTORY)
+        .inspect_err(|e| tracing::warn!("Throughput history unavailable: {}", e))
+        .ok()
+        .flatten();
+    let (estimated_seconds, estimate_basis) = estimate_seconds(
+        balance_result.total_bytes,
+        balance_result.total_moves,
+        measured,
+        state.config().assumed_throughput,
+    );
+
+    // Publish PlanReady only after confirming both DB reads succeeded
+    let _ = state.event_hub.publish(crate::events::Event::PlanReady {
+        plan_id: balance_result.plan_id,
+        total_moves: moves.len() as u32,
+        total_bytes: balance_result.total_bytes,
+        projected_imbalance: balance_result.projected_imbalance,
+    });
+
+    let projections = &balance_result.disk_projections;
+    let score = |util: fn(&crate::balancer::types::DiskProjection) -> f64| {
+        balance_score(projections.iter().map(|p| (util(p), p.target_util)))
+    };
+
+    Json(ApiResponse::ok(PlanSummary {
+        id: balance_result.plan_id,
+        created_at: plan.as_ref().and_then(|p| p.created_at.clone()),
+        name: plan.as_ref().and_then(|p| p.name.clone()),
+        tags: plan.as_ref().map(|p| p.tags.clone()).unwrap_or_default(),
+        tolerance: plan.as_ref().map_or(0.0, |p| p.tolerance),
+        slider_alpha: alpha,
+        target_utilization: balance_result.target_utilization,
+        initial_imbalance: Some(balance_result.initial_imbalance),
+        projected_imbalance: Some(balance_result.projected_imbalance),
+        total_moves: balance_result.total_moves as i32,
+        total_bytes_to_move: balance_result.total_bytes,
+        estimated_seconds,
+        estimate_basis,
+        status: PlanStatus::Planned,
+        no_moves_reason: balance_result.no_moves_reason,
+        capped: balance_result.capped,
+        balance_score_before: score(|p| p.before_util),
+        balance_score_after: score(|p| p.after_util),
+        stale_warning: None,
+        disk_projections: balance_result.disk_projections.clone(),
+        flows,
+        fs_mismatch_warnings,
+        split_folders: balance_result.split_folders.clone(),
+        moves,
+    }))
+}
+
+pub(crate) async fn get_plan_script(
+    State(state): State<Arc<AppState>>,
+    Path(plan_id): Path<i64>,
+) -> Response {
+    match state.db.get_plan(plan_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Json(ApiResponse::<&str>::err_with_code(
+                codes::PLAN_NOT_FOUND,
+                "Plan not found",
+            ))
+            .into_response();
+        }
+        Err(e) => return Json(ApiResponse::<&str>::err(format!("{e}"))).into_response(),
+    }
+
+    let moves = match state.db.get_plan_moves(plan_id) {
+        Ok(m) => m,
+        Err(e) => {
+            return Json(ApiResponse::<&str>::err(format!("Failed to fetch plan moves: {e}")))
+                .into_response();
+        }
+    };
+    let disk_mounts: HashMap<i64, String> = match state.db.get_all_disks() {
+        Ok(disks) => disks.into_iter().map(|d| (d.id, d.mount_path)).collect(),
+        Err(e) => {
+            return Json(ApiResponse::<&str>::err(format!("Failed to get disks: {e}")))
+                .into_response();
+        }
+    };
+
+    let config = state.config();
+    let rsync = crate::executor::RsyncOptions::from_config(
+        &config,
+        state.rsync_info().await.supports_progress2,
+    );
+    let script = render_plan_script(plan_id, &moves, &disk_mounts, &rsync, &config.path_remap);
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/x-shellscript; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"perfectly-balanced-plan-{plan_id}.sh\""),
+            ),
+        ],
+        script,
+    )
+        .into_response()
+}
+
+/// Diff the move sets of two plans, e.g. generated at different slider values.
+pub(crate) async fn compare_plans(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CompareQuery>,
+) -> impl IntoResponse {
+    match plan_comparison(&state, query.a, query.b) {
+        Ok(comparison) => Json(ApiResponse::ok(comparison)),
+        Err(e) => Json(ApiResponse::from(e)),
+    }
+}
+
+fn plan_comparison(state: &AppState, a: i64, b: i64) -> Result<PlanComparison, ApiError> {
+    let side = |plan_id: i64| -> Result<ComparedPlan, ApiError> {
+        match state.db.get_plan(plan_id) {
+            Ok(Some(plan)) => Ok(ComparedPlan {
+                plan_id,
+                slider_alpha: plan.slider_alpha,
+                total_moves: plan.total_moves,
+                total_bytes: plan.total_bytes_to_move,
+                projected_imbalance: plan.projected_imbalance,
+            }),
+            Ok(None) => {
+                Err(ApiError::new(codes::PLAN_NOT_FOUND, format!("Plan {plan_id} not found")))
+            }
+            Err(e) => Err(format!("{e}").into()),
+        }
+    };
+    let (plan_a, plan_b) = (side(a)?, side(b)?);
+    let moves_a = state.db.get_plan_moves(a).map_err(|e| ApiError::from(format!("{e}")))?;
+    let mut moves_b: HashMap<(i64, String), crate::db::PlannedMoveDetail> = state
+        .db
+        .get_plan_moves(b)
+        .map_err(|e| ApiError::from(format!("{e}")))?
+        .into_iter()
+        .map(|m| ((m.move_info.source_disk_id, m.move_info.file_path.clone()), m))
+        .collect();
+
+    let mut comparison = PlanComparison {
+        a: plan_a,
+        b: plan_b,
+        only_in_a: Vec::new(),
+        only_in_b: Vec::new(),
+        in_both: Vec::new(),
+    };
+    for m in moves_a {
+        let key = (m.move_info.source_disk_id, m.move_info.file_path.clone());
+        let in_b = moves_b.remove(&key);
+        let entry = ComparedMove {
+            file_path: m.move_info.file_path,
+            file_size: m.move_info.file_size,
+            source_disk: m.source_disk_name,
+            target_in_a: Some(m.target_disk_name),
+            target_in_b: in_b.as_ref().map(|other| other.target_disk_name.clone()),
+        };
+        if in_b.is_some() {
+            comparison.in_both.push(entry);
+        } else {
+            comparison.only_in_a.push(entry);
+        }
+    }
+    let mut rest: Vec<crate::db::PlannedMoveDetail> = moves_b.into_values().collect();
+    rest.sort_by_key(|m| m.move_info.move_order);
+    comparison.only_in_b = rest
+        .into_iter()
+        .map(|m| ComparedMove {
+            file_path: m.move_info.file_path,
+            file_size: m.move_info.file_size,
+            source_disk: m.source_disk_name,
+            target_in_a: None,
+            target_in_b: Some(m.target_disk_name),
+        })
+        .collect();
+    Ok(comparison)
+}
+
+/// Page through a plan's moves, optionally only those in one status.
+pub(crate) async fn list_plan_moves(
+    State(state): State<Arc<AppState>>,
+    Path(plan_id): Path<i64>,
+    Query(query): Query<MovesQuery>,
+) -> impl IntoResponse {
+    match move_page(&state, plan_id, &query) {
+        Ok(page) => Json(ApiResponse::ok(page)),
+        Err(e) => Json(ApiResponse::from(e)),
+    }
+}
+
+fn move_page(state: &AppState, plan_id: i64, query: &MovesQuery) -> Result<MovePage, ApiError> {
+    let status = match query.status.as_deref() {
+        None | Some("all") => None,
+        Some(s) => Some(MoveStatus::try_from(s).map_err(|e| ApiError::new(codes::VALIDATION, e))?),
+    };
+    match state.db.get_plan(plan_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::new(codes::PLAN_NOT_FOUND, "Plan not found")),
+        Err(e) => return Err(format!("{e}").into()),
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_MOVES_LIMIT).clamp(1, MAX_MOVES_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let (moves, total_moves) = state
+        .db
+        .get_plan_moves_page(plan_id, status, limit, offset)
+        .map_err(|e| ApiError::from(format!("Failed to fetch plan moves: {e}")))?;
+    Ok(MovePage { moves, total_moves, limit, offset })
+}
+
+/// Ensure a plan exists and isn't running, so its moves can be edited.
+fn check_plan_editable(state: &AppState, plan_id: i64) -> Result<(), ApiError> {
+    match state.db.get_plan(plan_id) {
+        Ok(Some(plan)) if plan.status == PlanStatus::Executing => {
+            Err(ApiError::new(codes::WRONG_STATUS, "Cannot edit a plan while it is executing"))
+        }
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(ApiError::new(codes::PLAN_NOT_FOUND, "Plan not found")),
+        Err(e) => Err(format!("{e}").into()),
+    }
+}
+
+pub(crate) async fn delete_plan_move(
+    State(state): State<Arc<AppState>>,
+    Path((plan_id, move_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    if let Err(e) = check_plan_editable(&state, plan_id) {
+        return Json(ApiResponse::<&str>::from(e));
+    }
+    match state.db.delete_pending_move(plan_id, move_id) {
+        Ok(true) => {}
+        Ok(false) => {
+            return Json(ApiResponse::<&str>::err_with_code(
+                codes::MOVE_NOT_FOUND,
+                "No pending move with that ID in plan",
+            ));
+        }
+        Err(e) => return Json(ApiResponse::<&str>::err(format!("Failed to delete move: {e}"))),
+    }
+    match state.db.refresh_plan_totals(plan_id) {
+        Ok(()) => Json(ApiResponse::ok("Move removed")),
+        Err(e) => Json(ApiResponse::<&str>::err(format!("Failed to update plan totals: {e}"))),
     }
 }
 
+pub(crate) async fn reorder_plan_moves(
+    State(state): State<Arc<AppState>>,
+    Path(plan_id): Path<i64>,
+    Json(req): Json<ReorderMovesRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = check_plan_editable(&state, plan_id) {
+        return Json(ApiResponse::<&str>::from(e));
+    }
+    match state.db.reorder_pending_moves(plan_id, &req.move_ids) {
+        Ok(()) => Json(ApiResponse::ok("Moves reordered")),
+        Err(e) => Json(ApiResponse::<&str>::err(format!("Failed to reorder moves: {e}"))),
+    }
+}
@@ -1,50 +1,150 @@
-use crate::api::responses::{ApiResponse, ScanRequest};
+use crate::api::responses::{codes, ApiError, ApiResponse, CancelRequest, ScanRequest};
+use crate::events::DiskScanSummary;
 use crate::{scanner, AppState, DaemonState, DaemonStatus};
-use axum::{extract::State, response::IntoResponse, Json};
-use std::collections::HashMap;
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
 use std::panic::AssertUnwindSafe;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub(crate) async fn start_scan(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ScanRequest>,
 ) -> impl IntoResponse {
+    let threads = req.threads.unwrap_or(state.config().scan_threads);
+    match begin_scan(&state, threads).await {
+        Ok(_) => Json(ApiResponse::ok("Scan started")),
+        Err(e) => Json(ApiResponse::<&str>::from(e)),
+    }
+}
+
+/// Cancel the running scan; unlike `/api/plan/{id}/cancel`, refuses to touch
+/// an execution or planning run.
+pub(crate) async fn cancel_scan(
+    State(state): State<Arc<AppState>>,
+    req: Option<Json<CancelRequest>>,
+) -> impl IntoResponse {
+    let reason = req.and_then(|Json(r)| r.reason);
+    match super::execution::cancel_current_operation(&state, reason, Some(DaemonState::Scanning))
+        .await
+    {
+        Ok(reason) => {
+            info!("Scan cancellation requested: {}", reason);
+            Json(ApiResponse::ok("Cancellation requested"))
+        }
+        Err(e) => Json(ApiResponse::<&str>::from(e)),
+    }
+}
+
+/// Rescan one known disk, skipping discovery of the rest of `/mnt`.
+pub(crate) async fn start_disk_scan(
+    State(state): State<Arc<AppState>>,
+    Path(disk_id): Path<i64>,
+    req: Option<Json<ScanRequest>>,
+) -> impl IntoResponse {
+    let disk = match state.db.get_disk(disk_id) {
+        Ok(Some(disk)) => disk,
+        Ok(None) => {
+            return Json(ApiResponse::<&str>::err_with_code(
+                codes::DISK_NOT_FOUND,
+                "Disk not found",
+            ));
+        }
+        Err(e) => return Json(ApiResponse::<&str>::err(format!("{e}"))),
+    };
+    if state.config().excluded_disks.contains(&disk.disk_name) {
+        return Json(ApiResponse::<&str>::err(format!(
+            "{} is excluded from scans",
+            disk.disk_name
+        )));
+    }
+    let threads = req.and_then(|Json(r)| r.threads).unwrap_or(state.config().scan_threads);
+    let only = scanner::DiscoveredDisk { name: disk.disk_name, mount_path: disk.mount_path };
+    match start_scan_task(&state, threads, Some(vec![only])).await {
+        Ok(_) => Json(ApiResponse::ok("Scan started")),
+        Err(e) => Json(ApiResponse::<&str>::from(e)),
+    }
+}
+
+/// A scan running as the background task.
+pub(crate) struct ScanTask {
+    /// Cancelled when the scan was, so a caller can tell that apart from a failure.
+    pub token: CancellationToken,
+    /// Resolves once the daemon is idle again: `true` when every disk was
+    /// scanned, `false` when discovery or any disk's walk failed, the scan
+    /// was cancelled, or the task panicked.
+    pub finished: tokio::sync::oneshot::Receiver<bool>,
+}
+
+/// Transition to scanning and run a full scan as the background task.
+pub(crate) async fn begin_scan(
+    state: &Arc<AppState>,
+    threads: usize,
+) -> Result<ScanTask, ApiError> {
+    start_scan_task(state, threads, None).await
+}
+
+/// Transition to scanning and rescan just `disks` as the background task.
+pub(crate) async fn begin_disks_scan(
+    state: &Arc<AppState>,
+    threads: usize,
+    disks: Vec<scanner::DiscoveredDisk>,
+) -> Result<ScanTask, ApiError> {
+    start_scan_task(state, threads, Some(disks)).await
+}
+
+/// Run a scan as the background task: of `only` when given, otherwise of every
+/// disk found by discovery.
+async fn start_scan_task(
+    state: &Arc<AppState>,
+    threads: usize,
+    only: Option<Vec<scanner::DiscoveredDisk>>,
+) -> Result<ScanTask, ApiError> {
     // Atomically check idle and transition to scanning
     {
         let mut status = state.status.write().await;
         if status.state != DaemonState::Idle {
-            return Json(ApiResponse::<&str>::err(format!(
-                "Cannot start scan: daemon is currently {:?}",
-                status.state
-            )));
+            return Err(ApiError::busy("start scan", status.state));
         }
         *status = DaemonStatus::scanning("Preparing scan...");
     }
 
-    let threads = req.threads.unwrap_or(state.config.scan_threads).clamp(1, 32);
+    let threads = threads.clamp(1, 32);
     let token = state.new_operation_token().await;
-    let state_clone = Arc::clone(&state);
+    let task_token = token.clone();
+    let state_clone = Arc::clone(state);
+    let (done_tx, finished) = tokio::sync::oneshot::channel();
 
     let handle = tokio::task::spawn_blocking(move || {
         let rt = tokio::runtime::Handle::current();
 
         let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
-            let discovered = match scanner::discover_disks(&state_clone.config.mnt_base) {
-                Ok(d) => d,
-                Err(e) => {
-                    error!("Disk discovery failed: {}", e);
-                    let _ = state_clone.event_hub.publish(crate::events::Event::DaemonError {
-                        message: format!("Disk discovery failed: {e}"),
-                    });
-                    return;
+            let discovered = if let Some(disks) = only {
+                disks
+            } else {
+                let config = state_clone.config();
+                match scanner::discover_disks(&config.mnt_base, &config.extra_mount_paths) {
+                    Ok(d) => {
+                        info!("Discovered {} disks", d.len());
+                        d
+                    }
+                    Err(e) => {
+                        error!("Disk discovery failed: {}", e);
+                        let _ = state_clone.event_hub.publish(crate::events::Event::DaemonError {
+                            message: format!("Disk discovery failed: {e}"),
+                        });
+                        return false;
+                    }
                 }
             };
 
-            info!("Discovered {} disks", discovered.len());
-            scan_discovered_disks(&state_clone, &discovered, threads, &rt, &token);
+            scan_discovered_disks(&state_clone, &discovered, threads, &rt, &task_token)
         }));
 
         if result.is_err() {
@@ -59,55 +159,61 @@ pub(crate) async fn start_scan(
             *state_clone.status.write().await = DaemonStatus::idle();
             *state_clone.background_task.lock().await = None;
         });
+        let _ = done_tx.send(result.unwrap_or(false));
     });
 
     *state.background_task.lock().await = Some(handle);
 
-    Json(ApiResponse::ok("Scan started"))
-}
-
-/// Parse /proc/mounts once into a mount_path → fs_type lookup.
-fn parse_mount_table() -> HashMap<String, String> {
-    let mut table = HashMap::new();
-    if let Ok(mounts) = std::fs::read_to_string("/proc/mounts") {
-        for line in mounts.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                table.insert(parts[1].to_string(), parts[2].to_string());
-            }
-        }
-    }
-    table
+    Ok(ScanTask { token, finished })
 }
 
+/// Scan each discovered disk, walking up to `parallel_disk_scans` at once.
+///
+/// Each walk runs on its own thread with its own [`scanner::ScanContext`]; the
+/// catalog's connection mutex serializes their writes. Cancelling `cancel`
+/// stops every walk in progress and keeps the rest from starting.
+///
+/// Returns whether every disk was scanned: `false` after a cancel or when any
+/// disk couldn't be measured, recorded or walked.
 fn scan_discovered_disks(
     state: &Arc<AppState>,
     discovered: &[scanner::DiscoveredDisk],
     threads: usize,
     rt: &tokio::runtime::Handle,
     cancel: &CancellationToken,
-) {
-    let mut total_files = 0u64;
-    let mut total_bytes = 0u64;
+) -> bool {
     let start = std::time::Instant::now();
-    let mount_table = parse_mount_table();
+    let config = state.config();
+    let mount_table = scanner::parse_mount_table();
 
     // Compute the catalog DB's parent directory so the scanner can skip it.
     // This prevents the DB files (catalog.db, -wal, -shm) from being cataloged
     // when the user places the catalog on a scanned disk (e.g. /mnt/cache/).
     let exclude_dir: Option<PathBuf> =
-        std::path::Path::new(&state.config.db_path).parent().map(PathBuf::from);
+        std::path::Path::new(&config.db_path).parent().map(PathBuf::from);
 
+    // Compile exclude globs once for the whole scan. Config validation already
+    // rejected invalid patterns, so a failure here is unexpected.
+    let exclude_patterns =
+        scanner::ExcludeMatcher::new(&config.exclude_patterns).unwrap_or_else(|e| {
+            error!("Invalid exclude patterns, scanning without them: {}", e);
+            scanner::ExcludeMatcher::empty()
+        });
+
+    let mut to_scan: Vec<(&scanner::DiscoveredDisk, i64)> = Vec::new();
+    let mut failed_disks = 0usize;
     for disk in discovered {
         let space = match scanner::get_disk_space(&disk.mount_path) {
             Ok(s) => s,
             Err(e) => {
                 error!("Failed to get disk space for {}: {}", disk.name, e);
+                failed_disks += 1;
                 continue;
             }
         };
 
-        let fs_type = mount_table.get(&disk.mount_path).map(String::as_str);
+        let fs_type =
+            scanner::mount_entry(&disk.mount_path, &mount_table).map(|m| m.fs_type.as_str());
 
         let disk_id = match state.db.upsert_disk(
             &disk.name,
@@ -120,43 +226,104 @@ fn scan_discovered_disks(
             Ok(id) => id,
             Err(e) => {
                 error!("Failed to upsert disk {}: {}", disk.name, e);
+                failed_disks += 1;
                 continue;
             }
         };
 
-        if state.config.excluded_disks.contains(&disk.name) {
+        if config.excluded_disks.contains(&disk.name) {
             info!("Skipping excluded disk: {}", disk.name);
             continue;
         }
 
-        rt.block_on(async {
-            *state.status.write().await =
-                DaemonStatus::scanning(format!("Scanning {}...", disk.name));
-        });
-
-        if cancel.is_cancelled() {
-            info!("Scan cancelled by user");
-            break;
+        // An unmounted disk's empty mountpoint would replace its catalog with nothing.
+        if let Some(problem) = scanner::mount_problem(&disk.mount_path, &mount_table, false) {
+            warn!("Skipping scan of {}: {}", disk.name, problem);
+            let _ = state.event_hub.publish(crate::events::Event::DaemonError {
+                message: format!("Skipped scanning {}: {problem}", disk.name),
+            });
+            continue;
         }
 
-        let ctx = scanner::ScanContext {
-            db: &state.db,
-            disk_id,
-            mount_path: &disk.mount_path,
-            event_hub: &state.event_hub,
-            cancel: cancel.clone(),
-            num_threads: threads,
-            exclude_dir: exclude_dir.as_deref(),
-        };
-        match scanner::scan_disk(&ctx) {
-            Ok(stats) => {
-                total_files += stats.files_scanned;
-                total_bytes += stats.bytes_cataloged;
-            }
-            Err(e) => {
-                error!("Scan failed for {}: {}", disk.name, e);
-            }
+        to_scan.push((disk, disk_id));
+    }
+
+    // Workers pull the next disk off a shared index until none are left.
+    let next = AtomicUsize::new(0);
+    let active: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let set_active = |update: &dyn Fn(&mut Vec<String>)| {
+        let mut names = active.lock().unwrap_or_else(PoisonError::into_inner);
+        update(&mut names);
+        if !names.is_empty() {
+            let message = format!("Scanning {}...", names.join(", "));
+            rt.block_on(async {
+                *state.status.write().await = DaemonStatus::scanning(message);
+            });
         }
+    };
+    let workers = config.parallel_disk_scans.clamp(1, 32).min(to_scan.len());
+    let mut results: Vec<(usize, scanner::ScanStats)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(&(disk, disk_id)) = to_scan.get(i) else {
+                            break;
+                        };
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+                        set_active(&|names| names.push(disk.name.clone()));
+                        let ctx = scanner::ScanContext {
+                            db: &state.db,
+                            disk_id,
+                            mount_path: &disk.mount_path,
+                            event_hub: &state.event_hub,
+                            cancel: cancel.clone(),
+                            num_threads: threads,
+                            exclude_dir: exclude_dir.as_deref(),
+                            exclude_patterns: &exclude_patterns,
+                            include_roots: &config.include_roots,
+                            symlink_policy: config.symlink_policy,
+                        };
+                        let result = scanner::scan_disk(&ctx);
+                        set_active(&|names| names.retain(|n| *n != disk.name));
+                        match result {
+                            Ok(stats) => done.push((i, stats)),
+                            Err(e) => error!("Scan failed for {}: {}", disk.name, e),
+                        }
+                    }
+                    done
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+    if cancel.is_cancelled() {
+        info!("Scan cancelled by user");
+    }
+
+    let complete = !cancel.is_cancelled() && failed_disks == 0 && results.len() == to_scan.len();
+
+    // Report disks in discovery order, whichever finished first.
+    results.sort_by_key(|(i, _)| *i);
+    let mut total_files = 0u64;
+    let mut total_bytes = 0u64;
+    let mut per_disk = Vec::new();
+    for (i, stats) in results {
+        state.metrics.record_files_scanned(stats.files_scanned);
+        total_files += stats.files_scanned;
+        total_bytes += stats.bytes_cataloged;
+        per_disk.push(DiskScanSummary {
+            disk: to_scan[i].0.name.clone(),
+            files: stats.files_scanned,
+            bytes: stats.bytes_cataloged,
+        });
     }
 
     let duration = start.elapsed().as_secs_f64();
@@ -166,13 +333,15 @@ fn scan_discovered_disks(
         total_files,
         total_bytes,
         duration_seconds: duration,
+        per_disk,
     });
 
     info!(
-        "Full scan complete: {} disks, {} files, {} bytes in {:.1}s",
+        "Scan complete: {} disks, {} files, {} bytes in {:.1}s",
         discovered.len(),
         total_files,
         total_bytes,
         duration
     );
+    complete
 }
@@ -1,36 +1,202 @@
-use crate::api::responses::{ApiResponse, SettingsUpdateRequest};
+use crate::api::responses::{codes, ApiResponse, SettingsUpdateRequest};
+use crate::config::AppConfig;
 use crate::AppState;
-use axum::{extract::State, response::IntoResponse, Json};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 pub(crate) async fn get_settings(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    Json(ApiResponse::ok(state.config.clone()))
+    Json(ApiResponse::ok(AppConfig::clone(&state.config())))
 }
 
 pub(crate) async fn update_settings(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SettingsUpdateRequest>,
 ) -> impl IntoResponse {
-    let mut config = state.config.clone();
+    // Start from the saved settings so an applied profile isn't written back as the base.
+    let mut config = match state.config().reload_saved() {
+        Ok(c) => c,
+        Err(e) => {
+            return Json(ApiResponse::<&str>::err(format!("Failed to reload settings: {e:#}")));
+        }
+    };
 
     if let Some(v) = req.scan_threads {
         config.scan_threads = v;
     }
+    if let Some(v) = req.parallel_disk_scans {
+        config.parallel_disk_scans = v;
+    }
     if let Some(v) = req.slider_alpha {
         config.slider_alpha = v;
     }
     if let Some(v) = req.max_tolerance {
         config.max_tolerance = v;
     }
+    if let Some(v) = req.target_utilization_override {
+        config.target_utilization_override = (v != 0.0).then_some(v);
+    }
     if let Some(v) = req.min_free_headroom {
         config.min_free_headroom = v;
     }
+    if let Some(v) = req.absolute_min_free {
+        config.absolute_min_free = v;
+    }
     if let Some(v) = req.excluded_disks {
         config.excluded_disks = v.into_iter().collect();
     }
+    if let Some(v) = req.exclude_patterns {
+        config.exclude_patterns = v;
+    }
+    if let Some(v) = req.symlink_policy {
+        config.symlink_policy = v;
+    }
+    if let Some(v) = req.include_roots {
+        config.include_roots = v;
+    }
+    if let Some(v) = req.never_target_disks {
+        config.never_target_disks = v;
+    }
+    if let Some(v) = req.refuse_fs_mismatch {
+        config.refuse_fs_mismatch = v;
+    }
+    if let Some(v) = req.pinned_paths {
+        config.pinned_paths = v;
+    }
+    if let Some(v) = req.extra_mount_paths {
+        config.extra_mount_paths = v;
+    }
     if let Some(v) = req.warn_parity_check {
         config.warn_parity_check = v;
     }
+    if let Some(v) = req.keep_folders_together {
+        config.keep_folders_together = v;
+    }
+    if let Some(v) = req.balance_metric {
+        config.balance_metric = v;
+    }
+    if let Some(v) = req.target_strategy {
+        config.target_strategy = v;
+    }
+    if let Some(v) = req.candidate_order {
+        config.candidate_order = v;
+    }
+    if let Some(v) = req.move_size_thresholds {
+        config.move_size_thresholds = v;
+    }
+    if let Some(v) = req.max_moves {
+        config.max_moves = (v > 0).then_some(v);
+    }
+    if let Some(v) = req.min_file_age_days {
+        config.min_file_age_days = (v > 0).then_some(v);
+    }
+    if let Some(v) = req.move_undated_files {
+        config.move_undated_files = v;
+    }
+    if let Some(v) = req.prefer_spinning_targets {
+        config.prefer_spinning_targets = v;
+    }
+    if let Some(v) = req.check_smart {
+        config.check_smart = v;
+    }
+    if let Some(v) = req.cleanup_partial_on_cancel {
+        config.cleanup_partial_on_cancel = v;
+    }
+    if let Some(v) = req.resume_partial {
+        config.resume_partial = v;
+    }
+    if let Some(v) = req.cancel_mode {
+        config.cancel_mode = v;
+    }
+    if let Some(v) = req.on_conflict {
+        config.on_conflict = v;
+    }
+    if let Some(v) = req.path_remap {
+        config.path_remap = v;
+    }
+    if let Some(v) = req.open_file_policy {
+        config.open_file_policy = v;
+    }
+    if let Some(v) = req.verify_size {
+        config.verify_size = v;
+    }
+    if let Some(v) = req.preserve_acls {
+        config.preserve_acls = v;
+    }
+    if let Some(v) = req.verify_xattrs {
+        config.verify_xattrs = v;
+    }
+    if let Some(v) = req.prune_empty_dirs {
+        config.prune_empty_dirs = v;
+    }
+    if let Some(v) = req.auto_rescan_after_execution {
+        config.auto_rescan_after_execution = v;
+    }
+    if let Some(v) = req.compress_transfers {
+        config.compress_transfers = v;
+    }
+    if let Some(v) = req.compress_level {
+        config.compress_level = (v > 0).then_some(v);
+    }
+    if let Some(v) = req.extra_rsync_args {
+        config.extra_rsync_args = v;
+    }
+    if let Some(v) = req.rsync_nice {
+        config.rsync_nice = (v != 0).then_some(v);
+    }
+    if let Some(v) = req.rsync_ionice_class {
+        config.rsync_ionice_class = (v > 0).then_some(v);
+    }
+    if let Some(v) = req.move_timeout_secs {
+        config.move_timeout_secs = (v > 0).then_some(v);
+    }
+    if let Some(v) = req.move_delay_ms {
+        config.move_delay_ms = v;
+    }
+    if let Some(v) = req.catalog_max_age_secs {
+        config.catalog_max_age_secs = (v > 0).then_some(v);
+    }
+    if let Some(v) = req.catalog_age_strict {
+        config.catalog_age_strict = v;
+    }
+    if let Some(v) = req.assumed_throughput {
+        config.assumed_throughput = v;
+    }
+    if let Some(v) = req.schedule {
+        config.schedule = (!v.is_empty()).then_some(v);
+    }
+    if let Some(v) = req.sse_keepalive_secs {
+        config.sse_keepalive_secs = v;
+    }
+    if let Some(v) = req.heartbeat_secs {
+        config.heartbeat_secs = v;
+    }
+    if let Some(v) = req.auto_vacuum_on_start {
+        config.auto_vacuum_on_start = v;
+    }
+    if let Some(v) = req.db_busy_timeout_ms {
+        config.db_busy_timeout_ms = v;
+    }
+    if let Some(v) = req.db_open_timeout_secs {
+        config.db_open_timeout_secs = v;
+    }
+    if let Some(v) = req.db_fallback_path {
+        config.db_fallback_path = (!v.is_empty()).then_some(v);
+    }
+    if let Some(v) = req.notify_webhook_url {
+        config.notify_webhook_url = (!v.is_empty()).then_some(v);
+    }
+    if let Some(v) = req.audit_log_path {
+        config.audit_log_path = (!v.is_empty()).then_some(v);
+    }
+    if let Some(v) = req.api_token {
+        config.api_token = (!v.is_empty()).then_some(v);
+    }
     if let Some(v) = req.catalog_path {
         if v.is_empty() {
             config.db_path = crate::config::defaults::DEFAULT_DB_PATH.to_string();
@@ -40,11 +206,98 @@ pub(crate) async fn update_settings(
     }
 
     if let Err(e) = config.validate() {
-        return Json(ApiResponse::<&str>::err(format!("Invalid settings: {e}")));
+        return Json(ApiResponse::<&str>::err_with_code(
+            codes::VALIDATION,
+            format!("Invalid settings: {e}"),
+        ));
+    }
+
+    if let Err(e) = config.save() {
+        return Json(ApiResponse::<&str>::err(format!("Failed to save settings: {e}")));
     }
 
-    match config.save() {
-        Ok(()) => Json(ApiResponse::ok("Settings saved (restart to apply)")),
-        Err(e) => Json(ApiResponse::<&str>::err(format!("Failed to save settings: {e}"))),
+    // Everything but the port, catalog path, busy timeout and schedule applies
+    // from the next scan or plan on; those are only read at startup.
+    let current = state.config();
+    let mut live = match current.active_profile.as_deref() {
+        Some(name) => config.with_profile(name).unwrap_or_else(|e| {
+            tracing::warn!("Dropping profile '{}' after settings change: {:#}", name, e);
+            config
+        }),
+        None => config,
+    };
+    live.port = current.port;
+    live.db_path.clone_from(&current.db_path);
+    state.replace_config(live);
+
+    Json(ApiResponse::ok(
+        "Settings saved and applied (port, catalog path, fallback and timeouts, and schedule \
+         apply after restart)",
+    ))
+}
+
+/// Profile keys whose values are secrets. Profiles are readable without the
+/// API token, so these are listed as set but never shown.
+const REDACTED_PROFILE_KEYS: [&str; 2] = ["API_TOKEN", "NOTIFY_WEBHOOK_URL"];
+
+#[derive(Serialize)]
+pub(crate) struct ProfileList {
+    pub active: Option<String>,
+    pub profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+pub(crate) async fn list_profiles(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = state.config();
+    Json(ApiResponse::ok(ProfileList {
+        active: config.active_profile.clone(),
+        profiles: config
+            .profiles
+            .iter()
+            .map(|(name, overrides)| {
+                let shown = overrides
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = if REDACTED_PROFILE_KEYS.contains(&key.as_str()) {
+                            "<redacted>".to_string()
+                        } else {
+                            value.clone()
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect();
+                (name.clone(), shown)
+            })
+            .collect(),
+    }))
+}
+
+/// Apply a named profile's overrides on top of the saved settings.
+///
+/// Takes effect for the next scan, plan or execution; running operations
+/// keep the config they started with. Nothing is written to disk.
+pub(crate) async fn apply_profile(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let saved = match state.config().reload_saved() {
+        Ok(c) => c,
+        Err(e) => {
+            return Json(ApiResponse::<String>::err(format!("Failed to reload settings: {e:#}")));
+        }
+    };
+    if !saved.profiles.contains_key(&name) {
+        return Json(ApiResponse::<String>::err_with_code(
+            codes::PROFILE_NOT_FOUND,
+            format!("No profile named '{name}'"),
+        ));
+    }
+    match saved.with_profile(&name) {
+        Ok(mut config) => {
+            config.db_path.clone_from(&state.config().db_path);
+            tracing::info!("Applied config profile '{}'", name);
+            state.replace_config(config);
+            Json(ApiResponse::ok(format!("Profile '{name}' applied")))
+        }
+        Err(e) => Json(ApiResponse::<String>::err_with_code(codes::VALIDATION, format!("{e:#}"))),
     }
 }
@@ -1,38 +1,55 @@
+use crate::events::StampedEvent;
 use crate::AppState;
 use axum::{
     extract::State,
+    http::HeaderMap,
     response::sse::{Event as SseEvent, KeepAlive, Sse},
+    response::{IntoResponse, Response},
 };
-use futures::stream::Stream;
 use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::StreamExt;
 
-pub(crate) async fn sse_events(
-    State(state): State<Arc<AppState>>,
-) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
-    let rx = state.event_hub.subscribe();
+pub(crate) async fn sse_events(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    // Browsers send Last-Event-ID when EventSource reconnects; replay what they missed.
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let (missed, rx) = match last_event_id {
+        Some(id) => state.event_hub.subscribe_since(id),
+        None => (Vec::new(), state.event_hub.subscribe()),
+    };
 
-    let stream =
-        BroadcastStream::new(rx).filter_map(
-            |result: Result<crate::events::Event, _>| match result {
-                Ok(event) => {
-                    let event_type = event.event_type().to_string();
-                    match serde_json::to_string(&event) {
-                        Ok(json) => Some(Ok(SseEvent::default().event(event_type).data(json))),
-                        Err(e) => {
-                            tracing::warn!("Failed to serialize SSE event: {}", e);
-                            None
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::debug!("SSE subscriber lagged: {}", e);
-                    None
-                }
-            },
-        );
+    let replay = tokio_stream::iter(missed).filter_map(|stamped| to_sse(&stamped));
+    let live = BroadcastStream::new(rx).filter_map(|result| match result {
+        Ok(stamped) => to_sse(&stamped),
+        Err(e) => {
+            tracing::debug!("SSE subscriber lagged: {}", e);
+            None
+        }
+    });
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+    let sse = Sse::new(replay.chain(live));
+    match state.config().sse_keepalive_secs {
+        0 => sse.into_response(),
+        secs => sse
+            .keep_alive(KeepAlive::new().interval(Duration::from_secs(secs)).text("keep-alive"))
+            .into_response(),
+    }
+}
+
+fn to_sse(stamped: &StampedEvent) -> Option<Result<SseEvent, Infallible>> {
+    match serde_json::to_string(&stamped.event) {
+        Ok(json) => Some(Ok(SseEvent::default()
+            .id(stamped.id.to_string())
+            .event(stamped.event.event_type())
+            .data(json))),
+        Err(e) => {
+            tracing::warn!("Failed to serialize SSE event: {}", e);
+            None
+        }
+    }
 }
@@ -1,13 +1,37 @@
-use crate::api::responses::{ApiResponse, StatusResponse};
+use crate::api::responses::{ApiResponse, HealthResponse, ReadyResponse, StatusResponse};
 use crate::AppState;
-use axum::{extract::State, response::IntoResponse, Json};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use std::sync::Arc;
 
 pub(crate) async fn get_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rsync_version = state.rsync_info().await.version.clone();
     let status = state.status.read().await;
     Json(ApiResponse::ok(StatusResponse {
         state: status.state,
         detail: status.detail.clone(),
         version: env!("CARGO_PKG_VERSION").to_string(),
+        rsync_version,
+        catalog_fallback: state.catalog_fallback.get().cloned(),
     }))
 }
+
+/// Live progress of the running execution; `data` is null when none is running.
+pub(crate) async fn get_progress(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(ApiResponse::ok(state.progress.read().await.clone()))
+}
+
+/// Liveness probe: answers without touching daemon state or the database.
+pub(crate) async fn get_health() -> impl IntoResponse {
+    Json(HealthResponse { status: "ok", version: env!("CARGO_PKG_VERSION") })
+}
+
+/// Readiness probe: `200` once the catalog answers a query, `503` otherwise.
+pub(crate) async fn get_ready(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.db.ping() {
+        Ok(()) => (StatusCode::OK, Json(ReadyResponse { status: "ready", error: None })),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ReadyResponse { status: "unavailable", error: Some(format!("{e:#}")) }),
+        ),
+    }
+}
@@ -0,0 +1,118 @@
+use crate::api::responses::{codes, ApiResponse, WsCommand};
+use crate::events::StampedEvent;
+use crate::AppState;
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::State,
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tracing::{debug, info, warn};
+
+/// An event as sent over the socket: the SSE payload plus its stream id.
+#[derive(Serialize)]
+struct WsEvent<'a> {
+    id: u64,
+    #[serde(flatten)]
+    event: &'a crate::events::Event,
+}
+
+/// Stream events over a WebSocket and accept control commands on the same socket.
+///
+/// Outbound text frames carry the same events as `/api/events`; inbound text
+/// frames are [`WsCommand`]s, each answered with an `ApiResponse` frame. With
+/// `api_token` set, `auth::require_ws_token` refuses the upgrade without it.
+pub(crate) async fn ws_events(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    ws.on_upgrade(move |socket| serve_socket(socket, state))
+}
+
+async fn serve_socket(socket: WebSocket, state: Arc<AppState>) {
+    let (mut sink, mut stream) = socket.split();
+    let (reply_tx, mut reply_rx) = mpsc::channel::<String>(16);
+    let mut events = state.event_hub.subscribe();
+
+    let mut forward = tokio::spawn(async move {
+        loop {
+            let text = tokio::select! {
+                received = events.recv() => match received {
+                    Ok(stamped) => match to_frame(&stamped) {
+                        Some(text) => text,
+                        None => continue,
+                    },
+                    Err(RecvError::Lagged(missed)) => {
+                        debug!("WebSocket subscriber lagged, {} events dropped", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                Some(reply) = reply_rx.recv() => reply,
+            };
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut control = tokio::spawn(async move {
+        while let Some(Ok(message)) = stream.next().await {
+            let Message::Text(text) = message else {
+                if matches!(message, Message::Close(_)) {
+                    break;
+                }
+                continue;
+            };
+            let reply = match serde_json::to_string(&handle_command(&state, &text).await) {
+                Ok(json) => json,
+                Err(e) => {
+                    warn!("Failed to serialize WebSocket reply: {}", e);
+                    continue;
+                }
+            };
+            if reply_tx.send(reply).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Whichever half finishes first means the client is gone.
+    tokio::select! {
+        _ = &mut forward => control.abort(),
+        _ = &mut control => forward.abort(),
+    }
+}
+
+/// Run one inbound control message.
+async fn handle_command(state: &AppState, text: &str) -> ApiResponse<&'static str> {
+    let command = match serde_json::from_str::<WsCommand>(text) {
+        Ok(c) => c,
+        Err(e) => {
+            return ApiResponse::err_with_code(
+                codes::VALIDATION,
+                format!("Invalid control message: {e}"),
+            );
+        }
+    };
+    match command {
+        WsCommand::Cancel { reason } => {
+            match super::execution::cancel_current_operation(state, reason, None).await {
+                Ok(reason) => {
+                    info!("Cancellation requested over WebSocket: {}", reason);
+                    ApiResponse::ok("Cancellation requested")
+                }
+                Err(e) => e.into(),
+            }
+        }
+    }
+}
+
+fn to_frame(stamped: &StampedEvent) -> Option<String> {
+    serde_json::to_string(&WsEvent { id: stamped.id, event: &stamped.event })
+        .inspect_err(|e| warn!("Failed to serialize WebSocket event: {}", e))
+        .ok()
+}
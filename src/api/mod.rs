@@ -1,12 +1,17 @@
+pub(crate) mod auth;
 mod handlers;
 pub(crate) mod responses;
 
+pub(crate) use handlers::{begin_execution, begin_scan, plan_from_config, stale_catalog_warning};
+
 use crate::AppState;
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
 use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
@@ -17,23 +22,71 @@ pub(crate) fn router(state: Arc<AppState>) -> Router {
     Router::new()
         // Status
         .route("/api/status", get(handlers::get_status))
+        .route("/api/progress", get(handlers::get_progress))
+        // Prometheus metrics
+        .route("/api/metrics", get(handlers::get_metrics))
+        // Array summary
+        .route("/api/summary", get(handlers::get_summary))
+        .route("/api/imbalance", get(handlers::get_imbalance))
         // Disks
         .route("/api/disks", get(handlers::get_disks))
+        .route("/api/disks/bulk", post(handlers::set_disks_included_bulk))
         .route("/api/disks/{disk_id}/include", post(handlers::set_disk_included))
         .route("/api/disks/{disk_id}/exclude", post(handlers::set_disk_excluded))
+        .route("/api/disks/{disk_id}/types", get(handlers::get_disk_file_types))
+        .route("/api/disks/{disk_id}/growth", get(handlers::get_disk_growth))
+        .route("/api/disks/{disk_id}/largest", get(handlers::get_largest_files_on_disk))
+        .route("/api/disks/{disk_id}/files", get(handlers::browse_disk_files))
+        .route("/api/disks/{disk_id}/export", get(handlers::export_disk_files))
+        .route("/api/disks/{disk_id}/drain", post(handlers::handle_drain_plan))
+        .route("/api/disks/{disk_id}/scan", post(handlers::start_disk_scan))
+        // Files
+        .route("/api/largest", get(handlers::get_largest_files))
+        .route("/api/duplicates", get(handlers::get_duplicates))
         // Scanning
         .route("/api/scan", post(handlers::start_scan))
+        .route("/api/scan/cancel", post(handlers::cancel_scan))
         // Planning
         .route("/api/plan", post(handlers::handle_generate_plan))
+        .route("/api/plans", get(handlers::list_plans))
+        .route("/api/plans/compare", get(handlers::compare_plans))
+        .route("/api/plan/{plan_id}/script", get(handlers::get_plan_script))
+        .route("/api/plan/{plan_id}/rollback", post(handlers::handle_rollback_plan))
+        .route("/api/plan/{plan_id}/moves", get(handlers::list_plan_moves))
+        .route("/api/plan/{plan_id}/moves/{move_id}", delete(handlers::delete_plan_move))
+        .route("/api/plan/{plan_id}/moves/reorder", post(handlers::reorder_plan_moves))
         // Execution
+        .route("/api/plan/{plan_id}/validate", post(handlers::validate_plan))
         .route("/api/plan/{plan_id}/execute", post(handlers::execute_plan))
         .route("/api/plan/{plan_id}/cancel", post(handlers::cancel_operation))
+        .route("/api/plan/{plan_id}/skip-current", post(handlers::skip_current_move))
+        .route("/api/moves/{move_id}/undo", post(handlers::undo_move))
         // Settings
         .route("/api/settings", get(handlers::get_settings))
         .route("/api/settings", post(handlers::update_settings))
+        .route("/api/settings/profiles", get(handlers::list_profiles))
+        .route("/api/settings/profile/{name}", post(handlers::apply_profile))
+        // Maintenance
+        .route("/api/maintenance/vacuum", post(handlers::vacuum_database))
+        .route("/api/maintenance/recover", post(handlers::recover_partial_files))
         // SSE events
         .route("/api/events", get(handlers::sse_events))
+        // WebSocket: the same events, plus control commands from the client
+        .route(
+            "/api/ws",
+            get(handlers::ws_events).route_layer(middleware::from_fn_with_state(
+                Arc::clone(&state),
+                auth::require_ws_token,
+            )),
+        )
+        .route_layer(middleware::from_fn_with_state(Arc::clone(&state), auth::require_token))
+        // Probes are added after the auth layer so they never need a token
+        .route("/api/health", get(handlers::get_health))
+        .route("/api/ready", get(handlers::get_ready))
         .with_state(state)
+        // Honors Accept-Encoding: gzip/deflate. The default predicate never
+        // compresses text/event-stream, so /api/events keeps streaming unbuffered.
+        .layer(CompressionLayer::new())
         .layer(cors)
         .layer(TraceLayer::new_for_http())
 }
@@ -1,4 +1,6 @@
+use crate::db::ScanSnapshot;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 /// Generic API response wrapper.
 #[derive(Debug, Serialize)]
@@ -8,15 +10,80 @@ pub(crate) struct ApiResponse<T: Serialize> {
     pub data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Stable machine-readable error kind from [`codes`], when one applies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
 }
 
 impl<T: Serialize> ApiResponse<T> {
     pub(crate) const fn ok(data: T) -> Self {
-        Self { success: true, data: Some(data), error: None }
+        Self { success: true, data: Some(data), error: None, code: None }
     }
 
     pub(crate) fn err(msg: impl Into<String>) -> Self {
-        Self { success: false, data: None, error: Some(msg.into()) }
+        Self { success: false, data: None, error: Some(msg.into()), code: None }
+    }
+
+    pub(crate) fn err_with_code(code: &'static str, msg: impl Into<String>) -> Self {
+        Self { success: false, data: None, error: Some(msg.into()), code: Some(code) }
+    }
+}
+
+impl<T: Serialize> From<ApiError> for ApiResponse<T> {
+    fn from(e: ApiError) -> Self {
+        Self { success: false, data: None, error: Some(e.message), code: e.code }
+    }
+}
+
+/// Error codes clients can branch on instead of matching message text.
+pub(crate) mod codes {
+    /// Another scan, plan or execution is in progress.
+    pub(crate) const DAEMON_BUSY: &str = "DAEMON_BUSY";
+    /// A cancel was requested while nothing was running.
+    pub(crate) const NOTHING_RUNNING: &str = "NOTHING_RUNNING";
+    pub(crate) const PLAN_NOT_FOUND: &str = "PLAN_NOT_FOUND";
+    pub(crate) const DISK_NOT_FOUND: &str = "DISK_NOT_FOUND";
+    pub(crate) const MOVE_NOT_FOUND: &str = "MOVE_NOT_FOUND";
+    pub(crate) const PROFILE_NOT_FOUND: &str = "PROFILE_NOT_FOUND";
+    /// A parity check blocks execution while `warn_parity_check` is on.
+    pub(crate) const PARITY_RUNNING: &str = "PARITY_RUNNING";
+    /// The plan or move is not in a status that allows the operation.
+    pub(crate) const WRONG_STATUS: &str = "WRONG_STATUS";
+    /// rsync couldn't be run, so nothing can be moved.
+    pub(crate) const RSYNC_MISSING: &str = "RSYNC_MISSING";
+    /// The request or resulting settings failed validation.
+    pub(crate) const VALIDATION: &str = "VALIDATION";
+    /// Fewer disks are included than a balance plan needs.
+    pub(crate) const TOO_FEW_DISKS: &str = "TOO_FEW_DISKS";
+}
+
+/// A failure from a shared operation helper, with an optional [`codes`] entry.
+#[derive(Debug)]
+pub(crate) struct ApiError {
+    pub code: Option<&'static str>,
+    pub message: String,
+}
+
+impl ApiError {
+    pub(crate) fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self { code: Some(code), message: message.into() }
+    }
+
+    /// The daemon isn't idle, so `action` can't start.
+    pub(crate) fn busy(action: &str, state: crate::DaemonState) -> Self {
+        Self::new(codes::DAEMON_BUSY, format!("Cannot {action}: daemon is currently {state:?}"))
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self { code: None, message }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
     }
 }
 
@@ -30,19 +97,216 @@ pub(crate) struct ScanRequest {
 #[derive(Debug, Deserialize)]
 pub(crate) struct PlanRequest {
     pub alpha: Option<f64>,
+    /// Fill level to balance toward for this plan, overriding the config.
+    pub target_utilization: Option<f64>,
+    /// Stop after this many moves, overriding the config.
+    pub max_moves: Option<usize>,
+    /// Name to tell this plan apart in the history, e.g. "pre-disk5-retirement".
+    pub name: Option<String>,
+    /// Labels to filter the plan list by.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Query parameters for GET /api/plans.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PlansQuery {
+    /// Only plans carrying this tag.
+    pub tag: Option<String>,
+}
+
+/// Optional request body for POST /api/plan/{id}/cancel and POST /api/scan/cancel.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CancelRequest {
+    pub reason: Option<String>,
+}
+
+/// Request body for POST /api/disks/bulk.
+#[derive(Debug, Deserialize)]
+pub(crate) struct BulkDiskRequest {
+    #[serde(default)]
+    pub included: Vec<i64>,
+    #[serde(default)]
+    pub excluded: Vec<i64>,
+}
+
+/// A control message sent by a client over `/api/ws`, e.g. `{"action":"cancel"}`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum WsCommand {
+    /// Cancel the running scan or execution, like POST /api/plan/{id}/cancel.
+    Cancel { reason: Option<String> },
+}
+
+/// Request body for POST /api/plan/{id}/moves/reorder.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReorderMovesRequest {
+    /// Pending move IDs in their new execution order.
+    pub move_ids: Vec<i64>,
+}
+
+/// Query parameters for the largest-files endpoints.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LargestFilesQuery {
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for GET /api/duplicates.
+#[derive(Debug, Deserialize)]
+pub(crate) struct DuplicatesQuery {
+    /// Ignore files smaller than this many bytes.
+    pub min_size: Option<u64>,
+    pub limit: Option<usize>,
+    /// Read the start and end of each copy to confirm the match.
+    #[serde(default)]
+    pub hash: bool,
+}
+
+/// Query parameters for GET /api/plans/compare.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CompareQuery {
+    pub a: i64,
+    pub b: i64,
+}
+
+/// Query parameters for GET /api/disks/{id}/files.
+#[derive(Debug, Deserialize)]
+pub(crate) struct BrowseQuery {
+    /// Disk-relative folder; empty or "." for the root.
+    pub path: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Query parameters for GET /api/plan/{id}/moves.
+#[derive(Debug, Deserialize)]
+pub(crate) struct MovesQuery {
+    /// A move status such as "failed", or "all" (the default).
+    pub status: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// File format for GET /api/disks/{id}/export.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+/// Query parameters for GET /api/disks/{id}/export.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// One page of a cataloged folder's children.
+#[derive(Debug, Serialize)]
+pub(crate) struct FolderListing {
+    /// Normalized folder path ("" for the root).
+    pub path: String,
+    pub entries: Vec<crate::db::CatalogEntry>,
+    /// Children in the folder across all pages.
+    pub total_entries: u64,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// One page of a plan's moves.
+#[derive(Debug, Serialize)]
+pub(crate) struct MovePage {
+    pub moves: Vec<crate::db::PlannedMoveDetail>,
+    /// Moves matching the status filter across all pages.
+    pub total_moves: u64,
+    pub limit: usize,
+    pub offset: usize,
 }
 
 /// Request body for POST /api/settings.
 #[derive(Debug, Deserialize)]
 pub(crate) struct SettingsUpdateRequest {
     pub scan_threads: Option<usize>,
+    pub parallel_disk_scans: Option<usize>,
     pub slider_alpha: Option<f64>,
     pub max_tolerance: Option<f64>,
+    /// 0 clears the override and balances toward the array average again.
+    pub target_utilization_override: Option<f64>,
     pub min_free_headroom: Option<u64>,
+    pub absolute_min_free: Option<u64>,
     pub excluded_disks: Option<Vec<String>>,
+    pub never_target_disks: Option<Vec<String>>,
+    pub refuse_fs_mismatch: Option<bool>,
+    pub exclude_patterns: Option<Vec<String>>,
+    pub symlink_policy: Option<crate::scanner::SymlinkPolicy>,
+    pub include_roots: Option<Vec<String>>,
+    pub pinned_paths: Option<Vec<String>>,
+    pub extra_mount_paths: Option<Vec<String>>,
     pub warn_parity_check: Option<bool>,
     /// Custom catalog DB path. Empty string = use default (tmpfs).
     pub catalog_path: Option<String>,
+    pub keep_folders_together: Option<bool>,
+    pub balance_metric: Option<crate::balancer::BalanceMetric>,
+    pub target_strategy: Option<crate::balancer::TargetStrategy>,
+    pub candidate_order: Option<crate::balancer::CandidateOrder>,
+    pub move_size_thresholds: Option<crate::balancer::SizeThresholds>,
+    /// 0 removes the cap.
+    pub max_moves: Option<usize>,
+    /// 0 turns the age filter off.
+    pub min_file_age_days: Option<u64>,
+    pub move_undated_files: Option<bool>,
+    pub prefer_spinning_targets: Option<bool>,
+    pub check_smart: Option<bool>,
+    pub cleanup_partial_on_cancel: Option<bool>,
+    pub resume_partial: Option<bool>,
+    pub cancel_mode: Option<crate::executor::CancelMode>,
+    pub on_conflict: Option<crate::executor::OnConflict>,
+    pub path_remap: Option<crate::executor::PathRemap>,
+    /// `{"mode":"skip"}` or `{"mode":"wait","retries":3,"delay_secs":10}`.
+    pub open_file_policy: Option<crate::executor::OpenFilePolicy>,
+    pub verify_size: Option<bool>,
+    pub preserve_acls: Option<bool>,
+    pub verify_xattrs: Option<bool>,
+    pub prune_empty_dirs: Option<bool>,
+    pub auto_rescan_after_execution: Option<bool>,
+    pub compress_transfers: Option<bool>,
+    /// zlib level 1-9 for compressed transfers. 0 = rsync's default.
+    pub compress_level: Option<u32>,
+    pub extra_rsync_args: Option<Vec<String>>,
+    /// Niceness for rsync. 0 = run at normal priority.
+    pub rsync_nice: Option<i32>,
+    /// ionice class for rsync (1-3). 0 = leave the I/O class alone.
+    pub rsync_ionice_class: Option<u8>,
+    /// Seconds without rsync progress before a move fails. 0 = no timeout.
+    pub move_timeout_secs: Option<u64>,
+    /// Milliseconds to pause between transfers. 0 = no pause.
+    pub move_delay_ms: Option<u64>,
+    /// Seconds before the catalog counts as stale for planning. 0 = no check.
+    pub catalog_max_age_secs: Option<u64>,
+    pub catalog_age_strict: Option<bool>,
+    /// Bytes/sec assumed for plan ETAs without execution history.
+    pub assumed_throughput: Option<u64>,
+    /// Cron expression for scheduled runs. Empty string = disabled.
+    pub schedule: Option<String>,
+    /// Seconds between SSE keep-alive comments. 0 = disabled.
+    pub sse_keepalive_secs: Option<u64>,
+    /// Seconds between execution heartbeats. 0 = disabled.
+    pub heartbeat_secs: Option<u64>,
+    pub auto_vacuum_on_start: Option<bool>,
+    /// Catalog lock wait in milliseconds; applies after restart.
+    pub db_busy_timeout_ms: Option<u64>,
+    /// Seconds to retry the catalog path at startup; applies after restart.
+    pub db_open_timeout_secs: Option<u64>,
+    /// Catalog used when the primary can't be opened. Empty string = none.
+    pub db_fallback_path: Option<String>,
+    /// Webhook URL for execution notifications. Empty string = disabled.
+    pub notify_webhook_url: Option<String>,
+    /// File each settled move is appended to. Empty string = disabled.
+    pub audit_log_path: Option<String>,
+    /// Bearer token for mutating requests. Empty string = auth disabled.
+    pub api_token: Option<String>,
 }
 
 /// Scan progress summary returned by status endpoint.
@@ -51,6 +315,25 @@ pub(crate) struct StatusResponse {
     pub state: crate::DaemonState,
     pub detail: Option<String>,
     pub version: String,
+    /// Installed rsync version; `None` when rsync is missing.
+    pub rsync_version: Option<String>,
+    /// Why the catalog is the `DB_FALLBACK_PATH` one this run; `None` normally.
+    pub catalog_fallback: Option<String>,
+}
+
+/// Liveness probe body, deliberately independent of daemon state.
+#[derive(Debug, Serialize)]
+pub(crate) struct HealthResponse {
+    pub status: &'static str,
+    pub version: &'static str,
+}
+
+/// Readiness probe body; `error` explains a `503`.
+#[derive(Debug, Serialize)]
+pub(crate) struct ReadyResponse {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Plan summary for responses.
@@ -58,6 +341,8 @@ pub(crate) struct StatusResponse {
 pub(crate) struct PlanSummary {
     pub id: i64,
     pub created_at: Option<String>,
+    pub name: Option<String>,
+    pub tags: Vec<String>,
     pub tolerance: f64,
     pub slider_alpha: f64,
     pub target_utilization: f64,
@@ -65,6 +350,221 @@ pub(crate) struct PlanSummary {
     pub projected_imbalance: Option<f64>,
     pub total_moves: i32,
     pub total_bytes_to_move: u64,
+    /// Rough execution time; treat as a ballpark, not a promise.
+    pub estimated_seconds: f64,
+    pub estimate_basis: crate::executor::estimate::EstimateBasis,
     pub status: crate::db::PlanStatus,
+    /// Why the plan is empty, when it has no moves.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_moves_reason: Option<crate::balancer::NoMovesReason>,
+    /// The plan stopped at `max_moves` and leaves the array short of balance.
+    pub capped: bool,
+    /// 0–100 balance score of the disks before and after the plan.
+    pub balance_score_before: u8,
+    pub balance_score_after: u8,
+    /// Set when the plan was built from a catalog older than `catalog_max_age_secs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_warning: Option<String>,
+    /// Each disk's utilization before and after the plan.
+    pub disk_projections: Vec<crate::balancer::types::DiskProjection>,
+    /// Moves and bytes per source → target disk pair, heaviest first.
+    pub flows: Vec<crate::db::DiskFlow>,
+    /// One entry per disk pair whose filesystems differ in a way the moves lose
+    /// something, e.g. reflinks; empty when every move stays on like filesystems.
+    pub fs_mismatch_warnings: Vec<String>,
+    /// Kept-together folders too large for any one disk, split across several.
+    /// Only reported when the plan is generated.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub split_folders: Vec<String>,
     pub moves: Vec<crate::db::PlannedMoveDetail>,
 }
+
+/// Read-only check of a plan's pending moves for POST /api/plan/{id}/validate.
+#[derive(Debug, Serialize)]
+pub(crate) struct PlanValidation {
+    pub plan_id: i64,
+    pub pending_moves: usize,
+    /// Pending moves whose preconditions all hold right now.
+    pub valid_moves: usize,
+    pub valid_bytes: u64,
+    /// Moves execution would skip or fail, in execution order.
+    pub invalid: Vec<InvalidMove>,
+}
+
+/// A pending move that would not run if the plan were executed now.
+#[derive(Debug, Serialize)]
+pub(crate) struct InvalidMove {
+    pub move_id: i64,
+    pub file_path: String,
+    /// `skipped` or `failed`, as execution would record it.
+    pub outcome: crate::db::MoveStatus,
+    pub reason: String,
+}
+
+/// Two plans side by side, from GET /api/plans/compare.
+///
+/// Moves are matched by source disk and path; `in_both` lists files both
+/// plans move, possibly to different targets.
+#[derive(Debug, Serialize)]
+pub(crate) struct PlanComparison {
+    pub a: ComparedPlan,
+    pub b: ComparedPlan,
+    pub only_in_a: Vec<ComparedMove>,
+    pub only_in_b: Vec<ComparedMove>,
+    pub in_both: Vec<ComparedMove>,
+}
+
+/// One side of a [`PlanComparison`].
+#[derive(Debug, Serialize)]
+pub(crate) struct ComparedPlan {
+    pub plan_id: i64,
+    pub slider_alpha: f64,
+    pub total_moves: i32,
+    pub total_bytes: u64,
+    pub projected_imbalance: Option<f64>,
+}
+
+/// A file moved by one or both compared plans, with each plan's target.
+#[derive(Debug, Serialize)]
+pub(crate) struct ComparedMove {
+    pub file_path: String,
+    pub file_size: u64,
+    pub source_disk: String,
+    pub target_in_a: Option<String>,
+    pub target_in_b: Option<String>,
+}
+
+/// Array-wide capacity and balance summary for GET /api/summary.
+#[derive(Debug, Serialize)]
+pub(crate) struct ArraySummary {
+    pub total_capacity: u64,
+    pub total_used: u64,
+    pub total_free: u64,
+    /// Overall utilization across every known disk (0.0 - 1.0).
+    pub utilization: f64,
+    pub included_disks: usize,
+    pub excluded_disks: usize,
+    /// Balance target over included disks; `None` until disks have been scanned.
+    pub target_utilization: Option<f64>,
+    pub max_imbalance: Option<f64>,
+    pub class_counts: DiskClassCounts,
+}
+
+/// Catalog size around a `POST /api/maintenance/vacuum`.
+#[derive(Debug, Serialize)]
+pub(crate) struct VacuumResponse {
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+/// Current balance state of the included disks, computed from the catalog.
+#[derive(Debug, Serialize)]
+pub(crate) struct ImbalanceResponse {
+    /// `None` until disks have been scanned.
+    pub target_utilization: Option<f64>,
+    /// The configured balance metric the deviations below are measured by.
+    pub balance_metric: crate::balancer::BalanceMetric,
+    /// Free bytes every disk is balanced toward; set only under `free_bytes`.
+    pub target_free_bytes: Option<u64>,
+    pub max_imbalance: Option<f64>,
+    /// 0–100, where 100 means every included disk is at the target.
+    pub balance_score: Option<u8>,
+    pub disks: Vec<DiskUtilization>,
+}
+
+/// One included disk's utilization and its deviation from the target.
+#[derive(Debug, Serialize)]
+pub(crate) struct DiskUtilization {
+    pub disk_id: i64,
+    pub disk_name: String,
+    pub utilization: f64,
+    /// Signed difference from the disk's target utilization; positive means above it.
+    pub deviation: Option<f64>,
+}
+
+/// Number of included disks in each balance class.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct DiskClassCounts {
+    pub over_utilized: usize,
+    pub above_average: usize,
+    pub below_average: usize,
+    pub under_utilized: usize,
+}
+
+/// Outcome of POST /api/disks/bulk: every disk after the change, plus the
+/// requested ids that matched no disk and were skipped.
+#[derive(Debug, Serialize)]
+pub(crate) struct BulkDiskResult {
+    pub disks: Vec<crate::db::Disk>,
+    pub unknown_ids: Vec<i64>,
+}
+
+/// Change in a disk's contents between its two most recent scans.
+///
+/// After the first scan only the baseline is known and every delta is `None`.
+#[derive(Debug, Serialize)]
+pub(crate) struct DiskGrowth {
+    pub disk_id: i64,
+    pub scanned_at: String,
+    pub previous_scanned_at: Option<String>,
+    pub total_files: u64,
+    pub total_bytes: u64,
+    pub file_count_delta: Option<i64>,
+    pub bytes_delta: Option<i64>,
+    /// Sum of growth across extensions that grew.
+    pub bytes_added: Option<u64>,
+    /// Sum of shrinkage across extensions that shrank.
+    pub bytes_removed: Option<u64>,
+    /// Per-extension byte changes, largest magnitude first; unchanged ones omitted.
+    pub extension_deltas: Vec<ExtensionDelta>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ExtensionDelta {
+    pub extension: String,
+    pub bytes_delta: i64,
+}
+
+impl DiskGrowth {
+    pub(crate) fn between(current: &ScanSnapshot, previous: Option<&ScanSnapshot>) -> Self {
+        let mut growth = Self {
+            disk_id: current.disk_id,
+            scanned_at: current.scanned_at.clone(),
+            previous_scanned_at: None,
+            total_files: current.total_files,
+            total_bytes: current.total_bytes,
+            file_count_delta: None,
+            bytes_delta: None,
+            bytes_added: None,
+            bytes_removed: None,
+            extension_deltas: Vec::new(),
+        };
+        let Some(previous) = previous else {
+            return growth;
+        };
+
+        let extensions: BTreeSet<&String> =
+            current.extension_bytes.keys().chain(previous.extension_bytes.keys()).collect();
+        let mut deltas: Vec<ExtensionDelta> = extensions
+            .into_iter()
+            .map(|ext| {
+                let now = current.extension_bytes.get(ext).copied().unwrap_or(0) as i64;
+                let before = previous.extension_bytes.get(ext).copied().unwrap_or(0) as i64;
+                ExtensionDelta { extension: ext.clone(), bytes_delta: now - before }
+            })
+            .filter(|d| d.bytes_delta != 0)
+            .collect();
+        deltas.sort_by_key(|d| std::cmp::Reverse(d.bytes_delta.unsigned_abs()));
+
+        growth.previous_scanned_at = Some(previous.scanned_at.clone());
+        growth.file_count_delta = Some(current.total_files as i64 - previous.total_files as i64);
+        growth.bytes_delta = Some(current.total_bytes as i64 - previous.total_bytes as i64);
+        growth.bytes_added =
+            Some(deltas.iter().filter(|d| d.bytes_delta > 0).map(|d| d.bytes_delta as u64).sum());
+        growth.bytes_removed = Some(
+            deltas.iter().filter(|d| d.bytes_delta < 0).map(|d| d.bytes_delta.unsigned_abs()).sum(),
+        );
+        growth.extension_deltas = deltas;
+        growth
+    }
+}
@@ -0,0 +1,163 @@
+use super::types::{BalanceMetric, DiskClass, DiskProjection, DiskState};
+use crate::db::{Disk, PlannedMove};
+
+/// Array-wide target utilization: total used over total capacity.
+///
+/// Returns `None` when the disks have no capacity (e.g. nothing scanned yet).
+pub(crate) fn target_utilization(disks: &[Disk]) -> Option<f64> {
+    let total_used: u64 = disks.iter().map(|d| d.used_bytes).sum();
+    let total_capacity: u64 = disks.iter().map(|d| d.total_bytes).sum();
+    if total_capacity == 0 {
+        return None;
+    }
+    Some(total_used as f64 / total_capacity as f64)
+}
+
+/// What every disk is balanced toward.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BalanceTarget {
+    /// The same utilization on every disk.
+    Utilization(f64),
+    /// The same number of free bytes on every disk.
+    FreeBytes(u64),
+}
+
+impl BalanceTarget {
+    /// The utilization a disk of `total_bytes` sits at when it meets this target.
+    ///
+    /// A disk smaller than the free-bytes target can't meet it and is aimed at empty.
+    pub(crate) fn utilization_for(self, total_bytes: u64) -> f64 {
+        match self {
+            Self::Utilization(target) => target,
+            Self::FreeBytes(_) if total_bytes == 0 => 0.0,
+            Self::FreeBytes(free) => (1.0 - free as f64 / total_bytes as f64).max(0.0),
+        }
+    }
+}
+
+/// The array-wide target under `metric`: average utilization, or the array's
+/// free bytes split evenly across the disks.
+///
+/// Returns `None` when the disks have no capacity (e.g. nothing scanned yet).
+pub(crate) fn balance_target(disks: &[Disk], metric: BalanceMetric) -> Option<BalanceTarget> {
+    let average = target_utilization(disks)?;
+    Some(match metric {
+        BalanceMetric::Utilization => BalanceTarget::Utilization(average),
+        BalanceMetric::FreeBytes => {
+            let total_free: u64 =
+                disks.iter().map(|d| d.total_bytes.saturating_sub(d.used_bytes)).sum();
+            BalanceTarget::FreeBytes(total_free / disks.len() as u64)
+        }
+    })
+}
+
+/// Tolerance band after applying the balance slider.
+pub(crate) fn effective_tolerance(max_tolerance: f64, slider_alpha: f64) -> f64 {
+    max_tolerance * (1.0 - slider_alpha)
+}
+
+/// Largest deviation of any utilization from its disk's target, as `(utilization, target)` pairs.
+fn max_deviation(utilizations: impl Iterator<Item = (f64, f64)>) -> f64 {
+    utilizations.map(|(u, target)| (u - target).abs()).fold(0.0_f64, f64::max)
+}
+
+/// Largest deviation of any disk's current utilization from `target`.
+///
+/// Under [`BalanceTarget::FreeBytes`] this is the distance from the free-bytes
+/// target as a fraction of the disk, so tolerances mean the same under either metric.
+pub(crate) fn max_imbalance(disks: &[Disk], target: BalanceTarget) -> f64 {
+    max_deviation(disks.iter().map(|d| (d.utilization(), target.utilization_for(d.total_bytes))))
+}
+
+/// Largest deviation of any disk's simulated utilization from its target.
+pub(crate) fn sim_max_imbalance(disk_states: &[DiskState]) -> f64 {
+    max_deviation(disk_states.iter().map(|ds| (ds.sim_utilization(), ds.target)))
+}
+
+/// A 0–100 balance score for `(utilization, target)` pairs, 100 meaning every
+/// disk at its target.
+///
+/// Blends the worst and the average deviation, so one outlier costs less than
+/// the whole array being off. Each deviation is scaled by the largest one
+/// possible at its disk's target (the disk empty or full). Rounded to a whole number.
+pub(crate) fn balance_score(utilizations: impl Iterator<Item = (f64, f64)>) -> u8 {
+    let deviations: Vec<f64> = utilizations
+        .map(|(u, target)| {
+            let worst_possible = target.max(1.0 - target);
+            if worst_possible <= 0.0 {
+                0.0
+            } else {
+                (u - target).abs() / worst_possible
+            }
+        })
+        .collect();
+    if deviations.is_empty() {
+        return 100;
+    }
+    let max = deviations.iter().copied().fold(0.0_f64, f64::max);
+    let mean = deviations.iter().sum::<f64>() / deviations.len() as f64;
+    let score = 100.0 * (1.0 - f64::midpoint(max, mean));
+    score.clamp(0.0, 100.0).round() as u8
+}
+
+/// Classify a utilization value relative to the target and tolerance band.
+pub(crate) fn classify(utilization: f64, target: f64, tolerance: f64) -> DiskClass {
+    if utilization > target + tolerance {
+        DiskClass::OverUtilized
+    } else if utilization > target {
+        DiskClass::AboveAverage
+    } else if utilization < target - tolerance {
+        DiskClass::UnderUtilized
+    } else {
+        DiskClass::BelowAverage
+    }
+}
+
+/// Build the planner's working state for each disk, classified against the target.
+pub(crate) fn classify_disks(
+    disks: &[Disk],
+    target: BalanceTarget,
+    tolerance: f64,
+) -> Vec<DiskState> {
+    disks
+        .iter()
+        .map(|d| {
+            let disk_target = target.utilization_for(d.total_bytes);
+            DiskState {
+                disk: d.clone(),
+                class: classify(d.utilization(), disk_target, tolerance),
+                sim_used: d.used_bytes,
+                target: disk_target,
+            }
+        })
+        .collect()
+}
+
+/// Per-disk before/after utilization from the planner's final simulated state.
+///
+/// Bytes in and out are summed from `moves`; "before" is the disk's scanned usage.
+pub(crate) fn disk_projections(
+    disk_states: &[DiskState],
+    moves: &[PlannedMove],
+) -> Vec<DiskProjection> {
+    disk_states
+        .iter()
+        .map(|ds| DiskProjection {
+            disk_id: ds.disk.id,
+            name: ds.disk.disk_name.clone(),
+            before_util: ds.disk.utilization(),
+            after_util: ds.sim_utilization(),
+            target_util: ds.target,
+            bytes_in: moves
+                .iter()
+                .filter(|m| m.target_disk_id == ds.disk.id)
+                .map(|m| m.file_size)
+                .sum(),
+            bytes_out: moves
+                .iter()
+                .filter(|m| m.source_disk_id == ds.disk.id)
+                .map(|m| m.file_size)
+                .sum(),
+        })
+        .collect()
+}
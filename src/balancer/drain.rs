@@ -0,0 +1,156 @@
+use super::analysis::{
+    classify, classify_disks, disk_projections, max_imbalance, sim_max_imbalance, BalanceTarget,
+};
+use super::filesystems::crosses_fs_caveat;
+use super::planner::group_into_units;
+use super::types::{BalanceResult, CandidateOrder, DiskState, NoMovesReason, PlanOptions};
+use crate::db::{Database, Disk, MoveStatus, PlannedMove};
+use anyhow::{bail, Result};
+use tracing::{info, warn};
+
+/// Generate a plan that moves every cataloged file off one disk.
+///
+/// The drained disk is a forced source whatever its utilization; every other
+/// included disk outside `never_target_disks` (and, with `refuse_fs_mismatch`,
+/// on a compatible filesystem) is a target, and each move unit
+/// goes to the one with the most free space left after `min_free_headroom` and
+/// `absolute_min_free`. Fails
+/// without creating a plan when the remaining disks can't hold everything.
+pub(crate) fn generate_drain_plan(
+    db: &Database,
+    opts: &PlanOptions,
+    drain_disk_id: i64,
+) -> Result<BalanceResult> {
+    let Some(drain_disk) = db.get_disk(drain_disk_id)? else {
+        bail!("Disk {drain_disk_id} not found");
+    };
+    let targets: Vec<Disk> = db
+        .get_all_disks()?
+        .into_iter()
+        .filter(|d| {
+            d.id != drain_disk_id
+                && d.included
+                && !opts.excluded_disk_ids.contains(&d.id)
+                && !opts.never_target_disks.contains(&d.disk_name)
+                && !(opts.refuse_fs_mismatch && crosses_fs_caveat(&drain_disk, d))
+        })
+        .collect();
+    if targets.is_empty() {
+        bail!("No other included disks to drain {} onto", drain_disk.disk_name);
+    }
+
+    let mut files = db.get_all_files_on_disk_by_size(drain_disk_id)?;
+    let before = files.len();
+    // Moving one link of a hardlinked file would duplicate its data, and a
+    // followed symlink's cataloged size isn't what its move would carry.
+    files.retain(|f| f.nlink <= 1 && !f.followed_link);
+    if files.len() < before {
+        warn!(
+            "Drain of {} leaves {} hardlinked files and followed links in place",
+            drain_disk.disk_name,
+            before - files.len()
+        );
+    }
+    // Everything leaves the disk, so place the largest units while the most room is left.
+    let units = group_into_units(&files, opts.keep_folders_together, CandidateOrder::LargestFirst);
+
+    let reserved_free = opts.min_free_headroom.max(opts.absolute_min_free);
+    let total_bytes: u64 = units.iter().map(|u| u.total_bytes).sum();
+    let available: u64 = targets
+        .iter()
+        .map(|d| d.total_bytes.saturating_sub(d.used_bytes).saturating_sub(reserved_free))
+        .sum();
+    if total_bytes > available {
+        bail!(
+            "Not enough space to drain {}: {} bytes to move, {} bytes available on other \
+             disks, {} more bytes needed",
+            drain_disk.disk_name,
+            total_bytes,
+            available,
+            total_bytes - available
+        );
+    }
+
+    // Target utilization of the remaining disks once the drained data lands on them.
+    let used: u64 = targets.iter().map(|d| d.used_bytes).sum::<u64>() + total_bytes;
+    let capacity: u64 = targets.iter().map(|d| d.total_bytes).sum();
+    let target = if capacity == 0 { 0.0 } else { used as f64 / capacity as f64 };
+    let mut states = classify_disks(&targets, BalanceTarget::Utilization(target), 0.0);
+    let initial_imbalance = max_imbalance(&targets, BalanceTarget::Utilization(target));
+
+    let mut planned_moves: Vec<PlannedMove> = Vec::new();
+    let mut move_order: i32 = 0;
+    for unit in &units {
+        let Some(tgt_idx) = (0..states.len())
+            .filter(|&i| states[i].sim_free().saturating_sub(reserved_free) >= unit.total_bytes)
+            .max_by_key(|&i| states[i].sim_free())
+        else {
+            bail!(
+                "Cannot drain {}: no single disk has room for {} ({} bytes)",
+                drain_disk.disk_name,
+                unit.files[0].file_path,
+                unit.total_bytes
+            );
+        };
+        for file in &unit.files {
+            move_order += 1;
+            planned_moves.push(PlannedMove {
+                id: 0,
+                plan_id: 0,
+                source_disk_id: drain_disk_id,
+                target_disk_id: states[tgt_idx].disk.id,
+                file_path: file.file_path.clone(),
+                file_size: file.size_bytes,
+                move_order,
+                phase: 1,
+                status: MoveStatus::Pending,
+                error_message: None,
+                source_mtime: file.mtime,
+                target_path: None,
+            });
+        }
+        states[tgt_idx].sim_used = states[tgt_idx].sim_used.saturating_add(unit.total_bytes);
+    }
+    let projected_imbalance = sim_max_imbalance(&states);
+    states.push(DiskState {
+        class: classify(drain_disk.utilization(), target, 0.0),
+        sim_used: drain_disk.used_bytes.saturating_sub(total_bytes),
+        target,
+        disk: drain_disk.clone(),
+    });
+
+    let plan_id = db.create_plan(0.0, opts.slider_alpha, target, initial_imbalance)?;
+    for m in &mut planned_moves {
+        m.plan_id = plan_id;
+    }
+    if !planned_moves.is_empty() {
+        db.insert_planned_moves(&planned_moves)?;
+    }
+    db.update_plan_projections(
+        plan_id,
+        projected_imbalance,
+        planned_moves.len() as i32,
+        total_bytes,
+    )?;
+
+    info!(
+        "Drain plan {} generated for {}: {} moves, {} bytes",
+        plan_id,
+        drain_disk.disk_name,
+        planned_moves.len(),
+        total_bytes
+    );
+
+    Ok(BalanceResult {
+        plan_id,
+        target_utilization: target,
+        initial_imbalance,
+        projected_imbalance,
+        total_moves: planned_moves.len(),
+        total_bytes,
+        no_moves_reason: planned_moves.is_empty().then_some(NoMovesReason::NoCandidates),
+        capped: false,
+        disk_projections: disk_projections(&states, &planned_moves),
+        split_folders: Vec::new(),
+    })
+}
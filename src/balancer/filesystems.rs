@@ -0,0 +1,47 @@
+use crate::db::{Disk, DiskFlow};
+use std::collections::HashMap;
+
+/// What a file loses moving from a `source` filesystem to a `target` of a
+/// different type, or `None` when the types match, either is unknown, or the
+/// pair has no known caveat.
+///
+/// Types are the mount table's names, compared case-insensitively.
+pub(crate) fn fs_caveat(source: Option<&str>, target: Option<&str>) -> Option<&'static str> {
+    let (source, target) = (source?, target?);
+    if source.eq_ignore_ascii_case(target) {
+        return None;
+    }
+    match source.to_ascii_lowercase().as_str() {
+        "btrfs" => Some(
+            "reflinked and snapshot-shared extents are copied in full and btrfs compression is lost",
+        ),
+        "zfs" => Some("ZFS compression and dedup are lost, so files may take more space"),
+        "xfs" => Some("reflinked extents are copied in full"),
+        _ => None,
+    }
+}
+
+/// Whether a move between these two disks crosses filesystems with a known caveat.
+pub(crate) fn crosses_fs_caveat(source: &Disk, target: &Disk) -> bool {
+    fs_caveat(source.filesystem.as_deref(), target.filesystem.as_deref()).is_some()
+}
+
+/// One warning per disk pair in `flows` whose filesystems differ with a caveat,
+/// e.g. "disk1 (xfs) → disk2 (btrfs), 12 moves: reflinked extents are copied in full".
+pub(crate) fn fs_mismatch_warnings(flows: &[DiskFlow], disks: &[Disk]) -> Vec<String> {
+    let by_id: HashMap<i64, &Disk> = disks.iter().map(|d| (d.id, d)).collect();
+    flows
+        .iter()
+        .filter_map(|flow| {
+            let source = by_id.get(&flow.source_disk_id)?;
+            let target = by_id.get(&flow.target_disk_id)?;
+            let (source_fs, target_fs) =
+                (source.filesystem.as_deref()?, target.filesystem.as_deref()?);
+            let caveat = fs_caveat(Some(source_fs), Some(target_fs))?;
+            Some(format!(
+                "{} ({source_fs}) → {} ({target_fs}), {} moves: {caveat}",
+                source.disk_name, target.disk_name, flow.moves
+            ))
+        })
+        .collect()
+}
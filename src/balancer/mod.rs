@@ -1,4 +1,14 @@
+pub(crate) mod analysis;
+mod drain;
+pub(crate) mod filesystems;
 mod planner;
+mod rollback;
 pub(crate) mod types;
 
+pub(crate) use drain::generate_drain_plan;
 pub(crate) use planner::generate_plan;
+pub(crate) use rollback::generate_rollback_plan;
+pub(crate) use types::{
+    BalanceMetric, CandidateOrder, NoMovesReason, PlanOptions, SizeThresholds, TargetStrategy,
+    TooFewDisks,
+};
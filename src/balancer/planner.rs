@@ -1,69 +1,92 @@
-use super::types::{BalanceResult, DiskClass, DiskState};
+use super::analysis::{
+    balance_target, classify_disks, disk_projections, effective_tolerance, sim_max_imbalance,
+    target_utilization, BalanceTarget,
+};
+use super::filesystems::crosses_fs_caveat;
+use super::types::{
+    BalanceResult, CandidateOrder, DiskClass, DiskState, MoveUnit, NoMovesReason, PlanOptions,
+    TargetStrategy, TooFewDisks, MIN_INCLUDED_DISKS,
+};
 use crate::db::{Database, Disk, FileEntry, MoveStatus, PlannedMove};
 use anyhow::{bail, Result};
-use std::collections::HashMap;
-use tracing::info;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing::{info, warn};
 
 /// Shared context for the move assignment phase.
 struct PlanContext {
     plan_id: i64,
-    target_utilization: f64,
     effective_tolerance: f64,
-    min_free_headroom: u64,
+    /// Free bytes to keep on a target: the larger of headroom and the hard floor.
+    reserved_free: u64,
     disk_idx: HashMap<i64, usize>,
+    /// Disks that may shed files but never receive them.
+    never_target: HashSet<i64>,
+    /// Disks known to be spun down; penalized as targets when non-empty.
+    spun_down: HashSet<i64>,
+    target_strategy: TargetStrategy,
+    max_moves: Option<usize>,
+    refuse_fs_mismatch: bool,
 }
 
-/// Compute the maximum deviation from target utilization across all disks.
-fn max_imbalance(disk_states: &[DiskState], target: f64) -> f64 {
-    disk_states.iter().map(|ds| (ds.sim_utilization() - target).abs()).fold(0.0_f64, f64::max)
-}
+/// Fraction of a disk's capacity deducted from a spun-down target's score, so
+/// an already-spinning disk wins unless the parked one is clearly better.
+const SPUN_DOWN_PENALTY: f64 = 0.05;
 
-/// Check if all disks are within tolerance of the target utilization.
-fn is_balanced(disk_states: &[DiskState], target: f64, tolerance: f64) -> bool {
-    disk_states.iter().all(|ds| (ds.sim_utilization() - target).abs() <= tolerance)
+/// Check if all disks are within tolerance of their target utilization.
+fn is_balanced(disk_states: &[DiskState], tolerance: f64) -> bool {
+    disk_states.iter().all(|ds| (ds.sim_utilization() - ds.target).abs() <= tolerance)
 }
 
 /// Generate a balance plan.
 ///
-/// `slider_alpha` ranges from 0.0 (fewest moves / high tolerance) to 1.0 (perfect balance).
-/// `max_tolerance` is the maximum tolerance (e.g., 0.15 for 15%).
-/// `min_free_headroom` is the minimum bytes to leave free on any disk.
-pub(crate) fn generate_plan(
-    db: &Database,
-    slider_alpha: f64,
-    max_tolerance: f64,
-    min_free_headroom: u64,
-    excluded_disk_ids: &[i64],
-) -> Result<BalanceResult> {
+/// See [`PlanOptions`] for the meaning of each tuning knob.
+pub(crate) fn generate_plan(db: &Database, opts: &PlanOptions) -> Result<BalanceResult> {
+    let slider_alpha = opts.slider_alpha;
     let all_disks = db.get_all_disks()?;
     let disks: Vec<Disk> = all_disks
         .into_iter()
-        .filter(|d| d.included && !excluded_disk_ids.contains(&d.id))
+        .filter(|d| d.included && !opts.excluded_disk_ids.contains(&d.id))
         .collect();
 
-    if disks.len() < 2 {
-        bail!("Need at least 2 included disks to balance");
+    if disks.len() < MIN_INCLUDED_DISKS {
+        return Err(
+            TooFewDisks { included: disks.into_iter().map(|d| d.disk_name).collect() }.into()
+        );
     }
 
-    let total_used: u64 = disks.iter().map(|d| d.used_bytes).sum();
-    let total_capacity: u64 = disks.iter().map(|d| d.total_bytes).sum();
-
-    if total_capacity == 0 {
+    let (Some(average_utilization), Some(metric_target)) =
+        (target_utilization(&disks), balance_target(&disks, opts.balance_metric))
+    else {
         bail!("Total disk capacity is zero");
-    }
-
-    let target_utilization = total_used as f64 / total_capacity as f64;
-    let effective_tolerance = max_tolerance * (1.0 - slider_alpha);
+    };
+    // An explicit target utilization overrides the metric for this plan.
+    let (target_utilization, target) = match opts.target_utilization_override {
+        Some(target) => {
+            let min_util = disks.iter().map(Disk::utilization).fold(f64::INFINITY, f64::min);
+            if target < min_util {
+                warn!(
+                    "Target utilization {:.2}% is below the emptiest disk ({:.2}%); \
+                     it can't be reached without adding disks",
+                    target * 100.0,
+                    min_util * 100.0
+                );
+            }
+            (target, BalanceTarget::Utilization(target))
+        }
+        None => (average_utilization, metric_target),
+    };
+    let effective_tolerance = effective_tolerance(opts.max_tolerance, slider_alpha);
 
     info!(
-        "Balance planning: target_utilization={:.2}%, tolerance={:.2}%, alpha={:.2}",
-        target_utilization * 100.0,
+        "Balance planning: target={:?}, tolerance={:.2}%, alpha={:.2}",
+        target,
         effective_tolerance * 100.0,
         slider_alpha
     );
 
-    let mut disk_states = classify_disks(&disks, target_utilization, effective_tolerance);
-    let initial_imbalance = max_imbalance(&disk_states, target_utilization);
+    let mut disk_states = classify_disks(&disks, target, effective_tolerance);
+    let initial_imbalance = sim_max_imbalance(&disk_states);
 
     let has_outer = disk_states
         .iter()
@@ -86,26 +109,62 @@ pub(crate) fn generate_plan(
             projected_imbalance: initial_imbalance,
             total_moves: 0,
             total_bytes: 0,
+            no_moves_reason: Some(NoMovesReason::AlreadyBalanced),
+            capped: false,
+            disk_projections: disk_projections(&disk_states, &[]),
+            split_folders: Vec::new(),
         });
     }
 
     let plan_id =
         db.create_plan(effective_tolerance, slider_alpha, target_utilization, initial_imbalance)?;
 
-    let candidate_files = collect_candidates(db, &disk_states)?;
+    let (candidate_files, filtered) = collect_candidates(db, &disk_states, opts)?;
+    let mut units =
+        group_into_units(&candidate_files, opts.keep_folders_together, opts.candidate_order);
 
     let plan_ctx = PlanContext {
         plan_id,
-        target_utilization,
         effective_tolerance,
-        min_free_headroom,
+        reserved_free: opts.min_free_headroom.max(opts.absolute_min_free),
         disk_idx: disk_states.iter().enumerate().map(|(i, ds)| (ds.disk.id, i)).collect(),
+        never_target: disk_states
+            .iter()
+            .filter(|ds| opts.never_target_disks.contains(&ds.disk.disk_name))
+            .map(|ds| ds.disk.id)
+            .collect(),
+        spun_down: disk_states
+            .iter()
+            .filter(|ds| opts.spun_down_disks.contains(&ds.disk.disk_name))
+            .map(|ds| ds.disk.id)
+            .collect(),
+        target_strategy: opts.target_strategy,
+        max_moves: opts.max_moves,
+        refuse_fs_mismatch: opts.refuse_fs_mismatch,
     };
 
-    let (planned_moves, total_bytes_to_move) =
-        assign_moves(&plan_ctx, &candidate_files, &mut disk_states);
-
-    let projected_imbalance = max_imbalance(&disk_states, target_utilization);
+    if opts.target_strategy == TargetStrategy::MinBytes {
+        units = min_bytes_order(&plan_ctx, units, &disk_states);
+    }
+    let (planned_moves, total_bytes_to_move, capped, split_folders) =
+        assign_moves(&plan_ctx, &units, &mut disk_states);
+
+    let projected_imbalance = sim_max_imbalance(&disk_states);
+
+    let no_moves_reason = if !planned_moves.is_empty() {
+        None
+    } else if units.is_empty() && filtered > 0 {
+        Some(NoMovesReason::AllFiltered)
+    } else if units.is_empty() {
+        Some(NoMovesReason::NoCandidates)
+    } else if capped {
+        Some(NoMovesReason::MoveCapTooSmall)
+    } else {
+        Some(NoMovesReason::NoTargetFits)
+    };
+    if let Some(reason) = no_moves_reason {
+        info!("Plan has no moves: {:?}", reason);
+    }
 
     info!(
         "Plan generated: {} moves, {} bytes, imbalance {:.2}% -> {:.2}%",
@@ -133,140 +192,425 @@ pub(crate) fn generate_plan(
         projected_imbalance,
         total_moves: planned_moves.len(),
         total_bytes: total_bytes_to_move,
+        no_moves_reason,
+        capped,
+        disk_projections: disk_projections(&disk_states, &planned_moves),
+        split_folders,
     })
 }
 
-fn classify_disks(
-    disks: &[Disk],
-    target_utilization: f64,
-    effective_tolerance: f64,
-) -> Vec<DiskState> {
-    disks
+/// Files eligible to move off the over-utilized disks, in candidate order,
+/// and how many of those disks' files the filters turned away.
+fn collect_candidates(
+    db: &Database,
+    disk_states: &[DiskState],
+    opts: &PlanOptions,
+) -> Result<(Vec<FileEntry>, usize)> {
+    let over_disks = disk_states
         .iter()
-        .map(|d| {
-            let utilization = d.utilization();
-
-            let class = if utilization > target_utilization + effective_tolerance {
-                DiskClass::OverUtilized
-            } else if utilization > target_utilization {
-                DiskClass::AboveAverage
-            } else if utilization < target_utilization - effective_tolerance {
-                DiskClass::UnderUtilized
-            } else {
-                DiskClass::BelowAverage
-            };
+        .filter(|ds| ds.class == DiskClass::OverUtilized || ds.class == DiskClass::AboveAverage)
+        .map(|ds| &ds.disk);
+
+    let mut candidate_files: Vec<FileEntry> = Vec::new();
+    let mut hardlinked = 0usize;
+    let mut followed_links = 0usize;
+    let mut below_threshold = 0usize;
+    let mut too_recent = 0usize;
+    let age_cutoff = opts
+        .min_file_age_days
+        .map(|days| chrono::Utc::now().timestamp() - (days as i64).saturating_mul(86_400));
+    let mut seen = 0usize;
+    for disk in over_disks {
+        let files = db.get_all_files_on_disk_by_size(disk.id)?;
+        seen += files.len();
+        candidate_files.extend(files.into_iter().filter(|f| {
+            // Moving one link of a hardlinked file would duplicate its data.
+            if f.nlink > 1 {
+                hardlinked += 1;
+                return false;
+            }
+            // Cataloged at its target's size, but a move would carry only the link.
+            if f.followed_link {
+                followed_links += 1;
+                return false;
+            }
+            if !opts.size_thresholds.allows(&f.extension(), f.size_bytes) {
+                below_threshold += 1;
+                return false;
+            }
+            if let Some(cutoff) = age_cutoff {
+                let old_enough = f.mtime.map_or(opts.move_undated_files, |m| m <= cutoff);
+                if !old_enough {
+                    too_recent += 1;
+                    return false;
+                }
+            }
+            is_included(f, &opts.include_roots)
+                && !is_pinned(&disk.mount_path, f, &opts.pinned_paths)
+        }));
+    }
+    if hardlinked > 0 {
+        info!("Skipped {} hardlinked files as move candidates", hardlinked);
+    }
+    if followed_links > 0 {
+        info!("Skipped {} followed symlinks as move candidates", followed_links);
+    }
+    if below_threshold > 0 {
+        info!("Skipped {} files below their type's move size threshold", below_threshold);
+    }
+    if too_recent > 0 {
+        info!("Skipped {} files newer than the minimum file age", too_recent);
+    }
 
-            DiskState { disk: d.clone(), class, sim_used: d.used_bytes }
-        })
-        .collect()
+    // Stable sorts: files tied on mtime keep the catalog's largest-first order.
+    match opts.candidate_order {
+        CandidateOrder::LargestFirst => {
+            candidate_files.sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+        }
+        // `None` sorts before any `Some`, so files without an mtime count as oldest.
+        CandidateOrder::OldestFirst => candidate_files.sort_by_key(|f| f.mtime),
+        CandidateOrder::NewestFirst => {
+            candidate_files.sort_by_key(|f| std::cmp::Reverse(f.mtime));
+        }
+    }
+    let filtered = seen - candidate_files.len();
+    Ok((candidate_files, filtered))
 }
 
-fn collect_candidates(db: &Database, disk_states: &[DiskState]) -> Result<Vec<FileEntry>> {
-    let over_disk_ids: Vec<i64> = disk_states
-        .iter()
-        .filter(|ds| ds.class == DiskClass::OverUtilized || ds.class == DiskClass::AboveAverage)
-        .map(|ds| ds.disk.id)
-        .collect();
+/// Whether a file lies under one of the include roots (always true when there are none).
+///
+/// Guards against a catalog scanned before the roots were narrowed.
+fn is_included(file: &FileEntry, include_roots: &[String]) -> bool {
+    include_roots.is_empty()
+        || include_roots.iter().any(|root| Path::new(&file.file_path).starts_with(root))
+}
 
-    let mut candidate_files: Vec<FileEntry> = Vec::new();
-    for disk_id in &over_disk_ids {
-        let files = db.get_all_files_on_disk_by_size(*disk_id)?;
-        candidate_files.extend(files);
+/// Whether a file lies under one of the pinned path prefixes.
+///
+/// Absolute prefixes are matched against `mount/file_path`, relative ones against
+/// the disk-relative path. Matching is per path component, so pinning `appdata`
+/// leaves `appdata-old` eligible.
+fn is_pinned(mount_path: &str, file: &FileEntry, pinned_paths: &[String]) -> bool {
+    let relative = Path::new(&file.file_path);
+    let full = Path::new(mount_path).join(relative);
+    pinned_paths.iter().any(|prefix| {
+        let prefix = Path::new(prefix);
+        if prefix.is_absolute() {
+            full.starts_with(prefix)
+        } else {
+            relative.starts_with(prefix)
+        }
+    })
+}
+
+/// Group candidate files into move units.
+///
+/// With `keep_folders_together`, files sharing a parent directory on the same
+/// disk form one unit so a folder is never split across targets. Files at the
+/// disk root have no meaningful folder and stay individual units. Folder units
+/// are ordered largest first under [`CandidateOrder::LargestFirst`], otherwise
+/// by where their first file falls in `candidate_files`.
+pub(super) fn group_into_units(
+    candidate_files: &[FileEntry],
+    keep_folders_together: bool,
+    order: CandidateOrder,
+) -> Vec<MoveUnit<'_>> {
+    if !keep_folders_together {
+        return candidate_files
+            .iter()
+            .map(|f| MoveUnit { disk_id: f.disk_id, files: vec![f], total_bytes: f.size_bytes })
+            .collect();
+    }
+
+    let mut units: Vec<MoveUnit<'_>> = Vec::new();
+    let mut unit_idx: HashMap<(i64, &str), usize> = HashMap::new();
+
+    for file in candidate_files {
+        let parent = Path::new(&file.file_path).parent().and_then(Path::to_str).unwrap_or("");
+        if parent.is_empty() {
+            units.push(MoveUnit {
+                disk_id: file.disk_id,
+                files: vec![file],
+                total_bytes: file.size_bytes,
+            });
+            continue;
+        }
+
+        let idx = *unit_idx.entry((file.disk_id, parent)).or_insert_with(|| {
+            units.push(MoveUnit { disk_id: file.disk_id, files: Vec::new(), total_bytes: 0 });
+            units.len() - 1
+        });
+        units[idx].files.push(file);
+        units[idx].total_bytes += file.size_bytes;
     }
 
-    candidate_files.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
-    Ok(candidate_files)
+    if order == CandidateOrder::LargestFirst {
+        units.sort_by_key(|u| std::cmp::Reverse(u.total_bytes));
+    }
+    units
 }
 
+/// Reorder units so each over-utilized source sheds close to the minimum bytes.
+///
+/// For every source above the tolerance band, with `excess` bytes left to shed:
+/// take its smallest unit that covers the excess on its own, otherwise its
+/// largest unit that doesn't, and repeat. Those picks go first; the remaining
+/// units follow in their original order so [`assign_moves`] can still fall back
+/// on them when a picked unit fits no target. Units of a source already back
+/// in band are skipped by `assign_moves`, so the fallbacks cost nothing when
+/// the picks all land.
+fn min_bytes_order<'a>(
+    ctx: &PlanContext,
+    units: Vec<MoveUnit<'a>>,
+    disk_states: &[DiskState],
+) -> Vec<MoveUnit<'a>> {
+    let mut picked: Vec<usize> = Vec::new();
+    for ds in disk_states {
+        let upper = ds.target + ctx.effective_tolerance;
+        // Flooring the band edge can only overstate the excess, never leave a source above it.
+        let mut excess = ds.sim_used.saturating_sub((upper * ds.disk.total_bytes as f64) as u64);
+        let mut own: Vec<usize> =
+            (0..units.len()).filter(|&i| units[i].disk_id == ds.disk.id).collect();
+        own.sort_by_key(|&i| units[i].total_bytes);
+        while excess > 0 {
+            // `own` is ascending, so with no unit covering the excess the last is the largest below it.
+            let Some(pos) = own
+                .iter()
+                .position(|&i| units[i].total_bytes >= excess)
+                .or_else(|| own.len().checked_sub(1))
+            else {
+                break;
+            };
+            let idx = own.remove(pos);
+            excess = excess.saturating_sub(units[idx].total_bytes);
+            picked.push(idx);
+        }
+    }
+
+    let mut slots: Vec<Option<MoveUnit<'a>>> = units.into_iter().map(Some).collect();
+    let mut ordered: Vec<MoveUnit<'a>> = picked.iter().filter_map(|&i| slots[i].take()).collect();
+    ordered.extend(slots.into_iter().flatten());
+    ordered
+}
+
+/// Place units on targets until balanced, returning the moves, their total
+/// bytes, whether `max_moves` cut planning short of balance, and a warning
+/// for each folder that had to be split.
+///
+/// A unit that would overshoot the cap is passed over so a smaller one may
+/// still fit; planning stops once the cap is reached exactly. A folder that
+/// fits no single target is spread over as few as possible by [`split_unit`].
 fn assign_moves(
     ctx: &PlanContext,
-    candidate_files: &[FileEntry],
+    units: &[MoveUnit<'_>],
     disk_states: &mut [DiskState],
-) -> (Vec<PlannedMove>, u64) {
+) -> (Vec<PlannedMove>, u64, bool, Vec<String>) {
     let mut planned_moves: Vec<PlannedMove> = Vec::new();
     let mut total_bytes_to_move: u64 = 0;
     let mut move_order: i32 = 0;
+    let mut last_target: Option<usize> = None;
+    let mut hit_cap = false;
+    let mut split_folders: Vec<String> = Vec::new();
 
-    for file in candidate_files {
-        let Some(&src_idx) = ctx.disk_idx.get(&file.disk_id) else {
+    for unit in units {
+        let Some(&src_idx) = ctx.disk_idx.get(&unit.disk_id) else {
             continue;
         };
 
         let src_util = disk_states[src_idx].sim_utilization();
-        if src_util <= ctx.target_utilization + ctx.effective_tolerance {
+        if src_util <= disk_states[src_idx].target + ctx.effective_tolerance {
             continue;
         }
+        if let Some(max) = ctx.max_moves {
+            if planned_moves.len() + unit.files.len() > max {
+                hit_cap = true;
+                if planned_moves.len() >= max {
+                    info!("Stopping at the cap of {} moves", max);
+                    break;
+                }
+                continue;
+            }
+        }
 
         let best_target =
-            find_best_target(disk_states, file, ctx.target_utilization, ctx.min_free_headroom);
+            find_best_target(ctx, disk_states, unit.disk_id, unit.total_bytes, last_target);
+
+        let placements = if let Some(tgt_idx) = best_target {
+            last_target = Some(tgt_idx);
+            vec![(tgt_idx, unit.files.clone())]
+        } else if unit.files.len() > 1 {
+            let placements = split_unit(ctx, disk_states, unit).unwrap_or_default();
+            if !placements.is_empty() {
+                let folder = Path::new(&unit.files[0].file_path)
+                    .parent()
+                    .and_then(Path::to_str)
+                    .unwrap_or("");
+                let targets: Vec<&str> = placements
+                    .iter()
+                    .map(|(i, _)| disk_states[*i].disk.disk_name.as_str())
+                    .collect();
+                let warning = format!(
+                    "{folder} on {} fits no single disk and was split across {}",
+                    disk_states[src_idx].disk.disk_name,
+                    targets.join(", ")
+                );
+                warn!("{}", warning);
+                split_folders.push(warning);
+            }
+            placements
+        } else {
+            Vec::new()
+        };
 
-        if let Some(tgt_idx) = best_target {
-            move_order += 1;
+        for (tgt_idx, files) in placements {
             let target_disk_id = disk_states[tgt_idx].disk.id;
-
-            planned_moves.push(PlannedMove {
-                id: 0,
-                plan_id: ctx.plan_id,
-                source_disk_id: file.disk_id,
-                target_disk_id,
-                file_path: file.file_path.clone(),
-                file_size: file.size_bytes,
-                move_order,
-                phase: 1,
-                status: MoveStatus::Pending,
-                error_message: None,
-                source_mtime: file.mtime,
-            });
-
-            disk_states[src_idx].sim_used =
-                disk_states[src_idx].sim_used.saturating_sub(file.size_bytes);
+            let bytes: u64 = files.iter().map(|f| f.size_bytes).sum();
+
+            for file in files {
+                move_order += 1;
+                planned_moves.push(PlannedMove {
+                    id: 0,
+                    plan_id: ctx.plan_id,
+                    source_disk_id: file.disk_id,
+                    target_disk_id,
+                    file_path: file.file_path.clone(),
+                    file_size: file.size_bytes,
+                    move_order,
+                    phase: 1,
+                    status: MoveStatus::Pending,
+                    error_message: None,
+                    source_mtime: file.mtime,
+                    target_path: None,
+                });
+            }
+
+            disk_states[src_idx].sim_used = disk_states[src_idx].sim_used.saturating_sub(bytes);
             disk_states[tgt_idx].sim_used = disk_states[tgt_idx]
                 .sim_used
-                .saturating_add(file.size_bytes)
+                .saturating_add(bytes)
                 .min(disk_states[tgt_idx].disk.total_bytes);
-            total_bytes_to_move += file.size_bytes;
+            total_bytes_to_move += bytes;
         }
 
-        if is_balanced(disk_states, ctx.target_utilization, ctx.effective_tolerance) {
+        if is_balanced(disk_states, ctx.effective_tolerance) {
             info!("All disks within tolerance after {} moves", planned_moves.len());
-            break;
+            // Units passed over for the cap didn't matter in the end.
+            return (planned_moves, total_bytes_to_move, false, split_folders);
         }
     }
 
-    (planned_moves, total_bytes_to_move)
+    (planned_moves, total_bytes_to_move, hit_cap, split_folders)
 }
 
-fn find_best_target(
+/// Spread a folder that fits no single target over as few targets as possible.
+///
+/// Targets are filled roomiest first, each taking the largest remaining files
+/// that still fit in its room. Returns each target used with its files, or
+/// `None` when the folder doesn't fit even across every eligible target.
+fn split_unit<'a>(
+    ctx: &PlanContext,
     disk_states: &[DiskState],
-    file: &FileEntry,
-    target_utilization: f64,
-    min_free_headroom: u64,
-) -> Option<usize> {
-    let mut best_target: Option<usize> = None;
-    let mut best_remaining = i64::MIN;
+    unit: &MoveUnit<'a>,
+) -> Option<Vec<(usize, Vec<&'a FileEntry>)>> {
+    let mut targets: Vec<usize> = (0..disk_states.len())
+        .filter(|&i| can_receive(ctx, disk_states, i, unit.disk_id))
+        .collect();
+    targets.sort_by_key(|&i| std::cmp::Reverse(room(ctx, &disk_states[i])));
 
-    for (i, ds) in disk_states.iter().enumerate() {
-        if ds.disk.id == file.disk_id {
-            continue;
+    let mut remaining = unit.files.clone();
+    remaining.sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+    let mut placements = Vec::new();
+    for i in targets {
+        if remaining.is_empty() {
+            break;
         }
-
-        if ds.sim_utilization() >= target_utilization {
-            continue;
+        let mut room = room(ctx, &disk_states[i]);
+        let (taken, rest): (Vec<&FileEntry>, Vec<&FileEntry>) =
+            remaining.into_iter().partition(|f| {
+                let fits = f.size_bytes <= room;
+                if fits {
+                    room -= f.size_bytes;
+                }
+                fits
+            });
+        remaining = rest;
+        if !taken.is_empty() {
+            placements.push((i, taken));
         }
+    }
+    remaining.is_empty().then_some(placements)
+}
 
-        let available = ds.sim_free().saturating_sub(min_free_headroom);
-        if available < file.size_bytes {
-            continue;
-        }
+/// Bytes a target can take before dropping below its reserved free space.
+const fn room(ctx: &PlanContext, ds: &DiskState) -> u64 {
+    ds.sim_free().saturating_sub(ctx.reserved_free)
+}
+
+/// Whether disk `i` may receive files from `source_disk_id` at all, regardless of size.
+fn can_receive(
+    ctx: &PlanContext,
+    disk_states: &[DiskState],
+    i: usize,
+    source_disk_id: i64,
+) -> bool {
+    let ds = &disk_states[i];
+    let source = ctx.disk_idx.get(&source_disk_id).map(|&s| &disk_states[s].disk);
+    ds.disk.id != source_disk_id
+        && !ctx.never_target.contains(&ds.disk.id)
+        && !(ctx.refuse_fs_mismatch && source.is_some_and(|s| crosses_fs_caveat(s, &ds.disk)))
+        && ds.sim_utilization() < ds.target
+}
 
-        let target_used = (target_utilization * ds.disk.total_bytes as f64) as u64;
-        let remaining = target_used as i64 - ds.sim_used as i64;
+/// Pick a target disk for a unit according to the configured [`TargetStrategy`].
+///
+/// `last_target` is the disk chosen for the previous unit; `Emptiest` keeps
+/// filling it while it is still eligible and `RoundRobin` continues after it.
+fn find_best_target(
+    ctx: &PlanContext,
+    disk_states: &[DiskState],
+    source_disk_id: i64,
+    size_bytes: u64,
+    last_target: Option<usize>,
+) -> Option<usize> {
+    let eligible = |i: &usize| {
+        can_receive(ctx, disk_states, *i, source_disk_id)
+            && room(ctx, &disk_states[*i]) >= size_bytes
+    };
+    let penalty = |ds: &DiskState| {
+        if ctx.spun_down.contains(&ds.disk.id) {
+            SPUN_DOWN_PENALTY
+        } else {
+            0.0
+        }
+    };
 
-        if remaining > best_remaining {
-            best_remaining = remaining;
-            best_target = Some(i);
+    match ctx.target_strategy {
+        TargetStrategy::MostHeadroom | TargetStrategy::MinBytes => {
+            let mut best_target: Option<usize> = None;
+            let mut best_remaining = i64::MIN;
+            for i in (0..disk_states.len()).filter(eligible) {
+                let ds = &disk_states[i];
+                let target_used = (ds.target * ds.disk.total_bytes as f64) as u64;
+                let remaining = target_used as i64
+                    - ds.sim_used as i64
+                    - (penalty(ds) * ds.disk.total_bytes as f64) as i64;
+                if remaining > best_remaining {
+                    best_remaining = remaining;
+                    best_target = Some(i);
+                }
+            }
+            best_target
+        }
+        TargetStrategy::Emptiest => last_target.filter(eligible).or_else(|| {
+            (0..disk_states.len()).filter(eligible).min_by(|&a, &b| {
+                let score = |i: usize| disk_states[i].sim_utilization() + penalty(&disk_states[i]);
+                score(a).total_cmp(&score(b))
+            })
+        }),
+        TargetStrategy::RoundRobin => {
+            let n = disk_states.len();
+            let start = last_target.map_or(0, |i| i + 1);
+            (0..n).map(|k| (start + k) % n).find(eligible)
         }
     }
-
-    best_target
 }
@@ -0,0 +1,141 @@
+use super::analysis::{
+    classify_disks, disk_projections, max_imbalance, sim_max_imbalance, target_utilization,
+    BalanceTarget,
+};
+use super::types::DiskState;
+use super::types::{BalanceResult, NoMovesReason};
+use crate::db::{Database, Disk, MoveStatus, PlanStatus, PlannedMove};
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Generate a plan that undoes the completed moves of an earlier plan.
+///
+/// Each `Completed` move is reversed (target → source) as long as the file is
+/// still on its new disk with the size it was moved at. The balance algorithm
+/// is not re-run; the new plan executes like any other.
+pub(crate) fn generate_rollback_plan(
+    db: &Database,
+    original_plan_id: i64,
+) -> Result<BalanceResult> {
+    let Some(original) = db.get_plan(original_plan_id)? else {
+        bail!("Plan {original_plan_id} not found");
+    };
+    if matches!(original.status, PlanStatus::Planned | PlanStatus::Executing) {
+        bail!("Plan {original_plan_id} is '{}' and has nothing to roll back", original.status);
+    }
+
+    let all_disks = db.get_all_disks()?;
+    let disk_mounts: HashMap<i64, &str> =
+        all_disks.iter().map(|d| (d.id, d.mount_path.as_str())).collect();
+
+    let mut move_order: i32 = 0;
+    let mut reversed: Vec<PlannedMove> = Vec::new();
+    for detail in db.get_plan_moves(original_plan_id)? {
+        let m = detail.move_info;
+        if m.status != MoveStatus::Completed {
+            continue;
+        }
+        let Some(meta) = unchanged_at_target(&disk_mounts, &m) else {
+            continue;
+        };
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        move_order += 1;
+        reversed.push(PlannedMove {
+            id: 0,
+            plan_id: 0,
+            source_disk_id: m.target_disk_id,
+            target_disk_id: m.source_disk_id,
+            file_path: m.destination_path().to_string(),
+            file_size: m.file_size,
+            move_order,
+            phase: 1,
+            status: MoveStatus::Pending,
+            error_message: None,
+            source_mtime: mtime,
+            // Pinned, so the way back never goes through `PATH_REMAP` again.
+            target_path: Some(m.file_path),
+        });
+    }
+
+    let disks: Vec<Disk> = all_disks.into_iter().filter(|d| d.included).collect();
+    let target = target_utilization(&disks).unwrap_or(original.target_utilization);
+    let initial_imbalance = max_imbalance(&disks, BalanceTarget::Utilization(target));
+    let states = simulate_moves(&disks, target, &reversed);
+    let projected_imbalance = sim_max_imbalance(&states);
+    let total_bytes: u64 = reversed.iter().map(|m| m.file_size).sum();
+
+    let plan_id =
+        db.create_plan(original.tolerance, original.slider_alpha, target, initial_imbalance)?;
+    for m in &mut reversed {
+        m.plan_id = plan_id;
+    }
+    if !reversed.is_empty() {
+        db.insert_planned_moves(&reversed)?;
+    }
+    db.update_plan_projections(plan_id, projected_imbalance, reversed.len() as i32, total_bytes)?;
+
+    info!(
+        "Rollback plan {} generated for plan {}: {} moves, {} bytes",
+        plan_id,
+        original_plan_id,
+        reversed.len(),
+        total_bytes
+    );
+
+    Ok(BalanceResult {
+        plan_id,
+        target_utilization: target,
+        initial_imbalance,
+        projected_imbalance,
+        total_moves: reversed.len(),
+        total_bytes,
+        no_moves_reason: reversed.is_empty().then_some(NoMovesReason::NoCandidates),
+        capped: false,
+        disk_projections: disk_projections(&states, &reversed),
+        split_folders: Vec::new(),
+    })
+}
+
+/// Stat a completed move's file at its new location.
+///
+/// Returns its metadata when the file is still there at the size it was moved
+/// at, `None` otherwise.
+fn unchanged_at_target(
+    disk_mounts: &HashMap<i64, &str>,
+    m: &PlannedMove,
+) -> Option<std::fs::Metadata> {
+    let mount = disk_mounts.get(&m.target_disk_id)?;
+    let path = crate::executor::disk_path(mount, m.destination_path());
+    let meta = match std::fs::metadata(&path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            warn!("Rollback skipping {}: {}", path, e);
+            return None;
+        }
+    };
+    if meta.len() != m.file_size {
+        warn!("Rollback skipping {}: size changed since it was moved", path);
+        return None;
+    }
+    Some(meta)
+}
+
+/// Disk states once every rollback move has been applied.
+fn simulate_moves(disks: &[Disk], target: f64, moves: &[PlannedMove]) -> Vec<DiskState> {
+    let mut states = classify_disks(disks, BalanceTarget::Utilization(target), 0.0);
+    let idx: HashMap<i64, usize> = states.iter().enumerate().map(|(i, s)| (s.disk.id, i)).collect();
+    for m in moves {
+        if let Some(&i) = idx.get(&m.source_disk_id) {
+            states[i].sim_used = states[i].sim_used.saturating_sub(m.file_size);
+        }
+        if let Some(&i) = idx.get(&m.target_disk_id) {
+            states[i].sim_used = states[i].sim_used.saturating_add(m.file_size);
+        }
+    }
+    states
+}
@@ -1,4 +1,7 @@
-use crate::db::Disk;
+use crate::config::AppConfig;
+use crate::db::{Disk, FileEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 /// Classification of a disk relative to the target utilization.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +19,8 @@ pub(crate) struct DiskState {
     pub class: DiskClass,
     /// Simulated used bytes (changes as moves are planned).
     pub sim_used: u64,
+    /// Utilization this disk is balanced toward under the plan's metric.
+    pub target: f64,
 }
 
 impl DiskState {
@@ -31,6 +36,386 @@ impl DiskState {
     }
 }
 
+/// How the planner chooses among target disks that can take a move unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetStrategy {
+    /// The disk with the most bytes left before it reaches target utilization.
+    #[default]
+    MostHeadroom,
+    /// The least-utilized disk, filled up to target before moving to the next.
+    Emptiest,
+    /// Rotate through eligible disks one unit at a time.
+    RoundRobin,
+    /// Pick the units that best fit what each source must shed to get back
+    /// within tolerance, moving fewer bytes than largest-first. Targets are
+    /// chosen as with `MostHeadroom`.
+    MinBytes,
+}
+
+impl TargetStrategy {
+    /// The snake_case name used in the config file and JSON API.
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::MostHeadroom => "most_headroom",
+            Self::Emptiest => "emptiest",
+            Self::RoundRobin => "round_robin",
+            Self::MinBytes => "min_bytes",
+        }
+    }
+}
+
+impl std::str::FromStr for TargetStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "most_headroom" => Ok(Self::MostHeadroom),
+            "emptiest" => Ok(Self::Emptiest),
+            "round_robin" => Ok(Self::RoundRobin),
+            "min_bytes" => Ok(Self::MinBytes),
+            _ => anyhow::bail!("unknown target strategy '{s}'"),
+        }
+    }
+}
+
+/// What the planner equalizes across disks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceMetric {
+    /// The same fraction of every disk used; big disks hold proportionally more.
+    #[default]
+    Utilization,
+    /// The same number of bytes free on every disk: the array's free space
+    /// split evenly, whatever each disk's size.
+    FreeBytes,
+}
+
+impl BalanceMetric {
+    /// The snake_case name used in the config file and JSON API.
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::Utilization => "utilization",
+            Self::FreeBytes => "free_bytes",
+        }
+    }
+}
+
+impl std::str::FromStr for BalanceMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utilization" => Ok(Self::Utilization),
+            "free_bytes" => Ok(Self::FreeBytes),
+            _ => anyhow::bail!("unknown balance metric '{s}'"),
+        }
+    }
+}
+
+/// The order in which the planner considers candidate files for moving.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)] // Names mirror the config values, e.g. "oldest_first"
+pub enum CandidateOrder {
+    /// Biggest files first, so balance is reached in the fewest moves.
+    #[default]
+    LargestFirst,
+    /// Least recently modified first; files without an mtime count as oldest.
+    OldestFirst,
+    /// Most recently modified first; files without an mtime go last.
+    NewestFirst,
+}
+
+impl CandidateOrder {
+    /// The snake_case name used in the config file and JSON API.
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::LargestFirst => "largest_first",
+            Self::OldestFirst => "oldest_first",
+            Self::NewestFirst => "newest_first",
+        }
+    }
+}
+
+impl std::str::FromStr for CandidateOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "largest_first" => Ok(Self::LargestFirst),
+            "oldest_first" => Ok(Self::OldestFirst),
+            "newest_first" => Ok(Self::NewestFirst),
+            _ => anyhow::bail!("unknown candidate order '{s}'"),
+        }
+    }
+}
+
+/// Smallest size at which files of one type may be moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MinMoveSize {
+    /// Files of at least this many bytes may move.
+    AtLeast(u64),
+    /// Files of this type never move.
+    Never,
+}
+
+impl std::fmt::Display for MinMoveSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [(&str, u64); 4] =
+            [("TB", 1 << 40), ("GB", 1 << 30), ("MB", 1 << 20), ("KB", 1 << 10)];
+        match *self {
+            Self::Never => f.write_str("never"),
+            Self::AtLeast(0) => f.write_str("0"),
+            Self::AtLeast(bytes) => match UNITS.iter().find(|(_, unit)| bytes % unit == 0) {
+                Some((suffix, unit)) => write!(f, "{}{suffix}", bytes / unit),
+                None => write!(f, "{bytes}"),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for MinMoveSize {
+    type Err = anyhow::Error;
+
+    /// `never`, or a byte count with an optional binary unit: `512`, `64K`, `50MB`, `1GiB`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("never") {
+            return Ok(Self::Never);
+        }
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (digits, unit) = s.split_at(split);
+        let shift = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 0,
+            "K" | "KB" | "KIB" => 10,
+            "M" | "MB" | "MIB" => 20,
+            "G" | "GB" | "GIB" => 30,
+            "T" | "TB" | "TIB" => 40,
+            _ => anyhow::bail!("unknown size unit in '{s}'"),
+        };
+        let value: u64 = digits.parse().map_err(|e| anyhow::anyhow!("invalid size '{s}': {e}"))?;
+        value
+            .checked_mul(1 << shift)
+            .map(Self::AtLeast)
+            .ok_or_else(|| anyhow::anyhow!("size '{s}' is too large"))
+    }
+}
+
+/// Per-file-type minimum sizes for move candidates.
+///
+/// Config form: comma-separated `.ext:size` entries plus an optional
+/// `default:size`, e.g. `.mkv:0,.nfo|.srt:never,default:50MB`. Extensions
+/// match the catalog's file type breakdown (see [`FileEntry::extension`]);
+/// `none` stands for extensionless files. Empty allows every file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SizeThresholds {
+    by_extension: BTreeMap<String, MinMoveSize>,
+    /// Applies to extensions without an entry; `None` lets them all move.
+    default: Option<MinMoveSize>,
+}
+
+impl SizeThresholds {
+    /// Whether a file with this extension and size may be a move candidate.
+    pub(crate) fn allows(&self, extension: &str, size_bytes: u64) -> bool {
+        match self.by_extension.get(extension).copied().or(self.default) {
+            None => true,
+            Some(MinMoveSize::AtLeast(min)) => size_bytes >= min,
+            Some(MinMoveSize::Never) => false,
+        }
+    }
+}
+
+impl std::fmt::Display for SizeThresholds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self
+            .by_extension
+            .iter()
+            .map(|(ext, size)| format!(".{ext}:{size}"))
+            .chain(self.default.map(|size| format!("default:{size}")));
+        f.write_str(&entries.collect::<Vec<_>>().join(","))
+    }
+}
+
+impl std::str::FromStr for SizeThresholds {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut thresholds = Self::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((types, size)) = entry.rsplit_once(':') else {
+                anyhow::bail!("size threshold '{entry}' must look like '.ext:size'");
+            };
+            let size: MinMoveSize = size.parse()?;
+            for ext in types.split('|').map(str::trim) {
+                if ext.eq_ignore_ascii_case("default") {
+                    thresholds.default = Some(size);
+                    continue;
+                }
+                let ext = ext.strip_prefix('.').unwrap_or(ext).to_lowercase();
+                if ext.is_empty() {
+                    anyhow::bail!("empty file type in size threshold '{entry}'");
+                }
+                thresholds.by_extension.insert(ext, size);
+            }
+        }
+        Ok(thresholds)
+    }
+}
+
+impl TryFrom<String> for SizeThresholds {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SizeThresholds> for String {
+    fn from(thresholds: SizeThresholds) -> Self {
+        thresholds.to_string()
+    }
+}
+
+/// Tunable inputs to the balance planner.
+#[derive(Debug, Clone)]
+pub(crate) struct PlanOptions {
+    /// 0.0 (fewest moves / high tolerance) to 1.0 (perfect balance).
+    pub slider_alpha: f64,
+    /// Maximum tolerance (e.g., 0.15 for 15%).
+    pub max_tolerance: f64,
+    /// Minimum bytes to leave free on any target disk.
+    pub min_free_headroom: u64,
+    /// Hard floor of free bytes no target may drop below, independent of
+    /// `min_free_headroom`.
+    pub absolute_min_free: u64,
+    /// Disk IDs to leave out of this plan in addition to config-excluded disks.
+    pub excluded_disk_ids: Vec<i64>,
+    /// Move files sharing a parent directory as one unit to the same target.
+    pub keep_folders_together: bool,
+    /// Disk-relative folders candidates must live under; empty allows all.
+    pub include_roots: Vec<String>,
+    /// Path prefixes whose files are never chosen as candidates.
+    /// See [`AppConfig::pinned_paths`].
+    pub pinned_paths: Vec<String>,
+    /// Names of disks that may be sources but are never chosen as targets.
+    pub never_target_disks: Vec<String>,
+    /// Never pick a target whose filesystem loses something a source's has
+    /// (see [`fs_caveat`](super::filesystems::fs_caveat)).
+    pub refuse_fs_mismatch: bool,
+    /// Names of disks currently spun down; the planner prefers spinning targets.
+    /// Empty when spin-aware targeting is off or spin state is unknown.
+    pub spun_down_disks: HashSet<String>,
+    /// How a target disk is picked for each move unit.
+    pub target_strategy: TargetStrategy,
+    /// Which candidate files the planner tries to move first.
+    pub candidate_order: CandidateOrder,
+    /// Whether disks are balanced toward equal utilization or equal free bytes.
+    pub balance_metric: BalanceMetric,
+    /// Balance toward this utilization instead of the array average.
+    /// Takes precedence over `balance_metric`.
+    pub target_utilization_override: Option<f64>,
+    /// Per-file-type minimum sizes; files below theirs are never candidates.
+    pub size_thresholds: SizeThresholds,
+    /// Stop planning once this many moves are planned, balanced or not.
+    pub max_moves: Option<usize>,
+    /// Files modified within this many days are never candidates.
+    pub min_file_age_days: Option<u64>,
+    /// Whether files without a cataloged mtime pass the age filter.
+    pub move_undated_files: bool,
+}
+
+impl PlanOptions {
+    /// Build planner options from the daemon config, overriding the slider value.
+    pub(crate) fn from_config(config: &AppConfig, slider_alpha: f64) -> Self {
+        Self {
+            slider_alpha,
+            max_tolerance: config.max_tolerance,
+            min_free_headroom: config.min_free_headroom,
+            absolute_min_free: config.absolute_min_free,
+            excluded_disk_ids: Vec::new(),
+            keep_folders_together: config.keep_folders_together,
+            include_roots: config.include_roots.clone(),
+            pinned_paths: config.pinned_paths.clone(),
+            never_target_disks: config.never_target_disks.clone(),
+            refuse_fs_mismatch: config.refuse_fs_mismatch,
+            spun_down_disks: HashSet::new(),
+            target_strategy: config.target_strategy,
+            candidate_order: config.candidate_order,
+            balance_metric: config.balance_metric,
+            target_utilization_override: config.target_utilization_override,
+            size_thresholds: config.move_size_thresholds.clone(),
+            max_moves: config.max_moves,
+            min_file_age_days: config.min_file_age_days,
+            move_undated_files: config.move_undated_files,
+        }
+    }
+}
+
+/// A set of files on one source disk that must land on the same target.
+///
+/// Without folder grouping every candidate file is its own unit.
+#[derive(Debug)]
+pub(crate) struct MoveUnit<'a> {
+    pub disk_id: i64,
+    pub files: Vec<&'a FileEntry>,
+    pub total_bytes: u64,
+}
+
+/// Fewest included disks a balance plan can work with: one to move files off
+/// and one to receive them.
+pub(crate) const MIN_INCLUDED_DISKS: usize = 2;
+
+/// Planning found fewer than [`MIN_INCLUDED_DISKS`] usable disks.
+#[derive(Debug, thiserror::Error)]
+#[error("Need at least {MIN_INCLUDED_DISKS} included disks to balance; {}", describe_included(.included))]
+pub(crate) struct TooFewDisks {
+    /// Names of the disks that are included (and not excluded for this plan).
+    pub included: Vec<String>,
+}
+
+/// "only disk1 is included; include at least 1 more" and similar.
+fn describe_included(included: &[String]) -> String {
+    let more = MIN_INCLUDED_DISKS.saturating_sub(included.len());
+    match included {
+        [] => format!("no disks are included; include at least {more}"),
+        [one] => format!("only {one} is included; include at least {more} more"),
+        many => format!("only {} are included; include at least {more} more", many.join(", ")),
+    }
+}
+
+/// Why a plan ended up with zero moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NoMovesReason {
+    /// Every disk was already within tolerance of the target.
+    AlreadyBalanced,
+    /// Over-utilized disks had no files to move.
+    NoCandidates,
+    /// Over-utilized disks had files, but every one was pinned, outside the
+    /// include roots, hardlinked, below its size threshold or too recent.
+    AllFiltered,
+    /// Candidates existed but no target disk could take any of them.
+    NoTargetFits,
+    /// Every movable unit held more files than `max_moves` allows.
+    MoveCapTooSmall,
+}
+
+/// One disk's utilization before and after a plan, as simulated by the planner.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct DiskProjection {
+    pub disk_id: i64,
+    pub name: String,
+    pub before_util: f64,
+    pub after_util: f64,
+    /// Utilization the disk was balanced toward; differs per disk under
+    /// [`BalanceMetric::FreeBytes`].
+    pub target_util: f64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
 /// Result of running the balance algorithm.
 #[derive(Debug)]
 pub(crate) struct BalanceResult {
@@ -40,4 +425,13 @@ pub(crate) struct BalanceResult {
     pub projected_imbalance: f64,
     pub total_moves: usize,
     pub total_bytes: u64,
+    /// Set only when `total_moves` is zero.
+    pub no_moves_reason: Option<NoMovesReason>,
+    /// Planning stopped at `max_moves` before the array was balanced.
+    pub capped: bool,
+    /// Per-disk utilization before and after the plan.
+    pub disk_projections: Vec<DiskProjection>,
+    /// One warning per kept-together folder that fit no single disk and was
+    /// split across several.
+    pub split_folders: Vec<String>,
 }
@@ -10,6 +10,10 @@ pub(crate) const DEFAULT_CONFIG_PATH: &str =
 /// cache-disk location (e.g. /mnt/cache/appdata/perfectly-balanced/catalog.db).
 pub(crate) const DEFAULT_DB_PATH: &str = "/tmp/perfectly-balanced/catalog.db";
 
+/// Single-instance lock file. On tmpfs, and independent of the catalog path,
+/// so a daemon running on the fallback catalog still excludes one on the primary.
+pub(crate) const DEFAULT_LOCK_PATH: &str = "/var/run/perfectly-balanced.lock";
+
 /// Default port the daemon listens on (localhost only).
 pub(crate) const DEFAULT_PORT: u16 = 7091;
 
@@ -22,5 +26,14 @@ pub(crate) const DEFAULT_SLIDER_ALPHA: f64 = 0.5;
 /// Default minimum free space headroom per disk in bytes (1 GB).
 pub(crate) const DEFAULT_MIN_FREE_HEADROOM: u64 = 1_073_741_824;
 
+/// Default copy speed assumed for plan ETAs before any execution history (100 MB/s).
+pub(crate) const DEFAULT_ASSUMED_THROUGHPUT: u64 = 100_000_000;
+
+/// Default time a catalog query waits on another connection's lock (5 seconds).
+pub(crate) const DEFAULT_DB_BUSY_TIMEOUT_MS: u64 = 5000;
+
+/// Default time startup keeps retrying the catalog path before giving up (60 seconds).
+pub(crate) const DEFAULT_DB_OPEN_TIMEOUT_SECS: u64 = 60;
+
 /// The base path where Unraid mounts array disks.
 pub(crate) const UNRAID_MNT_BASE: &str = "/mnt";
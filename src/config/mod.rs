@@ -4,3 +4,4 @@ pub(crate) mod settings;
 mod validation;
 
 pub(crate) use settings::AppConfig;
+pub(crate) use validation::validate_target_utilization;
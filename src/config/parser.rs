@@ -1,64 +1,306 @@
 use super::settings::AppConfig;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use std::fmt::Write as _;
 use std::fs;
 use std::path::Path;
 use tracing::warn;
 
 impl AppConfig {
     /// Parse Unraid's simple KEY="VALUE" config format.
+    ///
+    /// Lines after a `[profile NAME]` header are stored as that profile's
+    /// overrides rather than applied; other sections are ignored.
     pub(crate) fn parse_ini(&mut self, contents: &str) {
+        let mut section = Section::Main;
         for line in contents.lines() {
             let line = line.trim();
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = match header.trim().strip_prefix("profile ").map(str::trim) {
+                    Some(name) if !name.is_empty() => {
+                        self.profiles.entry(name.to_string()).or_default();
+                        Section::Profile(name.to_string())
+                    }
+                    _ => {
+                        warn!("Ignoring unknown config section [{}]", header);
+                        Section::Unknown
+                    }
+                };
+                continue;
+            }
+
             if let Some((key, value)) = line.split_once('=') {
                 let key = key.trim();
                 let value = value.trim().trim_matches('"');
 
-                match key {
-                    "PORT" => match value.parse() {
-                        Ok(v) => self.port = v,
-                        Err(e) => warn!("Invalid PORT value '{}': {}", value, e),
-                    },
-                    "SCAN_THREADS" => match value.parse() {
-                        Ok(v) => self.scan_threads = v,
-                        Err(e) => warn!("Invalid SCAN_THREADS value '{}': {}", value, e),
-                    },
-                    "SLIDER_ALPHA" => match value.parse() {
-                        Ok(v) => self.slider_alpha = v,
-                        Err(e) => warn!("Invalid SLIDER_ALPHA value '{}': {}", value, e),
-                    },
-                    "MAX_TOLERANCE" => match value.parse() {
-                        Ok(v) => self.max_tolerance = v,
-                        Err(e) => warn!("Invalid MAX_TOLERANCE value '{}': {}", value, e),
-                    },
-                    "MIN_FREE_HEADROOM" => match value.parse() {
-                        Ok(v) => self.min_free_headroom = v,
-                        Err(e) => warn!("Invalid MIN_FREE_HEADROOM value '{}': {}", value, e),
-                    },
-                    "EXCLUDED_DISKS" => {
-                        self.excluded_disks = value
-                            .split(',')
-                            .map(|s| s.trim().to_string())
-                            .filter(|s| !s.is_empty())
-                            .collect();
-                    }
-                    "WARN_PARITY_CHECK" => {
-                        self.warn_parity_check = value == "yes" || value == "true" || value == "1";
-                    }
-                    "CATALOG_PATH" => {
-                        if !value.is_empty() {
-                            self.db_path = value.to_string();
+                match &section {
+                    Section::Main => {
+                        if let Err(e) = self.apply_setting(key, value) {
+                            warn!("{}", e);
                         }
                     }
-                    _ => {} // Ignore unknown keys
+                    Section::Profile(name) => {
+                        self.profiles
+                            .entry(name.clone())
+                            .or_default()
+                            .insert(key.to_string(), value.to_string());
+                    }
+                    Section::Unknown => {}
                 }
             }
         }
     }
 
+    /// Apply a single INI setting, rejecting values that fail to parse.
+    pub(crate) fn apply_setting(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "PORT" => match value.parse() {
+                Ok(v) => self.port = v,
+                Err(e) => return Err(format!("Invalid PORT value '{value}': {e}")),
+            },
+            "SCAN_THREADS" => match value.parse() {
+                Ok(v) => self.scan_threads = v,
+                Err(e) => return Err(format!("Invalid SCAN_THREADS value '{value}': {e}")),
+            },
+            "PARALLEL_DISK_SCANS" => match value.parse() {
+                Ok(v) => self.parallel_disk_scans = v,
+                Err(e) => return Err(format!("Invalid PARALLEL_DISK_SCANS value '{value}': {e}")),
+            },
+            "SLIDER_ALPHA" => match value.parse() {
+                Ok(v) => self.slider_alpha = v,
+                Err(e) => return Err(format!("Invalid SLIDER_ALPHA value '{value}': {e}")),
+            },
+            "MAX_TOLERANCE" => match value.parse() {
+                Ok(v) => self.max_tolerance = v,
+                Err(e) => return Err(format!("Invalid MAX_TOLERANCE value '{value}': {e}")),
+            },
+            "TARGET_UTILIZATION" if value.is_empty() => {
+                self.target_utilization_override = None;
+            }
+            "TARGET_UTILIZATION" => match value.parse() {
+                Ok(v) => self.target_utilization_override = Some(v),
+                Err(e) => return Err(format!("Invalid TARGET_UTILIZATION value '{value}': {e}")),
+            },
+            "MIN_FREE_HEADROOM" => match value.parse() {
+                Ok(v) => self.min_free_headroom = v,
+                Err(e) => return Err(format!("Invalid MIN_FREE_HEADROOM value '{value}': {e}")),
+            },
+            "ABSOLUTE_MIN_FREE" => match value.parse() {
+                Ok(v) => self.absolute_min_free = v,
+                Err(e) => return Err(format!("Invalid ABSOLUTE_MIN_FREE value '{value}': {e}")),
+            },
+            "EXCLUDED_DISKS" => {
+                self.excluded_disks = parse_list(value).into_iter().collect();
+            }
+            "EXCLUDE_PATTERNS" => {
+                self.exclude_patterns = parse_list(value);
+            }
+            "SYMLINK_POLICY" => match value.parse() {
+                Ok(v) => self.symlink_policy = v,
+                Err(e) => return Err(format!("Invalid SYMLINK_POLICY value '{value}': {e}")),
+            },
+            "REFUSE_FS_MISMATCH" => self.refuse_fs_mismatch = parse_bool(value),
+            "NEVER_TARGET_DISKS" => {
+                self.never_target_disks = parse_list(value);
+            }
+            "INCLUDE_ROOTS" => {
+                self.include_roots = parse_list(value);
+            }
+            "PINNED_PATHS" => {
+                self.pinned_paths = parse_list(value);
+            }
+            "EXTRA_MOUNT_PATHS" => {
+                self.extra_mount_paths = parse_list(value);
+            }
+            "WARN_PARITY_CHECK" => {
+                self.warn_parity_check = parse_bool(value);
+            }
+            "KEEP_FOLDERS_TOGETHER" => {
+                self.keep_folders_together = parse_bool(value);
+            }
+            "BALANCE_METRIC" => match value.parse() {
+                Ok(v) => self.balance_metric = v,
+                Err(e) => return Err(format!("Invalid BALANCE_METRIC value '{value}': {e}")),
+            },
+            "TARGET_STRATEGY" => match value.parse() {
+                Ok(v) => self.target_strategy = v,
+                Err(e) => return Err(format!("Invalid TARGET_STRATEGY value '{value}': {e}")),
+            },
+            "CANDIDATE_ORDER" => match value.parse() {
+                Ok(v) => self.candidate_order = v,
+                Err(e) => return Err(format!("Invalid CANDIDATE_ORDER value '{value}': {e}")),
+            },
+            "MOVE_SIZE_THRESHOLDS" => match value.parse() {
+                Ok(v) => self.move_size_thresholds = v,
+                Err(e) => {
+                    return Err(format!("Invalid MOVE_SIZE_THRESHOLDS value '{value}': {e}"));
+                }
+            },
+            "PREFER_SPINNING_TARGETS" => {
+                self.prefer_spinning_targets = parse_bool(value);
+            }
+            "CHECK_SMART" => {
+                self.check_smart = parse_bool(value);
+            }
+            "CLEANUP_PARTIAL_ON_CANCEL" => {
+                self.cleanup_partial_on_cancel = parse_bool(value);
+            }
+            "RESUME_PARTIAL" => {
+                self.resume_partial = parse_bool(value);
+            }
+            "CANCEL_MODE" => match value.parse() {
+                Ok(v) => self.cancel_mode = v,
+                Err(e) => return Err(format!("Invalid CANCEL_MODE value '{value}': {e}")),
+            },
+            "ON_CONFLICT" => match value.parse() {
+                Ok(v) => self.on_conflict = v,
+                Err(e) => return Err(format!("Invalid ON_CONFLICT value '{value}': {e}")),
+            },
+            "PATH_REMAP" => match value.parse() {
+                Ok(v) => self.path_remap = v,
+                Err(e) => return Err(format!("Invalid PATH_REMAP value '{value}': {e}")),
+            },
+            "OPEN_FILE_POLICY" => match value.parse() {
+                Ok(v) => self.open_file_policy = v,
+                Err(e) => return Err(format!("Invalid OPEN_FILE_POLICY value '{value}': {e}")),
+            },
+            "VERIFY_SIZE" => {
+                self.verify_size = parse_bool(value);
+            }
+            "PRESERVE_ACLS" => {
+                self.preserve_acls = parse_bool(value);
+            }
+            "VERIFY_XATTRS" => {
+                self.verify_xattrs = parse_bool(value);
+            }
+            "PRUNE_EMPTY_DIRS" => {
+                self.prune_empty_dirs = parse_bool(value);
+            }
+            "AUTO_RESCAN_AFTER_EXECUTION" => {
+                self.auto_rescan_after_execution = parse_bool(value);
+            }
+            "COMPRESS_TRANSFERS" => {
+                self.compress_transfers = parse_bool(value);
+            }
+            "COMPRESS_LEVEL" if value.is_empty() => self.compress_level = None,
+            "COMPRESS_LEVEL" => match value.parse() {
+                Ok(v) => self.compress_level = Some(v),
+                Err(e) => return Err(format!("Invalid COMPRESS_LEVEL value '{value}': {e}")),
+            },
+            "EXTRA_RSYNC_ARGS" => {
+                self.extra_rsync_args = value.split_whitespace().map(String::from).collect();
+            }
+            "RSYNC_NICE" if value.is_empty() => self.rsync_nice = None,
+            "RSYNC_NICE" => match value.parse() {
+                Ok(v) => self.rsync_nice = Some(v),
+                Err(e) => return Err(format!("Invalid RSYNC_NICE value '{value}': {e}")),
+            },
+            "RSYNC_IONICE_CLASS" if value.is_empty() => self.rsync_ionice_class = None,
+            "RSYNC_IONICE_CLASS" => match value.parse() {
+                Ok(v) => self.rsync_ionice_class = Some(v),
+                Err(e) => return Err(format!("Invalid RSYNC_IONICE_CLASS value '{value}': {e}")),
+            },
+            "MAX_MOVES" => match value {
+                "" | "0" => self.max_moves = None,
+                _ => match value.parse() {
+                    Ok(v) => self.max_moves = Some(v),
+                    Err(e) => return Err(format!("Invalid MAX_MOVES value '{value}': {e}")),
+                },
+            },
+            "MIN_FILE_AGE_DAYS" => match value {
+                "" | "0" => self.min_file_age_days = None,
+                _ => match value.parse() {
+                    Ok(v) => self.min_file_age_days = Some(v),
+                    Err(e) => {
+                        return Err(format!("Invalid MIN_FILE_AGE_DAYS value '{value}': {e}"))
+                    }
+                },
+            },
+            "MOVE_UNDATED_FILES" => self.move_undated_files = parse_bool(value),
+            "MOVE_TIMEOUT_SECS" => match value {
+                "" | "0" => self.move_timeout_secs = None,
+                _ => match value.parse() {
+                    Ok(v) => self.move_timeout_secs = Some(v),
+                    Err(e) => {
+                        return Err(format!("Invalid MOVE_TIMEOUT_SECS value '{value}': {e}"))
+                    }
+                },
+            },
+            "MOVE_DELAY_MS" => match value.parse() {
+                Ok(v) => self.move_delay_ms = v,
+                Err(e) => return Err(format!("Invalid MOVE_DELAY_MS value '{value}': {e}")),
+            },
+            "CATALOG_MAX_AGE_SECS" if value.is_empty() => self.catalog_max_age_secs = None,
+            "CATALOG_MAX_AGE_SECS" => match value.parse() {
+                Ok(v) => self.catalog_max_age_secs = Some(v),
+                Err(e) => return Err(format!("Invalid CATALOG_MAX_AGE_SECS value '{value}': {e}")),
+            },
+            "CATALOG_AGE_STRICT" => {
+                self.catalog_age_strict = parse_bool(value);
+            }
+            "ASSUMED_THROUGHPUT" => match value.parse() {
+                Ok(v) => self.assumed_throughput = v,
+                Err(e) => return Err(format!("Invalid ASSUMED_THROUGHPUT value '{value}': {e}")),
+            },
+            "SCHEDULE" => {
+                self.schedule = (!value.is_empty()).then(|| value.to_string());
+            }
+            "DB_BUSY_TIMEOUT_MS" => match value.parse() {
+                Ok(v) => self.db_busy_timeout_ms = v,
+                Err(e) => return Err(format!("Invalid DB_BUSY_TIMEOUT_MS value '{value}': {e}")),
+            },
+            "DB_OPEN_TIMEOUT_SECS" => match value.parse() {
+                Ok(v) => self.db_open_timeout_secs = v,
+                Err(e) => return Err(format!("Invalid DB_OPEN_TIMEOUT_SECS value '{value}': {e}")),
+            },
+            "DB_FALLBACK_PATH" => {
+                self.db_fallback_path = (!value.is_empty()).then(|| value.to_string());
+            }
+            "AUDIT_LOG_PATH" => {
+                self.audit_log_path = (!value.is_empty()).then(|| value.to_string());
+            }
+            "SSE_KEEPALIVE_SECS" => match value.parse() {
+                Ok(v) => self.sse_keepalive_secs = v,
+                Err(e) => return Err(format!("Invalid SSE_KEEPALIVE_SECS value '{value}': {e}")),
+            },
+            "HEARTBEAT_SECS" => match value.parse() {
+                Ok(v) => self.heartbeat_secs = v,
+                Err(e) => return Err(format!("Invalid HEARTBEAT_SECS value '{value}': {e}")),
+            },
+            "AUTO_VACUUM_ON_START" => {
+                self.auto_vacuum_on_start = parse_bool(value);
+            }
+            "NOTIFY_WEBHOOK_URL" => {
+                self.notify_webhook_url = (!value.is_empty()).then(|| value.to_string());
+            }
+            "API_TOKEN" => {
+                self.api_token = (!value.is_empty()).then(|| value.to_string());
+            }
+            "CATALOG_PATH" if !value.is_empty() => {
+                self.db_path = value.to_string();
+            }
+            _ => {} // Ignore unknown keys
+        }
+        Ok(())
+    }
+
+    /// A copy of this config with profile `name`'s overrides applied and validated.
+    pub fn with_profile(&self, name: &str) -> Result<Self> {
+        let Some(overrides) = self.profiles.get(name) else {
+            bail!("Unknown profile '{name}'");
+        };
+        let mut config = self.clone();
+        for (key, value) in overrides {
+            config.apply_setting(key, value).map_err(anyhow::Error::msg)?;
+        }
+        config.validate().with_context(|| format!("Profile '{name}' is invalid"))?;
+        config.active_profile = Some(name.to_string());
+        Ok(config)
+    }
+
     /// Save current config back to the Unraid INI file.
     pub fn save(&self) -> Result<()> {
         use super::defaults::DEFAULT_DB_PATH;
@@ -68,27 +310,135 @@ impl AppConfig {
         // Write CATALOG_PATH only when the user has set a custom (non-default) location.
         let catalog_path = if self.db_path == DEFAULT_DB_PATH { "" } else { &self.db_path };
 
-        let contents = format!(
+        let mut contents = format!(
             r#"# Perfectly Balanced configuration
 # Auto-generated — edit via the plugin UI
 PORT="{}"
 SCAN_THREADS="{}"
+PARALLEL_DISK_SCANS="{}"
 SLIDER_ALPHA="{}"
 MAX_TOLERANCE="{}"
+TARGET_UTILIZATION="{}"
 MIN_FREE_HEADROOM="{}"
+ABSOLUTE_MIN_FREE="{}"
 EXCLUDED_DISKS="{}"
+NEVER_TARGET_DISKS="{}"
+REFUSE_FS_MISMATCH="{}"
+EXCLUDE_PATTERNS="{}"
+SYMLINK_POLICY="{}"
+INCLUDE_ROOTS="{}"
+PINNED_PATHS="{}"
+EXTRA_MOUNT_PATHS="{}"
 WARN_PARITY_CHECK="{}"
 CATALOG_PATH="{}"
+KEEP_FOLDERS_TOGETHER="{}"
+BALANCE_METRIC="{}"
+TARGET_STRATEGY="{}"
+CANDIDATE_ORDER="{}"
+MOVE_SIZE_THRESHOLDS="{}"
+MAX_MOVES="{}"
+MIN_FILE_AGE_DAYS="{}"
+MOVE_UNDATED_FILES="{}"
+PREFER_SPINNING_TARGETS="{}"
+CHECK_SMART="{}"
+CLEANUP_PARTIAL_ON_CANCEL="{}"
+RESUME_PARTIAL="{}"
+CANCEL_MODE="{}"
+ON_CONFLICT="{}"
+PATH_REMAP="{}"
+OPEN_FILE_POLICY="{}"
+VERIFY_SIZE="{}"
+PRESERVE_ACLS="{}"
+VERIFY_XATTRS="{}"
+PRUNE_EMPTY_DIRS="{}"
+AUTO_RESCAN_AFTER_EXECUTION="{}"
+COMPRESS_TRANSFERS="{}"
+COMPRESS_LEVEL="{}"
+EXTRA_RSYNC_ARGS="{}"
+RSYNC_NICE="{}"
+RSYNC_IONICE_CLASS="{}"
+MOVE_TIMEOUT_SECS="{}"
+MOVE_DELAY_MS="{}"
+CATALOG_MAX_AGE_SECS="{}"
+CATALOG_AGE_STRICT="{}"
+ASSUMED_THROUGHPUT="{}"
+SCHEDULE="{}"
+SSE_KEEPALIVE_SECS="{}"
+HEARTBEAT_SECS="{}"
+AUTO_VACUUM_ON_START="{}"
+DB_BUSY_TIMEOUT_MS="{}"
+DB_OPEN_TIMEOUT_SECS="{}"
+DB_FALLBACK_PATH="{}"
+NOTIFY_WEBHOOK_URL="{}"
+AUDIT_LOG_PATH="{}"
+API_TOKEN="{}"
 "#,
             self.port,
             self.scan_threads,
+            self.parallel_disk_scans,
             self.slider_alpha,
             self.max_tolerance,
+            self.target_utilization_override.map_or_else(String::new, |v| v.to_string()),
             self.min_free_headroom,
+            self.absolute_min_free,
             excluded,
-            if self.warn_parity_check { "yes" } else { "no" },
+            self.never_target_disks.join(","),
+            yes_no(self.refuse_fs_mismatch),
+            self.exclude_patterns.join(","),
+            self.symlink_policy.as_str(),
+            self.include_roots.join(","),
+            self.pinned_paths.join(","),
+            self.extra_mount_paths.join(","),
+            yes_no(self.warn_parity_check),
             catalog_path,
+            yes_no(self.keep_folders_together),
+            self.balance_metric.as_str(),
+            self.target_strategy.as_str(),
+            self.candidate_order.as_str(),
+            self.move_size_thresholds,
+            self.max_moves.map_or_else(String::new, |v| v.to_string()),
+            self.min_file_age_days.map_or_else(String::new, |v| v.to_string()),
+            yes_no(self.move_undated_files),
+            yes_no(self.prefer_spinning_targets),
+            yes_no(self.check_smart),
+            yes_no(self.cleanup_partial_on_cancel),
+            yes_no(self.resume_partial),
+            self.cancel_mode.as_str(),
+            self.on_conflict.as_str(),
+            self.path_remap,
+            self.open_file_policy,
+            yes_no(self.verify_size),
+            yes_no(self.preserve_acls),
+            yes_no(self.verify_xattrs),
+            yes_no(self.prune_empty_dirs),
+            yes_no(self.auto_rescan_after_execution),
+            yes_no(self.compress_transfers),
+            self.compress_level.map_or_else(String::new, |v| v.to_string()),
+            self.extra_rsync_args.join(" "),
+            self.rsync_nice.map_or_else(String::new, |v| v.to_string()),
+            self.rsync_ionice_class.map_or_else(String::new, |v| v.to_string()),
+            self.move_timeout_secs.map_or_else(String::new, |v| v.to_string()),
+            self.move_delay_ms,
+            self.catalog_max_age_secs.map_or_else(String::new, |v| v.to_string()),
+            yes_no(self.catalog_age_strict),
+            self.assumed_throughput,
+            self.schedule.as_deref().unwrap_or(""),
+            self.sse_keepalive_secs,
+            self.heartbeat_secs,
+            yes_no(self.auto_vacuum_on_start),
+            self.db_busy_timeout_ms,
+            self.db_open_timeout_secs,
+            self.db_fallback_path.as_deref().unwrap_or(""),
+            self.notify_webhook_url.as_deref().unwrap_or(""),
+            self.audit_log_path.as_deref().unwrap_or(""),
+            self.api_token.as_deref().unwrap_or(""),
         );
+        for (name, overrides) in &self.profiles {
+            let _ = write!(contents, "\n[profile {name}]\n");
+            for (key, value) in overrides {
+                let _ = writeln!(contents, "{key}=\"{value}\"");
+            }
+        }
 
         if let Some(parent) = Path::new(&self.config_path).parent() {
             fs::create_dir_all(parent)?;
@@ -100,3 +450,29 @@ CATALOG_PATH="{}"
         Ok(())
     }
 }
+
+/// Which part of the INI file the parser is reading.
+enum Section {
+    Main,
+    Profile(String),
+    Unknown,
+}
+
+/// Split a comma-separated INI value into trimmed, non-empty entries.
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+/// Interpret an INI boolean ("yes", "true" or "1" are truthy).
+fn parse_bool(value: &str) -> bool {
+    value == "yes" || value == "true" || value == "1"
+}
+
+/// Render a boolean the way the Unraid UI writes it.
+const fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
@@ -1,10 +1,14 @@
 use super::defaults::{
-    DEFAULT_CONFIG_PATH, DEFAULT_DB_PATH, DEFAULT_MIN_FREE_HEADROOM, DEFAULT_PORT,
+    DEFAULT_ASSUMED_THROUGHPUT, DEFAULT_CONFIG_PATH, DEFAULT_DB_BUSY_TIMEOUT_MS,
+    DEFAULT_DB_OPEN_TIMEOUT_SECS, DEFAULT_DB_PATH, DEFAULT_MIN_FREE_HEADROOM, DEFAULT_PORT,
     DEFAULT_SCAN_THREADS, DEFAULT_SLIDER_ALPHA, UNRAID_MNT_BASE,
 };
+use crate::balancer::{BalanceMetric, CandidateOrder, SizeThresholds, TargetStrategy};
+use crate::executor::{CancelMode, OnConflict, OpenFilePolicy, PathRemap};
+use crate::scanner::SymlinkPolicy;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::Path;
 
@@ -14,18 +18,154 @@ pub struct AppConfig {
     pub db_path: String,
     pub config_path: String,
     pub scan_threads: usize,
+    /// How many disks a scan walks at once; each uses `scan_threads` threads.
+    pub parallel_disk_scans: usize,
     /// Balance slider value [0.0, 1.0].
     /// 0.0 = fewest moves (high tolerance), 1.0 = perfect balance (low tolerance).
     pub slider_alpha: f64,
     /// Maximum tolerance percentage. Effective tolerance = max_tolerance * (1 - slider_alpha).
     pub max_tolerance: f64,
+    /// Fill level to balance toward instead of the array-wide average, e.g.
+    /// 0.7 to leave growth room on every disk. Must be within (0, 1).
+    pub target_utilization_override: Option<f64>,
     /// Minimum free space headroom per disk in bytes.
     pub min_free_headroom: u64,
+    /// Hard floor of free bytes no disk may be filled past, enforced by both
+    /// the planner and the executor. 0 disables it.
+    pub absolute_min_free: u64,
     /// Disk names explicitly excluded by the user (e.g., "disk3", "cache").
     pub excluded_disks: HashSet<String>,
+    /// Disk names that may give up files but never receive them, e.g. "cache".
+    /// Unlike `excluded_disks`, these still take part in planning as sources.
+    pub never_target_disks: Vec<String>,
+    /// Leave disks out as targets when moving onto them would lose filesystem
+    /// features, e.g. btrfs reflinks moving to XFS. Off just warns in the plan.
+    pub refuse_fs_mismatch: bool,
+    /// Glob patterns (disk-relative) for files never cataloged or moved, e.g. "*.tmp".
+    pub exclude_patterns: Vec<String>,
+    /// Whether scans skip symlinks, catalog them as links, or follow them.
+    pub symlink_policy: SymlinkPolicy,
+    /// Disk-relative top-level folders to scan and balance, e.g. "media".
+    /// Empty means the whole disk.
+    pub include_roots: Vec<String>,
+    /// Path prefixes whose files are cataloged but never moved. Absolute entries
+    /// (e.g. "/mnt/disk1/appdata") pin one disk; relative ones pin on every disk.
+    pub pinned_paths: Vec<String>,
     /// Base mount path for Unraid array disks.
     pub mnt_base: String,
+    /// Additional mountpoints to balance alongside the auto-discovered disks,
+    /// e.g. a ZFS pool at "/mnt/tank".
+    pub extra_mount_paths: Vec<String>,
     pub warn_parity_check: bool,
+    /// Move all files in a folder to the same target disk instead of file-by-file.
+    pub keep_folders_together: bool,
+    /// Whether the planner equalizes utilization or free bytes across disks.
+    pub balance_metric: BalanceMetric,
+    /// How the planner picks a target disk for each move.
+    pub target_strategy: TargetStrategy,
+    /// Which files the planner moves first: largest, oldest or newest.
+    pub candidate_order: CandidateOrder,
+    /// Per-file-type minimum sizes for move candidates, e.g.
+    /// `.mkv:0,.nfo:never,default:50MB`. Empty lets every file move.
+    pub move_size_thresholds: SizeThresholds,
+    /// Cap on moves per generated plan; `None` plans until balanced.
+    pub max_moves: Option<usize>,
+    /// Leave files modified within this many days in place, so active
+    /// downloads aren't shuffled. `None` moves files of any age.
+    pub min_file_age_days: Option<u64>,
+    /// Whether files with no known mtime may move while the age filter is on.
+    pub move_undated_files: bool,
+    /// Prefer already-spinning disks as move targets to avoid waking parked ones.
+    pub prefer_spinning_targets: bool,
+    /// Run `smartctl -H` on target disks before planning and executing, and
+    /// never move files onto a disk whose health isn't passing.
+    pub check_smart: bool,
+    /// Delete a cancelled move's partial target immediately rather than keeping it to resume.
+    pub cleanup_partial_on_cancel: bool,
+    /// Keep interrupted transfers in a partial dir and resume them on the next run.
+    /// Takes precedence over `cleanup_partial_on_cancel`.
+    pub resume_partial: bool,
+    /// Whether a cancel kills the running transfer or lets that file finish first.
+    /// Shutdown always kills it.
+    pub cancel_mode: CancelMode,
+    /// What to do when a move's target path already holds a different file.
+    pub on_conflict: OnConflict,
+    /// Folder prefixes rewritten on the target, e.g. `downloads/complete=>media`.
+    /// Empty keeps each file at its source's relative path.
+    pub path_remap: PathRemap,
+    /// What to do when a move's source file is open elsewhere.
+    pub open_file_policy: OpenFilePolicy,
+    /// Also check the copy's size against the source's size from before the
+    /// transfer; the planned size is checked regardless.
+    pub verify_size: bool,
+    /// Pass `-A` to rsync so POSIX ACLs are copied along with xattrs.
+    pub preserve_acls: bool,
+    /// Check that the copy's extended attributes (and ACLs, with
+    /// `preserve_acls`) match the source's before deleting the source.
+    pub verify_xattrs: bool,
+    /// After an execution, remove source directories its moves left empty.
+    pub prune_empty_dirs: bool,
+    /// After an execution that wasn't cancelled, rescan the disks it moved
+    /// files between so the catalog matches the array again.
+    pub auto_rescan_after_execution: bool,
+    /// Pass `--compress` to rsync; only worth it for slow or network-backed mounts.
+    pub compress_transfers: bool,
+    /// zlib level (1-9) for `compress_transfers`; `None` uses rsync's default.
+    pub compress_level: Option<u32>,
+    /// Extra rsync flags appended after the built-in ones, e.g. "--numeric-ids".
+    /// Flags that could delete or redirect data are rejected by validation.
+    pub extra_rsync_args: Vec<String>,
+    /// Run rsync under `nice -n` at this niceness (-20 to 19).
+    pub rsync_nice: Option<i32>,
+    /// Run rsync under `ionice -c` in this I/O scheduling class: 1 realtime,
+    /// 2 best-effort, 3 idle.
+    pub rsync_ionice_class: Option<u8>,
+    /// Fail a move whose rsync prints no progress for this many seconds.
+    /// `None` waits indefinitely.
+    pub move_timeout_secs: Option<u64>,
+    /// Milliseconds to pause between one transfer and the next, giving the
+    /// disks a rest. 0 starts the next move immediately.
+    pub move_delay_ms: u64,
+    /// Warn when planning against a catalog whose oldest included disk was
+    /// scanned more than this many seconds ago. `None` disables the check.
+    pub catalog_max_age_secs: Option<u64>,
+    /// Refuse to plan on a stale catalog instead of only warning.
+    pub catalog_age_strict: bool,
+    /// Copy speed in bytes/sec used for plan ETAs until real executions
+    /// provide a measured average.
+    pub assumed_throughput: u64,
+    /// Five-field cron expression (local time) for automatic scan → plan → execute
+    /// runs, e.g. "0 3 * * *". `None` disables scheduling.
+    pub schedule: Option<String>,
+    /// Seconds between SSE keep-alive comments on idle event streams; 0 disables them.
+    pub sse_keepalive_secs: u64,
+    /// Seconds between `execution_heartbeat` events during an execution; 0 disables them.
+    pub heartbeat_secs: u64,
+    /// VACUUM the catalog at startup, after migrations.
+    pub auto_vacuum_on_start: bool,
+    /// Milliseconds a catalog query waits for another connection's lock
+    /// before failing with "database is locked". Read at startup.
+    pub db_busy_timeout_ms: u64,
+    /// Seconds startup keeps retrying the catalog path (e.g. while the flash
+    /// drive mounts) before falling back or failing. Read at startup.
+    pub db_open_timeout_secs: u64,
+    /// Catalog used for this run when `db_path` can't be opened within
+    /// `db_open_timeout_secs`. `None` makes that a startup error.
+    pub db_fallback_path: Option<String>,
+    /// URL to POST a JSON summary to when a plan execution finishes.
+    pub notify_webhook_url: Option<String>,
+    /// File every settled move is appended to as one tab-separated line.
+    /// `None` keeps no audit log.
+    pub audit_log_path: Option<String>,
+    /// Bearer token required on mutating API requests. `None` leaves the API open.
+    /// Never echoed back by `GET /api/settings`.
+    #[serde(skip_serializing)]
+    pub api_token: Option<String>,
+    /// Named sets of INI overrides from `[profile NAME]` sections, applied at
+    /// runtime via `POST /api/settings/profile/{name}`.
+    pub profiles: BTreeMap<String, BTreeMap<String, String>>,
+    /// Profile currently applied on top of the saved settings. Runtime only.
+    pub active_profile: Option<String>,
 }
 
 impl Default for AppConfig {
@@ -35,12 +175,65 @@ impl Default for AppConfig {
             db_path: DEFAULT_DB_PATH.to_string(),
             config_path: DEFAULT_CONFIG_PATH.to_string(),
             scan_threads: DEFAULT_SCAN_THREADS,
+            parallel_disk_scans: 1,
             slider_alpha: DEFAULT_SLIDER_ALPHA,
             max_tolerance: 0.15,
+            target_utilization_override: None,
             min_free_headroom: DEFAULT_MIN_FREE_HEADROOM,
+            absolute_min_free: 0,
             excluded_disks: HashSet::new(),
+            never_target_disks: Vec::new(),
+            refuse_fs_mismatch: false,
+            exclude_patterns: Vec::new(),
+            symlink_policy: SymlinkPolicy::default(),
+            include_roots: Vec::new(),
+            pinned_paths: Vec::new(),
             mnt_base: UNRAID_MNT_BASE.to_string(),
+            extra_mount_paths: Vec::new(),
             warn_parity_check: true,
+            keep_folders_together: false,
+            balance_metric: BalanceMetric::default(),
+            target_strategy: TargetStrategy::default(),
+            candidate_order: CandidateOrder::default(),
+            move_size_thresholds: SizeThresholds::default(),
+            max_moves: None,
+            min_file_age_days: None,
+            move_undated_files: false,
+            prefer_spinning_targets: false,
+            check_smart: false,
+            cleanup_partial_on_cancel: true,
+            resume_partial: true,
+            cancel_mode: CancelMode::default(),
+            on_conflict: OnConflict::default(),
+            path_remap: PathRemap::default(),
+            open_file_policy: OpenFilePolicy::default(),
+            verify_size: true,
+            preserve_acls: false,
+            verify_xattrs: false,
+            prune_empty_dirs: false,
+            auto_rescan_after_execution: false,
+            compress_transfers: false,
+            compress_level: None,
+            extra_rsync_args: Vec::new(),
+            rsync_nice: None,
+            rsync_ionice_class: None,
+            move_timeout_secs: None,
+            move_delay_ms: 0,
+            catalog_max_age_secs: None,
+            catalog_age_strict: false,
+            assumed_throughput: DEFAULT_ASSUMED_THROUGHPUT,
+            schedule: None,
+            sse_keepalive_secs: 15,
+            heartbeat_secs: 10,
+            auto_vacuum_on_start: false,
+            db_busy_timeout_ms: DEFAULT_DB_BUSY_TIMEOUT_MS,
+            db_open_timeout_secs: DEFAULT_DB_OPEN_TIMEOUT_SECS,
+            db_fallback_path: None,
+            notify_webhook_url: None,
+            audit_log_path: None,
+            api_token: None,
+            profiles: BTreeMap::new(),
+            active_profile: None,
         }
     }
 }
@@ -73,4 +266,27 @@ impl AppConfig {
         config.validate()?;
         Ok(config)
     }
+
+    /// Re-read the saved settings from `config_path`, dropping any applied
+    /// profile. The port only takes effect at startup, so this config's is
+    /// kept. The catalog path is the saved one, which may differ from the
+    /// running catalog after a fallback; a config made live must carry
+    /// `db_path` over.
+    pub fn reload_saved(&self) -> Result<Self> {
+        let mut config = Self {
+            config_path: self.config_path.clone(),
+            mnt_base: self.mnt_base.clone(),
+            ..Self::default()
+        };
+        let cfg_path = Path::new(&config.config_path);
+        if cfg_path.exists() {
+            let contents = fs::read_to_string(cfg_path)
+                .with_context(|| format!("Failed to read config file: {}", config.config_path))?;
+            config.parse_ini(&contents);
+        }
+        config.port = self.port;
+
+        config.validate()?;
+        Ok(config)
+    }
 }
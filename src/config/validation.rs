@@ -9,6 +9,10 @@ impl AppConfig {
             self.scan_threads >= 1 && self.scan_threads <= 32,
             "scan_threads must be between 1 and 32"
         );
+        anyhow::ensure!(
+            self.parallel_disk_scans >= 1 && self.parallel_disk_scans <= 32,
+            "parallel_disk_scans must be between 1 and 32"
+        );
         anyhow::ensure!(
             (0.0..=1.0).contains(&self.slider_alpha),
             "slider_alpha must be between 0.0 and 1.0"
@@ -17,6 +21,54 @@ impl AppConfig {
             self.max_tolerance > 0.0 && self.max_tolerance <= 1.0,
             "max_tolerance must be between 0.0 and 1.0"
         );
+        if let Some(target) = self.target_utilization_override {
+            validate_target_utilization(target)?;
+        }
+        anyhow::ensure!(self.assumed_throughput > 0, "assumed_throughput must be > 0");
+        crate::scanner::ExcludeMatcher::new(&self.exclude_patterns)?;
+        for root in &self.include_roots {
+            let root_path = std::path::Path::new(root);
+            anyhow::ensure!(
+                root_path.is_relative()
+                    && root_path.components().all(|c| matches!(c, std::path::Component::Normal(_))),
+                "include_roots entries must be disk-relative folders: {root}"
+            );
+        }
+        for path in &self.extra_mount_paths {
+            anyhow::ensure!(
+                path.starts_with('/'),
+                "extra_mount_paths entries must be absolute: {path}"
+            );
+            crate::scanner::validation::validate_path(path)?;
+        }
+        if let Some(level) = self.compress_level {
+            anyhow::ensure!((1..=9).contains(&level), "compress_level must be between 1 and 9");
+        }
+        crate::executor::validate_extra_rsync_args(&self.extra_rsync_args)?;
+        if let Some(nice) = self.rsync_nice {
+            anyhow::ensure!((-20..=19).contains(&nice), "rsync_nice must be between -20 and 19");
+        }
+        if let Some(class) = self.rsync_ionice_class {
+            anyhow::ensure!((1..=3).contains(&class), "rsync_ionice_class must be 1, 2 or 3");
+        }
+        if let Some(expr) = &self.schedule {
+            crate::scheduler::CronSchedule::parse(expr)?;
+        }
+        if let Some(url) = &self.notify_webhook_url {
+            anyhow::ensure!(
+                url.starts_with("http://") || url.starts_with("https://"),
+                "notify_webhook_url must be an http:// or https:// URL"
+            );
+        }
         Ok(())
     }
 }
+
+/// Check a target utilization override lies strictly between 0 and 1.
+pub(crate) fn validate_target_utilization(target: f64) -> Result<()> {
+    anyhow::ensure!(
+        target > 0.0 && target < 1.0,
+        "target utilization must be between 0.0 and 1.0 (exclusive)"
+    );
+    Ok(())
+}
@@ -108,4 +108,28 @@ impl Database {
         anyhow::ensure!(affected > 0, "No disk found with id {disk_id}");
         Ok(())
     }
+
+    /// Include and exclude many disks in one transaction.
+    ///
+    /// Ids with no disk are skipped rather than failing the batch; they're
+    /// returned so the caller can report them.
+    pub fn set_disks_included_bulk(&self, included: &[i64], excluded: &[i64]) -> Result<Vec<i64>> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        let mut unknown = Vec::new();
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE disks SET included = ?1, \
+                 updated_at = strftime('%Y-%m-%dT%H:%M:%fZ','now') WHERE id = ?2",
+            )?;
+            let changes = included.iter().map(|&id| (id, true));
+            for (disk_id, flag) in changes.chain(excluded.iter().map(|&id| (id, false))) {
+                if stmt.execute(params![flag as i64, disk_id])? == 0 {
+                    unknown.push(disk_id);
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(unknown)
+    }
 }
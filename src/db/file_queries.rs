@@ -1,4 +1,6 @@
-use super::models::{FileEntry, FileInsert};
+use super::models::{
+    CatalogEntry, DuplicateGroup, DuplicateLocation, FileEntry, FileInsert, FileTypeStat,
+};
 use super::Database;
 use anyhow::Result;
 use rusqlite::params;
@@ -11,13 +13,61 @@ fn map_file_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FileEntry> {
         file_path: row.get(2)?,
         size_bytes: row.get::<_, i64>(3)? as u64,
         mtime: row.get(4)?,
+        inode: row.get::<_, Option<i64>>(5)?.map(|i| i as u64),
+        nlink: row.get::<_, i64>(6)? as u64,
+        followed_link: row.get(7)?,
     })
 }
 
-const FILE_COLUMNS: &str = "id, disk_id, file_path, size_bytes, mtime";
+const FILE_COLUMNS: &str = "id, disk_id, file_path, size_bytes, mtime, inode, nlink, followed_link";
+
+/// Immediate children of folder `?2` ("" for the disk root) on disk `?1`.
+///
+/// Files below the folder are grouped by their first remaining path component;
+/// a component followed by more path is a subfolder. The range filter on
+/// `file_path` keeps the lookup on the `(disk_id, file_path)` index.
+const FOLDER_CHILDREN: &str = "
+    WITH rest AS (
+        SELECT substr(file_path, CASE WHEN ?2 = '' THEN 1 ELSE length(?2) + 2 END) AS rest,
+               size_bytes
+        FROM files
+        WHERE disk_id = ?1 AND (?2 = '' OR (file_path >= ?2 || '/' AND file_path < ?2 || '0'))
+    ),
+    children AS (
+        SELECT CASE WHEN instr(rest, '/') > 0 THEN substr(rest, 1, instr(rest, '/') - 1)
+                    ELSE rest END AS name,
+               instr(rest, '/') > 0 AS is_dir,
+               size_bytes
+        FROM rest
+    )";
+
+/// Rebuild a disk's extension breakdown from its cataloged files.
+///
+/// SQLite has no "last index of", so the file name and extension are cut with
+/// the `rtrim(x, replace(x, sep, ''))` idiom, which strips everything after the
+/// last `sep`. Dotfiles such as `.bashrc` and names ending in a dot count as
+/// extensionless.
+const REBUILD_FILE_TYPE_STATS: &str = "\
+    INSERT INTO file_type_stats (disk_id, extension, file_count, total_bytes)
+    WITH names AS (
+        SELECT size_bytes,
+               substr(file_path, length(rtrim(file_path, replace(file_path, '/', ''))) + 1)
+                   AS name
+        FROM files WHERE disk_id = ?1
+    )
+    SELECT ?1,
+           CASE WHEN instr(substr(name, 2), '.') > 0
+                THEN COALESCE(
+                    NULLIF(lower(substr(name, length(rtrim(name, replace(name, '.', ''))) + 1)), ''),
+                    'none')
+                ELSE 'none'
+           END AS ext,
+           COUNT(*), SUM(size_bytes)
+    FROM names GROUP BY ext";
 
 impl Database {
-    /// Atomic disk scan: clear existing data and insert all files.
+    /// Atomic disk scan: clear existing data, insert all files, rebuild the
+    /// disk's file type breakdown and record a growth snapshot.
     ///
     /// The entire operation runs in a single transaction under a single mutex lock.
     /// If any step fails, the transaction is rolled back and previous data is preserved.
@@ -32,8 +82,8 @@ impl Database {
         {
             let mut stmt = tx.prepare_cached(
                 "INSERT OR REPLACE INTO files \
-                 (disk_id, file_path, size_bytes, mtime)
-                 VALUES (?1, ?2, ?3, ?4)",
+                 (disk_id, file_path, size_bytes, mtime, inode, nlink, followed_link)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             )?;
 
             for f in files {
@@ -42,11 +92,23 @@ impl Database {
                     f.file_path,
                     f.size_bytes as i64,
                     f.mtime,
+                    f.inode.map(|i| i as i64),
+                    f.nlink as i64,
+                    f.followed_link,
                 ])?;
             }
         }
 
+        tx.execute("DELETE FROM file_type_stats WHERE disk_id = ?1", params![disk_id])?;
+        tx.execute(REBUILD_FILE_TYPE_STATS, params![disk_id])?;
+        Self::record_scan_snapshot(&tx, disk_id)?;
+
         tx.commit()?;
+        drop(conn);
+        // A whole disk's catalog just went through the WAL; fold it back now.
+        if let Err(e) = self.checkpoint() {
+            tracing::warn!("WAL checkpoint after scan of disk {} failed: {:#}", disk_id, e);
+        }
         Ok(())
     }
 
@@ -64,4 +126,162 @@ impl Database {
 
         Ok(files)
     }
+
+    /// Up to `limit` files on a disk with IDs above `after_id`, in ID order.
+    ///
+    /// Lets exports walk a whole disk page by page without holding the
+    /// connection lock for the duration.
+    pub fn get_files_page(
+        &self,
+        disk_id: i64,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<FileEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {FILE_COLUMNS} FROM files \
+                 WHERE disk_id = ?1 AND id > ?2 \
+                 ORDER BY id LIMIT ?3"
+        ))?;
+
+        let files = stmt
+            .query_map(params![disk_id, after_id, limit as i64], map_file_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(files)
+    }
+
+    /// List one page of a folder's immediate children, folders first, plus the
+    /// total number of children. `folder` is disk-relative without slashes at
+    /// either end; "" is the root.
+    pub fn list_folder(
+        &self,
+        disk_id: i64,
+        folder: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<CatalogEntry>, u64)> {
+        let conn = self.conn()?;
+        let total: i64 = conn.query_row(
+            &format!("{FOLDER_CHILDREN} SELECT COUNT(*) FROM (SELECT DISTINCT name, is_dir FROM children)"),
+            params![disk_id, folder],
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "{FOLDER_CHILDREN} SELECT name, is_dir, SUM(size_bytes), COUNT(*) FROM children \
+             GROUP BY name, is_dir ORDER BY is_dir DESC, name LIMIT ?3 OFFSET ?4"
+        ))?;
+        let entries = stmt
+            .query_map(params![disk_id, folder, limit as i64, offset as i64], |row| {
+                Ok(CatalogEntry {
+                    name: row.get(0)?,
+                    is_dir: row.get(1)?,
+                    size_bytes: row.get::<_, i64>(2)? as u64,
+                    file_count: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((entries, total as u64))
+    }
+
+    /// Find files sharing a name and size on two or more disks, largest first.
+    ///
+    /// Only files of at least `min_size` bytes are considered, and at most
+    /// `limit` groups are returned. Read-only: nothing is hashed or removed here.
+    pub fn find_duplicates(&self, min_size: u64, limit: usize) -> Result<Vec<DuplicateGroup>> {
+        let conn = self.conn()?;
+        // rtrim() strips the trailing non-slash characters, leaving the folder
+        // prefix; removing that prefix leaves the file name.
+        let mut stmt = conn.prepare(
+            "WITH named AS (
+                 SELECT disk_id, file_path, size_bytes,
+                        replace(file_path, rtrim(file_path, replace(file_path, '/', '')), '')
+                            AS name
+                 FROM files WHERE size_bytes >= ?1
+             ),
+             dups AS (
+                 SELECT name, size_bytes FROM named
+                 GROUP BY name, size_bytes HAVING COUNT(DISTINCT disk_id) > 1
+                 ORDER BY size_bytes DESC, name LIMIT ?2
+             )
+             SELECT n.name, n.size_bytes, n.disk_id, d.disk_name, n.file_path
+             FROM named n
+             JOIN dups USING (name, size_bytes)
+             JOIN disks d ON d.id = n.disk_id
+             ORDER BY n.size_bytes DESC, n.name, d.disk_name, n.file_path",
+        )?;
+        let rows = stmt.query_map(params![min_size as i64, limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)? as u64,
+                DuplicateLocation {
+                    disk_id: row.get(2)?,
+                    disk_name: row.get(3)?,
+                    file_path: row.get(4)?,
+                    partial_hash: None,
+                },
+            ))
+        })?;
+
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+        for row in rows {
+            let (file_name, size_bytes, location) = row?;
+            match groups.last_mut() {
+                Some(g) if g.file_name == file_name && g.size_bytes == size_bytes => {
+                    g.locations.push(location);
+                }
+                _ => groups.push(DuplicateGroup {
+                    file_name,
+                    size_bytes,
+                    locations: vec![location],
+                    hashes_match: None,
+                }),
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Get a disk's file type breakdown, largest total first.
+    pub fn get_file_type_stats(&self, disk_id: i64) -> Result<Vec<FileTypeStat>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT extension, file_count, total_bytes FROM file_type_stats \
+             WHERE disk_id = ?1 ORDER BY total_bytes DESC, extension",
+        )?;
+        let stats = stmt
+            .query_map(params![disk_id], |row| {
+                Ok(FileTypeStat {
+                    extension: row.get(0)?,
+                    file_count: row.get::<_, i64>(1)? as u64,
+                    total_bytes: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+
+    /// Get the `limit` largest files on a disk, walking the size index.
+    pub fn get_largest_files_on_disk(&self, disk_id: i64, limit: usize) -> Result<Vec<FileEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {FILE_COLUMNS} FROM files \
+                 WHERE disk_id = ?1 \
+                 ORDER BY size_bytes DESC LIMIT ?2"
+        ))?;
+        let files = stmt
+            .query_map(params![disk_id, limit as i64], map_file_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
+
+    /// Get the `limit` largest files across all disks.
+    pub fn get_largest_files(&self, limit: usize) -> Result<Vec<FileEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {FILE_COLUMNS} FROM files ORDER BY size_bytes DESC LIMIT ?1"
+        ))?;
+        let files =
+            stmt.query_map(params![limit as i64], map_file_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(files)
+    }
 }
@@ -4,6 +4,7 @@ mod models;
 mod move_queries;
 mod optional_ext;
 mod plan_queries;
+mod snapshot_queries;
 
 pub(crate) use models::*;
 
@@ -11,8 +12,30 @@ use anyhow::{Context, Result};
 use rusqlite::Connection;
 use std::path::Path;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
+/// Run `attempt` until it succeeds or `wait` runs out, sleeping 250ms, then
+/// doubling up to 5s, between tries. Returns the last error.
+fn retry_with_backoff<T>(wait: Duration, mut attempt: impl FnMut() -> Result<T>) -> Result<T> {
+    let deadline = Instant::now() + wait;
+    let mut delay = Duration::from_millis(250);
+    loop {
+        match attempt() {
+            Ok(v) => return Ok(v),
+            Err(e) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(e);
+                }
+                warn!("Catalog not ready ({:#}), retrying in {:?}", e, delay.min(remaining));
+                std::thread::sleep(delay.min(remaining));
+                delay = (delay * 2).min(Duration::from_secs(5));
+            }
+        }
+    }
+}
+
 /// Thread-safe wrapper around a SQLite connection.
 ///
 /// SQLite in WAL mode supports concurrent readers but only one writer.
@@ -30,27 +53,56 @@ impl std::fmt::Debug for Database {
 
 impl Database {
     /// Open (or create) the SQLite database at the given path.
-    pub fn open(path: &str) -> Result<Self> {
-        if let Some(parent) = Path::new(path).parent() {
-            std::fs::create_dir_all(parent).with_context(|| {
-                format!("Failed to create database directory: {}", parent.display())
-            })?;
-        }
+    ///
+    /// Queries wait up to `busy_timeout_ms` for a lock held by another
+    /// connection (e.g. the sqlite3 CLI) instead of failing at once. The WAL
+    /// is truncated back to `journal_size_limit` after each checkpoint, so it
+    /// doesn't keep its high-water size on the flash drive.
+    pub fn open(path: &str, busy_timeout_ms: u64) -> Result<Self> {
+        Self::create_dir(path)?;
 
         let conn =
             Connection::open(path).with_context(|| format!("Failed to open database at {path}"))?;
 
-        conn.execute_batch(
+        conn.execute_batch(&format!(
             "PRAGMA journal_mode = WAL;
              PRAGMA synchronous = NORMAL;
              PRAGMA foreign_keys = ON;
              PRAGMA cache_size = -64000;
-             PRAGMA temp_store = MEMORY;",
-        )?;
+             PRAGMA temp_store = MEMORY;
+             PRAGMA busy_timeout = {busy_timeout_ms};
+             PRAGMA journal_size_limit = 67108864;"
+        ))?;
 
         Ok(Self { conn: Mutex::new(conn) })
     }
 
+    /// [`open`](Self::open), retried with backoff for up to `wait`.
+    ///
+    /// At boot the flash drive holding the catalog can mount after the daemon
+    /// starts, so a missing directory or unopenable file is retried (250ms,
+    /// doubling to 5s) rather than failing at once. Returns the last error once
+    /// `wait` runs out.
+    pub fn open_with_retry(path: &str, busy_timeout_ms: u64, wait: Duration) -> Result<Self> {
+        retry_with_backoff(wait, || Self::open(path, busy_timeout_ms))
+    }
+
+    /// Create the directory that will hold the catalog at `path`, retried
+    /// like [`open_with_retry`](Self::open_with_retry). Lets startup take the
+    /// instance lock beside the catalog before opening it.
+    pub fn create_dir_with_retry(path: &str, wait: Duration) -> Result<()> {
+        retry_with_backoff(wait, || Self::create_dir(path))
+    }
+
+    fn create_dir(path: &str) -> Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create database directory: {}", parent.display())
+            })?;
+        }
+        Ok(())
+    }
+
     /// Open an in-memory database (for testing).
     #[cfg(test)]
     pub fn open_in_memory() -> Result<Self> {
@@ -117,6 +169,69 @@ impl Database {
             info!("Migration 003_lean_schema applied successfully");
         }
 
+        if current_version < 4 {
+            info!("Applying migration 004_file_type_stats...");
+            let migration = include_str!("../../migrations/004_file_type_stats.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 004_file_type_stats applied successfully");
+        }
+
+        if current_version < 5 {
+            info!("Applying migration 005_scan_snapshots...");
+            let migration = include_str!("../../migrations/005_scan_snapshots.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 005_scan_snapshots applied successfully");
+        }
+
+        if current_version < 6 {
+            info!("Applying migration 006_hardlinks...");
+            let migration = include_str!("../../migrations/006_hardlinks.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 006_hardlinks applied successfully");
+        }
+
+        if current_version < 7 {
+            info!("Applying migration 007_execution_history...");
+            let migration = include_str!("../../migrations/007_execution_history.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 007_execution_history applied successfully");
+        }
+
+        if current_version < 8 {
+            info!("Applying migration 008_cancel_reason...");
+            let migration = include_str!("../../migrations/008_cancel_reason.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 008_cancel_reason applied successfully");
+        }
+
+        if current_version < 9 {
+            info!("Applying migration 009_executed_moves...");
+            let migration = include_str!("../../migrations/009_executed_moves.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 009_executed_moves applied successfully");
+        }
+
+        if current_version < 10 {
+            info!("Applying migration 010_plan_labels...");
+            let migration = include_str!("../../migrations/010_plan_labels.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 010_plan_labels applied successfully");
+        }
+
+        if current_version < 11 {
+            info!("Applying migration 011_move_target_path...");
+            let migration = include_str!("../../migrations/011_move_target_path.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 011_move_target_path applied successfully");
+        }
+
+        if current_version < 12 {
+            info!("Applying migration 012_followed_links...");
+            let migration = include_str!("../../migrations/012_followed_links.sql");
+            conn.execute_batch(migration)?;
+            info!("Migration 012_followed_links applied successfully");
+        }
+
         Ok(())
     }
 
@@ -127,8 +242,46 @@ impl Database {
         })
     }
 
+    /// Run a trivial query to confirm the catalog is reachable.
+    pub(crate) fn ping(&self) -> Result<()> {
+        self.conn()?.query_row("SELECT 1", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Copy committed WAL pages into the database without waiting on readers.
+    ///
+    /// Run after large writes so the WAL is recycled rather than growing for
+    /// as long as some reader keeps the default auto-checkpoint from finishing.
+    pub(crate) fn checkpoint(&self) -> Result<()> {
+        self.conn()?.query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Rebuild the database file to reclaim free pages, then truncate the WAL.
+    ///
+    /// Returns the database size in bytes before and after.
+    pub(crate) fn vacuum(&self) -> Result<(u64, u64)> {
+        let conn = self.conn()?;
+        let size = || -> rusqlite::Result<u64> {
+            conn.query_row(
+                "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+                [],
+                |row| row.get(0),
+            )
+        };
+        let before = size()?;
+        conn.execute_batch("VACUUM;")?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        let after = size()?;
+        info!("Vacuumed catalog: {} -> {} bytes", before, after);
+        Ok((before, after))
+    }
+
     /// Recover stale states left behind by a crash or kill.
     ///
+    /// Runs at startup and from `POST /api/maintenance/recover`, both only
+    /// while nothing is executing.
+    ///
     /// In a single transaction:
     /// 1. Collect IDs of moves stuck at `in_progress` (for later filesystem cleanup)
     /// 2. Mark any `executing` plans as `failed`
@@ -156,7 +309,7 @@ impl Database {
 
         if plans_failed > 0 || moves_reset > 0 {
             warn!(
-                "Startup recovery: {} plan(s) marked failed, {} move(s) reset",
+                "Recovery: {} plan(s) marked failed, {} move(s) reset",
                 plans_failed, moves_reset
             );
         }
@@ -165,7 +318,7 @@ impl Database {
     }
 }
 
-/// Stats returned by startup recovery.
+/// Stats returned by stale-state recovery.
 pub(crate) struct RecoveryStats {
     /// IDs of moves that were `in_progress` at crash time — need filesystem cleanup.
     pub recovered_move_ids: Vec<i64>,
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
 /// Represents a physical disk in the Unraid array.
@@ -23,6 +24,14 @@ impl Disk {
         }
         self.used_bytes as f64 / self.total_bytes as f64
     }
+
+    /// Seconds since this disk's row was last refreshed, as of `now`.
+    ///
+    /// `None` when `updated_at` is missing or unparseable.
+    pub fn age_secs(&self, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+        let updated = chrono::DateTime::parse_from_rfc3339(self.updated_at.as_deref()?).ok()?;
+        Some((now - updated.with_timezone(&chrono::Utc)).num_seconds())
+    }
 }
 
 /// A file entry in the catalog.
@@ -33,6 +42,26 @@ pub struct FileEntry {
     pub file_path: String,
     pub size_bytes: u64,
     pub mtime: Option<i64>,
+    pub inode: Option<u64>,
+    /// Hard link count; above 1 the data is shared with another path.
+    pub nlink: u64,
+    /// A symlink cataloged at its target's size (`SYMLINK_POLICY=follow`);
+    /// never a move candidate, since the move would carry only the link.
+    pub followed_link: bool,
+}
+
+impl FileEntry {
+    /// Lowercased extension without the dot, as in the file type breakdown:
+    /// "none" for extensionless names, dotfiles and names ending in a dot.
+    pub fn extension(&self) -> String {
+        let name = self.file_path.rsplit('/').next().unwrap_or("");
+        // A leading dot marks a dotfile, not an extension.
+        let rest = name.chars().next().map_or("", |c| &name[c.len_utf8()..]);
+        match rest.rsplit_once('.') {
+            Some((_, ext)) if !ext.is_empty() => ext.to_lowercase(),
+            _ => "none".to_string(),
+        }
+    }
 }
 
 /// Status of a balance plan.
@@ -134,6 +163,12 @@ pub struct BalancePlan {
     pub total_moves: i32,
     pub total_bytes_to_move: u64,
     pub status: PlanStatus,
+    /// Why the plan was cancelled; set only for cancelled plans.
+    pub cancel_reason: Option<String>,
+    /// Optional name given when the plan was created.
+    pub name: Option<String>,
+    /// Labels to find the plan by later, sorted.
+    pub tags: Vec<String>,
 }
 
 /// A single file move within a balance plan.
@@ -150,6 +185,17 @@ pub struct PlannedMove {
     pub status: MoveStatus,
     pub error_message: Option<String>,
     pub source_mtime: Option<i64>,
+    /// Relative path on the target disk when it differs from `file_path`,
+    /// fixed once the move starts executing (see `PATH_REMAP`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_path: Option<String>,
+}
+
+impl PlannedMove {
+    /// Where the file sits relative to the target disk once moved.
+    pub fn destination_path(&self) -> &str {
+        self.target_path.as_deref().unwrap_or(&self.file_path)
+    }
 }
 
 /// A move with additional context for display.
@@ -161,17 +207,95 @@ pub struct PlannedMoveDetail {
     pub target_disk_name: String,
 }
 
+/// A plan's traffic from one source disk to one target disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskFlow {
+    pub source_disk_id: i64,
+    pub target_disk_id: i64,
+    pub moves: u64,
+    pub bytes: u64,
+}
+
+/// Enough about a completed move to reverse it, kept after its plan is pruned.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutedMove {
+    pub move_id: i64,
+    pub source_mount: String,
+    pub target_mount: String,
+    pub file_path: String,
+    /// Relative path the file landed at on the target disk.
+    pub target_path: String,
+    pub file_size: u64,
+    /// Target mtime (Unix seconds) right after the move; undo requires it unchanged.
+    pub target_mtime: Option<i64>,
+    pub executed_at: Option<String>,
+    pub undone_at: Option<String>,
+}
+
 /// Lightweight path info for a move — used by crash recovery to check filesystem state.
 #[derive(Debug, Clone)]
 pub struct MovePathInfo {
     pub id: i64,
     pub file_path: String,
+    /// Relative path on the target disk, which `PATH_REMAP` may have rewritten.
+    pub target_path: String,
     pub file_size: u64,
     pub source_mount: String,
     pub target_mount: String,
     pub source_mtime: Option<i64>,
 }
 
+/// One immediate child of a cataloged folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub is_dir: bool,
+    /// File size, or the total of every file beneath a folder.
+    pub size_bytes: u64,
+    /// 1 for a file, number of files beneath a folder.
+    pub file_count: u64,
+}
+
+/// Files with the same name and size on more than one disk: likely copies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub locations: Vec<DuplicateLocation>,
+    /// Whether every copy's partial hash matched; `None` unless hashing was requested.
+    pub hashes_match: Option<bool>,
+}
+
+/// One copy within a [`DuplicateGroup`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateLocation {
+    pub disk_id: i64,
+    pub disk_name: String,
+    pub file_path: String,
+    pub partial_hash: Option<String>,
+}
+
+/// Aggregated size of one file extension on a disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTypeStat {
+    /// Lowercased extension without the dot, or "none" for extensionless files.
+    pub extension: String,
+    pub file_count: u64,
+    pub total_bytes: u64,
+}
+
+/// Totals recorded for a disk at the end of one scan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSnapshot {
+    pub id: i64,
+    pub disk_id: i64,
+    pub scanned_at: String,
+    pub total_files: u64,
+    pub total_bytes: u64,
+    /// Bytes per lowercased extension, as in [`FileTypeStat`].
+    pub extension_bytes: HashMap<String, u64>,
+}
+
 /// Insert batch for scanning — lighter weight than FileEntry.
 #[derive(Debug, Clone)]
 pub struct FileInsert {
@@ -179,4 +303,7 @@ pub struct FileInsert {
     pub file_path: String,
     pub size_bytes: u64,
     pub mtime: Option<i64>,
+    pub inode: Option<u64>,
+    pub nlink: u64,
+    pub followed_link: bool,
 }
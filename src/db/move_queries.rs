@@ -1,6 +1,9 @@
-use super::models::{MovePathInfo, MoveStatus, PlannedMove, PlannedMoveDetail};
+use super::models::{
+    DiskFlow, ExecutedMove, MovePathInfo, MoveStatus, PlannedMove, PlannedMoveDetail,
+};
+use super::optional_ext::OptionalExt;
 use super::Database;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use rusqlite::params;
 
 /// Map a row from the planned_moves JOIN query into a `PlannedMoveDetail`.
@@ -22,6 +25,7 @@ fn map_move_detail_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PlannedMoveD
             status,
             error_message: row.get(9)?,
             source_mtime: row.get(12)?,
+            target_path: row.get(13)?,
         },
         source_disk_name: row.get(10)?,
         target_disk_name: row.get(11)?,
@@ -32,7 +36,7 @@ const MOVE_DETAIL_SELECT: &str = "\
     SELECT m.id, m.plan_id, m.source_disk_id, m.target_disk_id,
            m.file_path, m.file_size, m.exec_order, m.phase, m.status, m.error_message,
            s.disk_name AS source_disk_name, t.disk_name AS target_disk_name,
-           m.source_mtime
+           m.source_mtime, m.target_path
     FROM planned_moves m
     JOIN disks s ON m.source_disk_id = s.id
     JOIN disks t ON m.target_disk_id = t.id";
@@ -47,8 +51,8 @@ impl Database {
             let mut stmt = tx.prepare_cached(
                 "INSERT INTO planned_moves \
                  (plan_id, source_disk_id, target_disk_id, file_path, \
-                 file_size, exec_order, phase, source_mtime)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                 file_size, exec_order, phase, source_mtime, target_path)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             )?;
 
             for m in moves {
@@ -61,6 +65,7 @@ impl Database {
                     m.move_order,
                     m.phase,
                     m.source_mtime,
+                    m.target_path,
                 ])?;
             }
         }
@@ -80,6 +85,55 @@ impl Database {
         Ok(moves)
     }
 
+    /// One page of a plan's moves in execution order, optionally only those in
+    /// `status`, plus how many moves match across all pages.
+    pub fn get_plan_moves_page(
+        &self,
+        plan_id: i64,
+        status: Option<MoveStatus>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<PlannedMoveDetail>, u64)> {
+        let conn = self.conn()?;
+        let status = status.map(MoveStatus::as_str);
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM planned_moves WHERE plan_id = ?1 AND (?2 IS NULL OR status = ?2)",
+            params![plan_id, status],
+            |row| row.get(0),
+        )?;
+        let sql = format!(
+            "{MOVE_DETAIL_SELECT} WHERE m.plan_id = ?1 AND (?2 IS NULL OR m.status = ?2) \
+             ORDER BY m.exec_order LIMIT ?3 OFFSET ?4"
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let moves = stmt
+            .query_map(params![plan_id, status, limit as i64, offset as i64], map_move_detail_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((moves, total as u64))
+    }
+
+    /// Moves and bytes per source → target disk pair of a plan, heaviest first.
+    pub fn get_plan_flows(&self, plan_id: i64) -> Result<Vec<DiskFlow>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT source_disk_id, target_disk_id, COUNT(*), COALESCE(SUM(file_size), 0) \
+             FROM planned_moves WHERE plan_id = ?1 \
+             GROUP BY source_disk_id, target_disk_id \
+             ORDER BY 4 DESC, source_disk_id, target_disk_id",
+        )?;
+        let flows = stmt
+            .query_map(params![plan_id], |row| {
+                Ok(DiskFlow {
+                    source_disk_id: row.get(0)?,
+                    target_disk_id: row.get(1)?,
+                    moves: row.get::<_, i64>(2)? as u64,
+                    bytes: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(flows)
+    }
+
     /// Update the status of a specific move.
     pub fn update_move_status(
         &self,
@@ -95,6 +149,22 @@ impl Database {
         Ok(())
     }
 
+    /// Skip every pending move of a plan onto `target_disk_id`, returning how many.
+    pub fn skip_pending_moves_to_disk(
+        &self,
+        plan_id: i64,
+        target_disk_id: i64,
+        reason: &str,
+    ) -> Result<usize> {
+        let conn = self.conn()?;
+        let count = conn.execute(
+            "UPDATE planned_moves SET status = 'skipped', error_message = ?1 \
+             WHERE plan_id = ?2 AND target_disk_id = ?3 AND status = 'pending'",
+            params![reason, plan_id, target_disk_id],
+        )?;
+        Ok(count)
+    }
+
     /// Get all pending moves for a plan in a specific phase.
     pub fn get_pending_moves_for_phase(
         &self,
@@ -132,7 +202,8 @@ impl Database {
         let conn = self.conn()?;
         let placeholders: String = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
         let sql = format!(
-            "SELECT m.id, m.file_path, m.file_size, s.mount_path, t.mount_path, m.source_mtime \
+            "SELECT m.id, m.file_path, m.file_size, s.mount_path, t.mount_path, m.source_mtime, \
+             COALESCE(m.target_path, m.file_path) \
              FROM planned_moves m \
              JOIN disks s ON m.source_disk_id = s.id \
              JOIN disks t ON m.target_disk_id = t.id \
@@ -152,6 +223,7 @@ impl Database {
                     source_mount: row.get(3)?,
                     target_mount: row.get(4)?,
                     source_mtime: row.get(5)?,
+                    target_path: row.get(6)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -159,6 +231,16 @@ impl Database {
         Ok(moves)
     }
 
+    /// Record where a move's file will land when `PATH_REMAP` rewrote it.
+    pub fn set_move_target_path(&self, move_id: i64, target_path: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE planned_moves SET target_path = ?1 WHERE id = ?2",
+            params![target_path, move_id],
+        )?;
+        Ok(())
+    }
+
     /// Mark all in_progress moves for a plan as failed (used by panic guard).
     pub fn fail_in_progress_moves(&self, plan_id: i64) -> Result<usize> {
         let conn = self.conn()?;
@@ -169,4 +251,115 @@ impl Database {
         )?;
         Ok(count)
     }
+
+    /// Delete a single pending move from a plan.
+    ///
+    /// Returns `false` when no pending move with that ID belongs to the plan.
+    pub fn delete_pending_move(&self, plan_id: i64, move_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+        let deleted = conn.execute(
+            "DELETE FROM planned_moves WHERE id = ?1 AND plan_id = ?2 AND status = 'pending'",
+            params![move_id, plan_id],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Rewrite the execution order of a plan's pending moves.
+    ///
+    /// The listed moves are redistributed over the `exec_order` slots they
+    /// already occupy, in the given order, so moves that are not listed (or no
+    /// longer pending) keep their positions. Every ID must be a distinct pending
+    /// move of the plan; otherwise nothing is changed.
+    pub fn reorder_pending_moves(&self, plan_id: i64, move_ids: &[i64]) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+
+        let mut slots: Vec<i32> = Vec::with_capacity(move_ids.len());
+        {
+            let mut stmt = tx.prepare_cached(
+                "SELECT exec_order FROM planned_moves \
+                 WHERE id = ?1 AND plan_id = ?2 AND status = 'pending'",
+            )?;
+            for id in move_ids {
+                let slot: Option<i32> =
+                    stmt.query_row(params![id, plan_id], |row| row.get(0)).optional()?;
+                let Some(slot) = slot else {
+                    bail!("Move {id} is not a pending move of plan {plan_id}");
+                };
+                slots.push(slot);
+            }
+        }
+        slots.sort_unstable();
+        if slots.windows(2).any(|w| w[0] == w[1]) {
+            bail!("Move list contains duplicates");
+        }
+
+        {
+            let mut stmt =
+                tx.prepare_cached("UPDATE planned_moves SET exec_order = ?1 WHERE id = ?2")?;
+            for (id, slot) in move_ids.iter().zip(&slots) {
+                stmt.execute(params![slot, id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Log a completed move so it can be undone later.
+    pub fn record_executed_move(&self, entry: &ExecutedMove) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO executed_moves \
+             (move_id, source_mount, target_mount, file_path, file_size, target_mtime, target_path) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                entry.move_id,
+                entry.source_mount,
+                entry.target_mount,
+                entry.file_path,
+                entry.file_size as i64,
+                entry.target_mtime,
+                entry.target_path
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_executed_move(&self, move_id: i64) -> Result<Option<ExecutedMove>> {
+        let conn = self.conn()?;
+        let entry = conn
+            .query_row(
+                "SELECT move_id, source_mount, target_mount, file_path, file_size, \
+                 target_mtime, executed_at, undone_at, COALESCE(target_path, file_path) \
+                 FROM executed_moves WHERE move_id = ?1",
+                params![move_id],
+                |row| {
+                    Ok(ExecutedMove {
+                        move_id: row.get(0)?,
+                        source_mount: row.get(1)?,
+                        target_mount: row.get(2)?,
+                        file_path: row.get(3)?,
+                        target_path: row.get(8)?,
+                        file_size: row.get::<_, i64>(4)? as u64,
+                        target_mtime: row.get(5)?,
+                        executed_at: row.get(6)?,
+                        undone_at: row.get(7)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    /// Stamp a logged move as undone so it can't be reversed twice.
+    pub fn mark_move_undone(&self, move_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE executed_moves SET undone_at = strftime('%Y-%m-%dT%H:%M:%fZ','now') \
+             WHERE move_id = ?1",
+            params![move_id],
+        )?;
+        Ok(())
+    }
 }
@@ -2,7 +2,42 @@ use super::models::{BalancePlan, PlanStatus};
 use super::optional_ext::OptionalExt;
 use super::Database;
 use anyhow::Result;
-use rusqlite::params;
+use rusqlite::{params, Connection};
+
+const PLAN_SELECT: &str = "\
+    SELECT id, created_at, tolerance, slider_alpha, target_utilization,
+           initial_imbalance, projected_imbalance, total_moves,
+           total_bytes_to_move, status, cancel_reason, name
+    FROM balance_plans";
+
+/// Map a `PLAN_SELECT` row into a `BalancePlan`; tags are filled in separately.
+fn map_plan_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BalancePlan> {
+    let status_str: String = row.get(9)?;
+    let status = PlanStatus::try_from(status_str.as_str()).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(9, rusqlite::types::Type::Text, Box::from(e))
+    })?;
+    Ok(BalancePlan {
+        id: row.get(0)?,
+        created_at: row.get(1)?,
+        tolerance: row.get(2)?,
+        slider_alpha: row.get(3)?,
+        target_utilization: row.get(4)?,
+        initial_imbalance: row.get(5)?,
+        projected_imbalance: row.get(6)?,
+        total_moves: row.get(7)?,
+        total_bytes_to_move: row.get::<_, i64>(8)? as u64,
+        status,
+        cancel_reason: row.get(10)?,
+        name: row.get(11)?,
+        tags: Vec::new(),
+    })
+}
+
+fn plan_tags(conn: &Connection, plan_id: i64) -> rusqlite::Result<Vec<String>> {
+    conn.prepare_cached("SELECT tag FROM plan_tags WHERE plan_id = ?1 ORDER BY tag")?
+        .query_map(params![plan_id], |row| row.get(0))?
+        .collect()
+}
 
 impl Database {
     /// Create a new balance plan.
@@ -41,6 +76,55 @@ impl Database {
         Ok(())
     }
 
+    /// Recompute a plan's move count and byte total from its current moves.
+    pub fn refresh_plan_totals(&self, plan_id: i64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE balance_plans SET \
+             total_moves = (SELECT COUNT(*) FROM planned_moves WHERE plan_id = ?1), \
+             total_bytes_to_move = \
+                (SELECT COALESCE(SUM(file_size), 0) FROM planned_moves WHERE plan_id = ?1) \
+             WHERE id = ?1",
+            params![plan_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record how many bytes a finished execution moved and how long it took.
+    pub fn record_execution(
+        &self,
+        plan_id: i64,
+        moves_completed: u32,
+        bytes_moved: u64,
+        duration_seconds: f64,
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO execution_history \
+             (plan_id, moves_completed, bytes_moved, duration_seconds) VALUES (?1, ?2, ?3, ?4)",
+            params![plan_id, moves_completed, bytes_moved as i64, duration_seconds],
+        )?;
+        Ok(())
+    }
+
+    /// Average bytes per second over the last `limit` executions that moved data.
+    ///
+    /// `None` when there is no usable history yet.
+    pub fn recent_throughput(&self, limit: usize) -> Result<Option<f64>> {
+        let conn = self.conn()?;
+        let (bytes, seconds): (Option<i64>, Option<f64>) = conn.query_row(
+            "SELECT SUM(bytes_moved), SUM(duration_seconds) FROM \
+             (SELECT bytes_moved, duration_seconds FROM execution_history \
+              WHERE bytes_moved > 0 AND duration_seconds > 0 ORDER BY id DESC LIMIT ?1)",
+            params![limit as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(match (bytes, seconds) {
+            (Some(b), Some(s)) if s > 0.0 => Some(b as f64 / s),
+            _ => None,
+        })
+    }
+
     /// Update plan status.
     pub fn update_plan_status(&self, plan_id: i64, status: PlanStatus) -> Result<()> {
         let conn = self.conn()?;
@@ -51,41 +135,58 @@ impl Database {
         Ok(())
     }
 
+    /// Mark a plan cancelled, recording why.
+    pub fn cancel_plan(&self, plan_id: i64, reason: Option<&str>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE balance_plans SET status = ?1, cancel_reason = ?2 WHERE id = ?3",
+            params![PlanStatus::Cancelled.as_str(), reason, plan_id],
+        )?;
+        Ok(())
+    }
+
     /// Get a balance plan by ID.
     pub fn get_plan(&self, plan_id: i64) -> Result<Option<BalancePlan>> {
         let conn = self.conn()?;
         let plan = conn
-            .query_row(
-                "SELECT id, created_at, tolerance, slider_alpha, target_utilization,
-                        initial_imbalance, projected_imbalance, total_moves, \
-                 total_bytes_to_move, status
-                 FROM balance_plans WHERE id = ?1",
-                params![plan_id],
-                |row| {
-                    let status_str: String = row.get(9)?;
-                    let status = PlanStatus::try_from(status_str.as_str()).map_err(|e| {
-                        rusqlite::Error::FromSqlConversionFailure(
-                            9,
-                            rusqlite::types::Type::Text,
-                            Box::from(e),
-                        )
-                    })?;
-                    Ok(BalancePlan {
-                        id: row.get(0)?,
-                        created_at: row.get(1)?,
-                        tolerance: row.get(2)?,
-                        slider_alpha: row.get(3)?,
-                        target_utilization: row.get(4)?,
-                        initial_imbalance: row.get(5)?,
-                        projected_imbalance: row.get(6)?,
-                        total_moves: row.get(7)?,
-                        total_bytes_to_move: row.get::<_, i64>(8)? as u64,
-                        status,
-                    })
-                },
-            )
+            .query_row(&format!("{PLAN_SELECT} WHERE id = ?1"), params![plan_id], map_plan_row)
             .optional()?;
+        let Some(mut plan) = plan else {
+            return Ok(None);
+        };
+        plan.tags = plan_tags(&conn, plan_id)?;
+        Ok(Some(plan))
+    }
 
-        Ok(plan)
+    /// Every plan, newest first; with `tag`, only plans carrying it.
+    pub fn list_plans(&self, tag: Option<&str>) -> Result<Vec<BalancePlan>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "{PLAN_SELECT} WHERE ?1 IS NULL \
+             OR id IN (SELECT plan_id FROM plan_tags WHERE tag = ?1) ORDER BY id DESC"
+        ))?;
+        let mut plans =
+            stmt.query_map(params![tag], map_plan_row)?.collect::<Result<Vec<_>, _>>()?;
+        for plan in &mut plans {
+            plan.tags = plan_tags(&conn, plan.id)?;
+        }
+        Ok(plans)
+    }
+
+    /// Set a plan's name and replace its tags.
+    pub fn set_plan_labels(&self, plan_id: i64, name: Option<&str>, tags: &[String]) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("UPDATE balance_plans SET name = ?1 WHERE id = ?2", params![name, plan_id])?;
+        tx.execute("DELETE FROM plan_tags WHERE plan_id = ?1", params![plan_id])?;
+        {
+            let mut stmt = tx
+                .prepare_cached("INSERT OR IGNORE INTO plan_tags (plan_id, tag) VALUES (?1, ?2)")?;
+            for tag in tags {
+                stmt.execute(params![plan_id, tag])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
     }
 }
@@ -0,0 +1,58 @@
+use super::models::ScanSnapshot;
+use super::Database;
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+/// Snapshots kept per disk; older ones are pruned as new scans land.
+const SNAPSHOTS_PER_DISK: i64 = 50;
+
+impl Database {
+    /// Snapshot a disk's totals from its freshly rebuilt `file_type_stats`.
+    ///
+    /// Runs on the scan's transaction so the snapshot always matches the catalog.
+    pub(super) fn record_scan_snapshot(conn: &Connection, disk_id: i64) -> Result<()> {
+        conn.execute(
+            "INSERT INTO scan_snapshots (disk_id, total_files, total_bytes, extension_bytes)
+             SELECT ?1, COALESCE(SUM(file_count), 0), COALESCE(SUM(total_bytes), 0),
+                    COALESCE(json_group_object(extension, total_bytes), '{}')
+             FROM file_type_stats WHERE disk_id = ?1",
+            params![disk_id],
+        )?;
+        conn.execute(
+            "DELETE FROM scan_snapshots WHERE disk_id = ?1 AND id NOT IN \
+             (SELECT id FROM scan_snapshots WHERE disk_id = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![disk_id, SNAPSHOTS_PER_DISK],
+        )?;
+        Ok(())
+    }
+
+    /// Get a disk's most recent snapshots, newest first.
+    pub fn get_recent_snapshots(&self, disk_id: i64, limit: usize) -> Result<Vec<ScanSnapshot>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, disk_id, scanned_at, total_files, total_bytes, extension_bytes \
+             FROM scan_snapshots WHERE disk_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let snapshots = stmt
+            .query_map(params![disk_id, limit as i64], |row| {
+                let json: String = row.get(5)?;
+                let extension_bytes = serde_json::from_str(&json).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        5,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    )
+                })?;
+                Ok(ScanSnapshot {
+                    id: row.get(0)?,
+                    disk_id: row.get(1)?,
+                    scanned_at: row.get(2)?,
+                    total_files: row.get::<_, i64>(3)? as u64,
+                    total_bytes: row.get::<_, i64>(4)? as u64,
+                    extension_bytes,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(snapshots)
+    }
+}
@@ -1,4 +1,6 @@
 use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
 use tokio::sync::broadcast;
 
 /// Events that flow from background tasks (scanner, executor) to SSE subscribers.
@@ -10,7 +12,8 @@ pub enum Event {
         disk: String,
         files_scanned: u64,
         bytes_cataloged: u64,
-        /// Estimated percent complete for this disk (0.0 - 100.0).
+        /// Estimated percent complete for this disk (0.0 - 99.0), measured against
+        /// the file count of its previous scan; 0.0 on a disk's first scan.
         percent: f64,
     },
 
@@ -18,7 +21,14 @@ pub enum Event {
     ScanDiskComplete { disk: String, total_files: u64, total_bytes: u64 },
 
     /// All disk scanning is complete.
-    ScanComplete { total_disks: u32, total_files: u64, total_bytes: u64, duration_seconds: f64 },
+    ScanComplete {
+        total_disks: u32,
+        total_files: u64,
+        total_bytes: u64,
+        duration_seconds: f64,
+        /// One entry per disk that finished scanning, in scan order.
+        per_disk: Vec<DiskScanSummary>,
+    },
 
     /// A balance plan has been generated and is ready for review.
     PlanReady { plan_id: i64, total_moves: u32, total_bytes: u64, projected_imbalance: f64 },
@@ -41,6 +51,26 @@ pub enum Event {
         status: String, // "success" | "failed" | "skipped"
         verified: bool,
         error: Option<String>,
+        /// Bytes transferred; 0 unless the move succeeded.
+        bytes: u64,
+        /// Average transfer speed in bytes/sec; 0.0 unless the move succeeded.
+        avg_speed_bps: f64,
+    },
+
+    /// Periodic whole-plan progress while an execution runs, sent even while
+    /// one long file is still copying.
+    ExecutionHeartbeat {
+        plan_id: i64,
+        moves_done: u32,
+        moves_total: u32,
+        /// Bytes of completed moves plus the copied part of the current one.
+        bytes_done: u64,
+        bytes_total: u64,
+        elapsed_seconds: f64,
+        /// Seconds left at the rate so far; `None` until some bytes have moved.
+        eta_seconds: Option<f64>,
+        /// Projected finish time (RFC 3339, UTC) for `eta_seconds`.
+        expected_completion: Option<String>,
     },
 
     /// The entire plan execution has finished.
@@ -50,12 +80,28 @@ pub enum Event {
         moves_failed: u32,
         moves_skipped: u32,
         duration_seconds: f64,
+        /// Set when the plan was cancelled, e.g. "cancelled by user: watching a movie".
+        cancel_reason: Option<String>,
+    },
+
+    /// A scheduled balancing run changed stage.
+    ScheduledRun {
+        stage: String, // "started" | "skipped" | "finished"
+        message: String,
     },
 
     /// A generic error event.
     DaemonError { message: String },
 }
 
+/// What one disk contributed to a scan, carried by [`Event::ScanComplete`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskScanSummary {
+    pub disk: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
 impl Event {
     /// Returns the SSE event type name for this event variant.
     pub const fn event_type(&self) -> &'static str {
@@ -66,42 +112,89 @@ impl Event {
             Self::PlanReady { .. } => "plan_ready",
             Self::MoveProgress { .. } => "move_progress",
             Self::MoveComplete { .. } => "move_complete",
+            Self::ExecutionHeartbeat { .. } => "execution_heartbeat",
             Self::ExecutionComplete { .. } => "execution_complete",
+            Self::ScheduledRun { .. } => "scheduled_run",
             Self::DaemonError { .. } => "daemon_error",
         }
     }
 }
 
+/// An event tagged with its position in the hub's stream, used as the SSE `id`.
+#[derive(Debug, Clone)]
+pub struct StampedEvent {
+    pub id: u64,
+    pub event: Event,
+}
+
+/// Recently published events kept for replay, plus the next id to hand out.
+#[derive(Debug)]
+struct History {
+    next_id: u64,
+    recent: VecDeque<StampedEvent>,
+}
+
 /// The central event broadcast hub.
 ///
 /// Background tasks (scanner, executor) send events here via `publish()`.
 /// SSE endpoint handlers subscribe via `subscribe()` and forward events to the browser.
+/// The last `capacity` events are also kept so a reconnecting client can catch
+/// up on what it missed via [`EventHub::subscribe_since`].
 #[derive(Debug, Clone)]
 pub struct EventHub {
-    sender: broadcast::Sender<Event>,
+    sender: broadcast::Sender<StampedEvent>,
+    history: Arc<Mutex<History>>,
+    capacity: usize,
 }
 
 impl EventHub {
-    /// Create a new EventHub with the given channel capacity.
+    /// Create a new EventHub with the given channel and replay capacity.
     ///
     /// If subscribers fall behind by more than `capacity` events, they will
     /// receive a `Lagged` error and miss intermediate events. 256 is a safe
     /// default for the expected event rate.
     pub fn new(capacity: usize) -> Self {
         let (sender, _) = broadcast::channel(capacity);
-        Self { sender }
+        let history = History { next_id: 1, recent: VecDeque::with_capacity(capacity) };
+        Self { sender, history: Arc::new(Mutex::new(history)), capacity }
     }
 
     /// Publish an event to all current subscribers.
     ///
     /// Returns Ok(subscriber_count) or Err if there are no active subscribers
-    /// (which is fine — events are fire-and-forget).
-    pub fn publish(&self, event: Event) -> Result<usize, broadcast::error::SendError<Event>> {
-        self.sender.send(event)
+    /// (which is fine — events are fire-and-forget). The event is recorded for
+    /// replay either way.
+    pub fn publish(
+        &self,
+        event: Event,
+    ) -> Result<usize, broadcast::error::SendError<StampedEvent>> {
+        // Hold the history lock while sending so `subscribe_since` can never see
+        // an event both in its replay snapshot and on its live receiver.
+        let mut history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        let stamped = StampedEvent { id: history.next_id, event };
+        history.next_id += 1;
+        if history.recent.len() == self.capacity {
+            history.recent.pop_front();
+        }
+        history.recent.push_back(stamped.clone());
+        self.sender.send(stamped)
     }
 
     /// Subscribe to the event stream. Returns a broadcast Receiver.
-    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+    pub fn subscribe(&self) -> broadcast::Receiver<StampedEvent> {
         self.sender.subscribe()
     }
+
+    /// Subscribe and return the buffered events published after `last_id`.
+    ///
+    /// Replay is best-effort: a client gone longer than the buffer covers just
+    /// gets whatever is still held.
+    pub fn subscribe_since(
+        &self,
+        last_id: u64,
+    ) -> (Vec<StampedEvent>, broadcast::Receiver<StampedEvent>) {
+        let history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        let missed = history.recent.iter().filter(|e| e.id > last_id).cloned().collect();
+        (missed, self.sender.subscribe())
+    }
 }
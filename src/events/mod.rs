@@ -1,3 +1,3 @@
 mod hub;
 
-pub(crate) use hub::{Event, EventHub};
+pub(crate) use hub::{DiskScanSummary, Event, EventHub, StampedEvent};
@@ -0,0 +1,90 @@
+use crate::db::{MoveStatus, PlannedMove};
+use std::collections::HashMap;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// Append-only, plain-text record of every settled move, kept outside the
+/// catalog so it survives losing or rebuilding it.
+///
+/// One tab-separated line per move: timestamp, result, size, source path,
+/// target path and the reason for a skip or failure. Each line is written
+/// out as soon as it is recorded, so an early return, panic or kill loses
+/// nothing. Does nothing when no `audit_log_path` is configured; write errors
+/// are logged, never fatal.
+pub(crate) struct AuditLog {
+    file: Option<tokio::fs::File>,
+    disk_mounts: HashMap<i64, String>,
+}
+
+impl AuditLog {
+    /// Open `path` for appending, creating it if needed. A file that can't
+    /// be opened is reported and auditing is off for this run.
+    pub(crate) async fn open(path: Option<&str>, disk_mounts: HashMap<i64, String>) -> Self {
+        let file = match path {
+            Some(path) => {
+                match tokio::fs::OpenOptions::new().create(true).append(true).open(path).await {
+                    Ok(file) => Some(file),
+                    Err(e) => {
+                        warn!("Cannot open audit log {}: {}", path, e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        Self { file, disk_mounts }
+    }
+
+    /// Append one line for `m`, which landed (or would have) at `target_path`
+    /// on its target disk.
+    pub(crate) async fn record(
+        &mut self,
+        m: &PlannedMove,
+        target_path: &str,
+        result: MoveStatus,
+        detail: Option<&str>,
+    ) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let full_path = |disk_id: i64, rel: &str| {
+            self.disk_mounts
+                .get(&disk_id)
+                .map_or_else(|| rel.to_string(), |mount| super::disk_path(mount, rel))
+        };
+        let line = audit_line(
+            &chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            result,
+            m.file_size,
+            (&full_path(m.source_disk_id, &m.file_path), &full_path(m.target_disk_id, target_path)),
+            detail,
+        );
+        // One write per line: an append-mode write lands whole, and a tokio
+        // file only finishes it once flushed.
+        if let Err(e) = file.write_all(line.as_bytes()).await {
+            warn!("Failed to write audit log: {}", e);
+        }
+        if let Err(e) = file.flush().await {
+            warn!("Failed to flush audit log: {}", e);
+        }
+    }
+}
+
+/// Format one audit line. Tabs, newlines and backslashes in paths or reasons
+/// are escaped so every move stays on one greppable line.
+pub(crate) fn audit_line(
+    timestamp: &str,
+    result: MoveStatus,
+    size: u64,
+    (source, target): (&str, &str),
+    detail: Option<&str>,
+) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n");
+    format!(
+        "{timestamp}\t{}\t{size}\t{}\t{}\t{}\n",
+        result.as_str(),
+        escape(source),
+        escape(target),
+        escape(detail.unwrap_or(""))
+    )
+}
@@ -0,0 +1,54 @@
+use serde::Serialize;
+
+/// Past executions averaged for the measured throughput.
+pub(crate) const THROUGHPUT_HISTORY: usize = 5;
+
+/// Fixed cost per move on top of the copy itself (lsof, stat, verify, unlink).
+const PER_MOVE_OVERHEAD_SECS: f64 = 2.0;
+
+/// Where the throughput behind a plan's ETA came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EstimateBasis {
+    /// Rolling average of recent executions.
+    Measured,
+    /// The configured `assumed_throughput`; no usable history yet.
+    Assumed,
+}
+
+/// Seconds left in a running execution at the rate achieved so far.
+///
+/// `None` until some bytes have moved, since there's no rate to go on yet.
+pub(crate) fn remaining_seconds(
+    bytes_done: u64,
+    bytes_total: u64,
+    elapsed_secs: f64,
+) -> Option<f64> {
+    if bytes_done == 0 || elapsed_secs <= 0.0 {
+        return None;
+    }
+    let rate = bytes_done as f64 / elapsed_secs;
+    Some(bytes_total.saturating_sub(bytes_done) as f64 / rate)
+}
+
+/// Approximate wall-clock seconds to execute a plan.
+///
+/// A measured rate covers whole past executions, so per-move overhead is already
+/// in it; the assumed rate is a raw copy speed and gets a fixed cost per move.
+pub(crate) fn estimate_seconds(
+    total_bytes: u64,
+    total_moves: usize,
+    measured: Option<f64>,
+    assumed_throughput: u64,
+) -> (f64, EstimateBasis) {
+    match measured {
+        Some(rate) if rate > 0.0 => (total_bytes as f64 / rate, EstimateBasis::Measured),
+        _ => {
+            let copy_secs = total_bytes as f64 / assumed_throughput.max(1) as f64;
+            (
+                (total_moves as f64).mul_add(PER_MOVE_OVERHEAD_SECS, copy_secs),
+                EstimateBasis::Assumed,
+            )
+        }
+    }
+}
@@ -1,43 +1,672 @@
+pub(crate) mod audit;
+pub(crate) mod estimate;
+pub(crate) mod notify;
 pub(crate) mod recovery;
+pub(crate) mod remap;
+pub(crate) mod script;
+pub(crate) mod xattrs;
 
+pub(crate) use remap::PathRemap;
+
+use crate::config::AppConfig;
 use anyhow::{bail, Context, Result};
 use regex::Regex;
-use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 use tokio::process::Command;
-use tracing::info;
+use tracing::{info, warn};
 
-/// Cached result of rsync --info=progress2 support check.
-static RSYNC_PROGRESS2: OnceLock<bool> = OnceLock::new();
+/// What probing the installed rsync found.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RsyncInfo {
+    /// Reported version, e.g. "3.2.7"; `None` when rsync couldn't be run.
+    pub version: Option<String>,
+    /// rsync is new enough for `--info=progress2` (>= 3.1.0).
+    pub supports_progress2: bool,
+}
 
-/// Check rsync version to determine if --info=progress2 is supported (>= 3.1.0).
-async fn probe_rsync_progress2() -> Result<bool> {
-    let output = Command::new("rsync")
-        .arg("--version")
-        .output()
+/// Run `rsync --version` to learn whether rsync is installed and what it supports.
+pub(crate) async fn probe_rsync() -> RsyncInfo {
+    let output = match Command::new("rsync").arg("--version").output().await {
+        Ok(o) => o,
+        Err(e) => {
+            warn!("rsync is not available, plans cannot be executed: {}", e);
+            return RsyncInfo::default();
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some((major, minor, patch)) = parse_rsync_version(&stdout) else {
+        warn!("Could not parse rsync version; progress2 disabled");
+        return RsyncInfo { version: Some("unknown".to_string()), supports_progress2: false };
+    };
+    info!("rsync version: {major}.{minor}.{patch}");
+    RsyncInfo {
+        version: Some(format!("{major}.{minor}.{patch}")),
+        supports_progress2: major > 3 || (major == 3 && minor >= 1),
+    }
+}
+
+#[allow(clippy::unwrap_used)] // Compile-time constant regex, provably valid
+static RSYNC_VERSION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"rsync\s+version\s+v?(\d+)\.(\d+)\.(\d+)").unwrap());
+
+/// Extract `(major, minor, patch)` from `rsync --version` output.
+pub(crate) fn parse_rsync_version(stdout: &str) -> Option<(u32, u32, u32)> {
+    let caps = RSYNC_VERSION_RE.captures(stdout)?;
+    Some((caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?))
+}
+
+/// Directory, relative to the target file's folder, where rsync keeps
+/// interrupted transfers when resuming is enabled.
+pub(crate) const PARTIAL_DIR: &str = ".pb-partial";
+
+/// Where rsync parks an interrupted transfer of `target` under [`PARTIAL_DIR`].
+pub(crate) fn partial_fragment_path(target: &str) -> Option<PathBuf> {
+    let target = Path::new(target);
+    Some(target.parent()?.join(PARTIAL_DIR).join(target.file_name()?))
+}
+
+/// Suffix of the temporary name rsync writes to before the verified copy is
+/// renamed into place.
+pub(crate) const STAGING_SUFFIX: &str = ".pb-tmp";
+
+/// The temporary path a move into `target` is copied to.
+///
+/// Only a finished, verified copy is renamed to `target`, so a leftover staging
+/// file is never the sole copy of anything and can always be deleted.
+pub(crate) fn staging_path(target: &str) -> String {
+    format!("{target}{STAGING_SUFFIX}")
+}
+
+/// What to do when a move's target path already holds a different file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    /// Leave both files alone and mark the move skipped.
+    #[default]
+    Skip,
+    /// Replace the target file with the new copy.
+    Overwrite,
+    /// Mark the move failed.
+    Fail,
+}
+
+impl OnConflict {
+    /// The snake_case name used in the config file and JSON API.
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Overwrite => "overwrite",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+impl std::str::FromStr for OnConflict {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "fail" => Ok(Self::Fail),
+            _ => bail!("unknown conflict policy '{s}'"),
+        }
+    }
+}
+
+/// What to do when a move's source file is open in another process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "mode")]
+pub enum OpenFilePolicy {
+    /// Skip the move straight away.
+    #[default]
+    Skip,
+    /// Re-check up to `retries` times, `delay_secs` apart, then skip.
+    Wait { retries: u32, delay_secs: u64 },
+}
+
+impl OpenFilePolicy {
+    /// Retries used by a bare `wait` in the config file.
+    const DEFAULT_RETRIES: u32 = 3;
+    /// Delay used by a bare `wait` in the config file.
+    const DEFAULT_DELAY_SECS: u64 = 10;
+}
+
+/// Config file form: `skip`, `wait`, or `wait:<retries>:<delay_secs>`.
+impl std::fmt::Display for OpenFilePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skip => f.write_str("skip"),
+            Self::Wait { retries, delay_secs } => write!(f, "wait:{retries}:{delay_secs}"),
+        }
+    }
+}
+
+impl std::str::FromStr for OpenFilePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(':').collect::<Vec<_>>().as_slice() {
+            ["skip"] => Ok(Self::Skip),
+            ["wait"] => Ok(Self::Wait {
+                retries: Self::DEFAULT_RETRIES,
+                delay_secs: Self::DEFAULT_DELAY_SECS,
+            }),
+            ["wait", retries, delay_secs] => Ok(Self::Wait {
+                retries: retries.parse().context("invalid wait retries")?,
+                delay_secs: delay_secs.parse().context("invalid wait delay")?,
+            }),
+            _ => bail!("unknown open file policy '{s}'"),
+        }
+    }
+}
+
+/// Remove the directories between `file_path` and `mount` that are now empty,
+/// deepest first. Returns how many were removed.
+///
+/// `remove_dir` refuses a directory that still holds anything, so the walk
+/// stops at the first one with other contents. Top-level share folders
+/// (direct children of `mount`) are kept.
+pub(crate) async fn prune_empty_parents(mount: &str, file_path: &str) -> usize {
+    let mount = Path::new(mount);
+    let mut removed = 0;
+    for dir in Path::new(file_path).ancestors().skip(1) {
+        if dir.parent().is_none_or(|p| p.as_os_str().is_empty()) {
+            break;
+        }
+        let full = mount.join(dir);
+        if tokio::fs::remove_dir(&full).await.is_err() {
+            break;
+        }
+        info!("Removed empty directory: {}", full.display());
+        removed += 1;
+    }
+    removed
+}
+
+/// How a cancel treats the move being transferred.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelMode {
+    /// Kill rsync straight away.
+    #[default]
+    Immediate,
+    /// Let the current file finish (and be verified), then stop before the next.
+    Graceful,
+}
+
+impl CancelMode {
+    /// The snake_case name used in the config file and JSON API.
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Graceful => "graceful",
+        }
+    }
+}
+
+impl std::str::FromStr for CancelMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "immediate" => Ok(Self::Immediate),
+            "graceful" => Ok(Self::Graceful),
+            _ => bail!("unknown cancel mode '{s}'"),
+        }
+    }
+}
+
+/// Describe why `target` can't safely receive the file described by `source`.
+///
+/// `None` when the target path is free, or already holds a copy with the same
+/// size and mtime (e.g. left by a run interrupted before the source was removed).
+pub(crate) fn target_conflict(source: &std::fs::Metadata, target: &str) -> Option<String> {
+    let existing = match std::fs::symlink_metadata(target) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => return Some(format!("Cannot inspect target path: {e}")),
+    };
+    if !existing.is_file() {
+        return Some("Target path already occupied by a non-file".to_string());
+    }
+    let same_mtime = matches!(
+        (existing.modified(), source.modified()),
+        (Ok(a), Ok(b)) if a == b
+    );
+    if existing.len() == source.len() && same_mtime {
+        return None;
+    }
+    Some(format!(
+        "Target path already occupied by a different file ({} bytes, source {} bytes)",
+        existing.len(),
+        source.len()
+    ))
+}
+
+/// Whether two mount paths resolve to the same device (`st_dev`), e.g. one disk
+/// mounted twice. A move between them would copy a file onto itself. `false`
+/// when either can't be read; an unusable mount is reported elsewhere.
+pub(crate) fn same_device(source_mount: &str, target_mount: &str) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(source_mount), std::fs::metadata(target_mount)) {
+        (Ok(source), Ok(target)) => source.dev() == target.dev(),
+        _ => false,
+    }
+}
+
+/// Modification time of `meta` in whole Unix seconds, as stored in the catalog.
+pub(crate) fn mtime_secs(meta: &std::fs::Metadata) -> Option<i64> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+/// Full path of the catalog-relative `file_path` on the disk mounted at `mount`.
+///
+/// Joined as paths rather than formatted strings, so `%`, spaces or control
+/// characters in a name pass through untouched. A leading `/` is stripped so
+/// `file_path` can't replace `mount` the way an absolute join would.
+pub(crate) fn disk_path(mount: &str, file_path: &str) -> String {
+    Path::new(mount).join(file_path.trim_start_matches('/')).to_string_lossy().into_owned()
+}
+
+/// Describe why a completed move can't be undone, or `None` if it can.
+///
+/// The file must still sit on the target with the size and mtime it had right
+/// after the move, and nothing may have taken its old place on the source.
+pub(crate) fn undo_blocker(entry: &crate::db::ExecutedMove) -> Option<String> {
+    if entry.undone_at.is_some() {
+        return Some("Move was already undone".to_string());
+    }
+    let target = disk_path(&entry.target_mount, &entry.target_path);
+    let meta = match std::fs::symlink_metadata(&target) {
+        Ok(meta) => meta,
+        Err(e) => return Some(format!("File is no longer on the target disk: {e}")),
+    };
+    if !meta.is_file() || meta.len() != entry.file_size || mtime_secs(&meta) != entry.target_mtime {
+        return Some("File changed since it was moved".to_string());
+    }
+    let source = disk_path(&entry.source_mount, &entry.file_path);
+    if std::fs::symlink_metadata(&source).is_ok() {
+        return Some("Original location on the source disk is occupied".to_string());
+    }
+    None
+}
+
+/// Whether a failed rsync ran out of space on the target.
+///
+/// rsync reports ENOSPC as "No space left on device" in stderr; exits 11
+/// (file I/O) and 12 (data stream, when the receiver dies mid-write) also count
+/// once the target is seen with less than `needed` bytes free.
+pub(crate) fn rsync_disk_full(
+    exit_code: i32,
+    stderr: &str,
+    target_free: Option<u64>,
+    needed: u64,
+) -> bool {
+    stderr.contains("No space left on device")
+        || (matches!(exit_code, 11 | 12) && target_free.is_some_and(|free| free < needed))
+}
+
+/// Compare a finished copy's size against the planned size and, when given,
+/// the source's size from before the transfer. Returns why they disagree, if
+/// they do.
+pub(crate) fn copy_size_mismatch(
+    expected: u64,
+    source: Option<u64>,
+    target: u64,
+) -> Option<String> {
+    match source {
+        _ if target != expected => Some(format!("target size {target} != expected {expected}")),
+        Some(source) if target != source => {
+            Some(format!("target size {target} != source size {source}"))
+        }
+        _ => None,
+    }
+}
+
+/// What [`verify_and_remove_source`] checks a finished copy against.
+pub(crate) struct CopyChecks {
+    /// Size the move was planned with; the copy must always match it.
+    pub expected_size: u64,
+    /// Also compare against the source's size from before the transfer.
+    pub verify_size: bool,
+    /// Compare extended attributes (and ACLs when copied).
+    pub verify_xattrs: bool,
+    /// Whether rsync was told to copy ACLs.
+    pub preserve_acls: bool,
+}
+
+/// Verify the copy at `target`'s staging path, rename it into place, then
+/// remove the source.
+///
+/// Safety invariant: the source file is NEVER deleted unless:
+/// 1. The staged copy exists and its size matches the planned size and, with
+///    `verify_size`, the source's size from before rsync started
+/// 2. The source mtime hasn't changed since rsync started (no concurrent modification)
+/// 3. With `verify_xattrs`, the staged copy carries the source's extended
+///    attributes (and ACLs, when rsync was told to copy them)
+/// 4. The staged copy was renamed to `target`
+pub(crate) async fn verify_and_remove_source(
+    source: &str,
+    target: &str,
+    pre_rsync: &std::fs::Metadata,
+    checks: &CopyChecks,
+) -> anyhow::Result<()> {
+    let staged = staging_path(target);
+    // Verify the staged copy exists and size matches
+    let target_meta = tokio::fs::symlink_metadata(&staged).await.map_err(|e| {
+        anyhow::anyhow!("Post-copy verification failed: target file missing or unreadable: {e}")
+    })?;
+    let source_size = checks.verify_size.then_some(pre_rsync.len());
+    if let Some(msg) = copy_size_mismatch(checks.expected_size, source_size, target_meta.len()) {
+        anyhow::bail!("Post-copy verification failed: {msg} (both copies preserved)");
+    }
+    if checks.verify_xattrs {
+        let (src, stg, acls) = (source.to_string(), staged.clone(), checks.preserve_acls);
+        let mismatch =
+            tokio::task::spawn_blocking(move || xattrs::xattr_mismatch(&src, &stg, acls))
+                .await?
+                .map_err(|e| anyhow::anyhow!("Post-copy verification failed: {e:#}"))?;
+        if let Some(msg) = mismatch {
+            anyhow::bail!("Post-copy verification failed: {msg} (both copies preserved)");
+        }
+    }
+
+    // Verify source hasn't been modified during the transfer
+    let source_meta = tokio::fs::symlink_metadata(source)
         .await
-        .context("Failed to execute rsync --version")?;
+        .map_err(|e| anyhow::anyhow!("Post-copy verification failed: cannot stat source: {e}"))?;
+    let current_mtime = source_meta.modified()?;
+    if current_mtime != pre_rsync.modified()? {
+        anyhow::bail!(
+            "Post-copy verification failed: source was modified during transfer \
+             (both copies preserved)"
+        );
+    }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let re = Regex::new(r"rsync\s+version\s+(\d+)\.(\d+)\.(\d+)")?;
-
-    if let Some(caps) = re.captures(&stdout) {
-        let major: u32 = caps[1].parse()?;
-        let minor: u32 = caps[2].parse()?;
-        let patch: u32 = caps[3].parse()?;
-        info!("rsync version: {major}.{minor}.{patch}");
-        Ok(major > 3 || (major == 3 && minor >= 1))
-    } else {
-        bail!("Could not parse rsync version from output");
+    // Same filesystem, so the copy appears at the real path all at once.
+    tokio::fs::rename(&staged, target).await.map_err(|e| {
+        anyhow::anyhow!("Copy verified but failed to rename {staged} into place: {e}")
+    })?;
+
+    // All checks passed — safe to delete source
+    tokio::fs::remove_file(source).await.map_err(|e| {
+        anyhow::anyhow!(
+            "Copy verified but failed to remove source (both copies exist, manual cleanup needed): {e}"
+        )
+    })?;
+
+    Ok(())
+}
+
+/// One rsync progress update. `speed` and `eta` are empty when rsync omitted them.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RsyncProgress {
+    pub percent: f64,
+    pub speed: String,
+    pub eta: String,
+}
+
+/// A progress row: byte count (thousands separators, optional `-h` suffix),
+/// percent, then optional rate, `h:mm:ss` ETA and `(xfr#…)` trailer. Anchored
+/// at the end of the line, so a `%` inside a file name never matches and a
+/// name left in front of the row doesn't hide it.
+#[allow(clippy::unwrap_used)] // Compile-time constant regex, provably valid
+static PROGRESS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:^|\s)[\d.,]+[KMGTP]?\s+(\d{1,3})%(?:\s+([\d.,]+[kKMGTP]?B/s))?(?:\s+(\d+:\d{2}:\d{2}))?(?:\s+\(xfr#[^)]*\))?\s*$",
+    )
+    .unwrap()
+});
+
+/// Parse the newest progress row out of one `\r`-delimited chunk of rsync output.
+///
+/// A chunk can also hold the file-name line `-v` prints before a transfer, and
+/// the final row carries an `(xfr#N, to-chk=…)` trailer; both are tolerated.
+pub(crate) fn parse_rsync_progress(chunk: &str) -> Option<RsyncProgress> {
+    chunk.lines().rev().find_map(|line| {
+        let caps = PROGRESS_RE.captures(line)?;
+        Some(RsyncProgress {
+            percent: caps[1].parse().ok()?,
+            speed: caps.get(2).map(|m| m.as_str().to_string()).unwrap_or_default(),
+            eta: caps.get(3).map(|m| m.as_str().to_string()).unwrap_or_default(),
+        })
+    })
+}
+
+/// Minimum interval between MoveProgress events for one move (milliseconds).
+const PROGRESS_INTERVAL_MS: u64 = 500;
+
+/// Percent change that publishes progress before the interval has passed.
+const PROGRESS_MIN_STEP: f64 = 1.0;
+
+/// Decides which parsed rsync progress rows become `MoveProgress` events.
+///
+/// A fast transfer prints many rows a second; publishing each one floods the
+/// broadcast channel and makes slow subscribers lag.
+#[derive(Debug, Default)]
+pub(crate) struct ProgressThrottle {
+    last: Option<(Instant, f64)>,
+}
+
+impl ProgressThrottle {
+    /// Whether to publish `percent` seen at `now`: the first row, 100%, a row
+    /// at least [`PROGRESS_MIN_STEP`] past the last published one, or any row
+    /// once [`PROGRESS_INTERVAL_MS`] has elapsed.
+    pub(crate) fn should_publish(&mut self, percent: f64, now: Instant) -> bool {
+        let publish = match self.last {
+            None => true,
+            Some((at, last_percent)) => {
+                percent >= 100.0
+                    || (percent - last_percent).abs() >= PROGRESS_MIN_STEP
+                    || now.duration_since(at) >= Duration::from_millis(PROGRESS_INTERVAL_MS)
+            }
+        };
+        if publish {
+            self.last = Some((now, percent));
+        }
+        publish
+    }
+}
+
+/// Lines of rsync stderr kept for a failed move's error message.
+const STDERR_TAIL_LINES: usize = 5;
+/// Longest stderr line kept, in bytes; rsync's own lines are far shorter.
+const STDERR_LINE_MAX: usize = 512;
+
+/// The last few non-empty lines rsync wrote to stderr.
+///
+/// Only the tail is kept so a transfer that logs many warnings can't grow the
+/// buffer, while the lines that explain the exit survive.
+#[derive(Debug, Default)]
+pub(crate) struct StderrTail {
+    lines: std::collections::VecDeque<String>,
+}
+
+impl StderrTail {
+    pub(crate) fn push(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        let mut end = line.len().min(STDERR_LINE_MAX);
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        if self.lines.len() == STDERR_TAIL_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line[..end].to_string());
+    }
+
+    /// The kept lines joined with "; ", or `None` when rsync printed nothing.
+    pub(crate) fn summary(&self) -> Option<String> {
+        (!self.lines.is_empty()).then(|| Vec::from(self.lines.clone()).join("; "))
+    }
+}
+
+/// Settings that shape every rsync invocation, shared by the executor and the
+/// script export so both run the exact same command.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RsyncOptions {
+    /// rsync is new enough for `--info=progress2`.
+    pub use_progress2: bool,
+    /// Keep interrupted transfers in [`PARTIAL_DIR`] so the next run resumes them.
+    pub resume_partial: bool,
+    /// Compress file data in transit, at `compress_level` when set.
+    pub compress: bool,
+    pub compress_level: Option<u32>,
+    /// Copy POSIX ACLs (`-A`) as well as xattrs.
+    pub preserve_acls: bool,
+    /// User-supplied flags, already checked by [`validate_extra_rsync_args`].
+    pub extra_args: Vec<String>,
+    /// Run rsync under `nice -n` at this niceness.
+    pub nice: Option<i32>,
+    /// Run rsync under `ionice -c` in this I/O scheduling class.
+    pub ionice_class: Option<u8>,
+}
+
+impl RsyncOptions {
+    pub(crate) fn from_config(config: &AppConfig, use_progress2: bool) -> Self {
+        Self {
+            use_progress2,
+            resume_partial: config.resume_partial,
+            compress: config.compress_transfers,
+            compress_level: config.compress_level,
+            preserve_acls: config.preserve_acls,
+            extra_args: config.extra_rsync_args.clone(),
+            nice: config.rsync_nice.filter(|_| wrapper_available("nice")),
+            ionice_class: config.rsync_ionice_class.filter(|_| wrapper_available("ionice")),
+        }
+    }
+}
+
+/// Whether `program` is on `PATH`; a missing priority wrapper is logged and
+/// skipped so rsync still runs, just at normal priority.
+fn wrapper_available(program: &str) -> bool {
+    let found = std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|d| d.join(program).is_file()));
+    if !found {
+        tracing::warn!("{} not found on PATH, running rsync without it", program);
+    }
+    found
+}
+
+/// Long options that could delete data, redirect the destination, replace the
+/// transport, or skip or corrupt the staged copy (appending onto a leftover
+/// fragment, or not copying at all). Matched as prefixes, so `--del` also
+/// covers `--delete-after` and `--append` covers `--append-verify`.
+const BLOCKED_LONG_RSYNC_FLAGS: &[&str] = &[
+    "--remove",
+    "--del",
+    "--backup-dir",
+    "--compare-dest",
+    "--copy-dest",
+    "--link-dest",
+    "--partial-dir",
+    "--temp-dir",
+    "--files-from",
+    "--filter",
+    "--exclude",
+    "--include",
+    "--rsh",
+    "--rsync-path",
+    "--read-batch",
+    "--write-batch",
+    "--only-write-batch",
+    "--dry-run",
+    "--copy-links",
+    "--copy-unsafe-links",
+    "--copy-dirlinks",
+    "--no-links",
+    "--append",
+    "--relative",
+    "--existing",
+    "--ignore-existing",
+];
+
+/// Short options with the same effect (`-e` rsh, `-T` temp dir, `-f` filter,
+/// `-n` dry run, `-L`/`-k` dereferencing symlinks, `-R` relative paths).
+/// Rejected anywhere inside a short-option cluster.
+const BLOCKED_SHORT_RSYNC_FLAGS: &[char] = &['e', 'T', 'f', 'F', 'n', 'L', 'k', 'R'];
+
+/// Reject user rsync flags that could delete or misdirect data.
+///
+/// Every entry must be an option; a bare word would become an extra source or
+/// destination path.
+pub(crate) fn validate_extra_rsync_args(args: &[String]) -> Result<()> {
+    for arg in args {
+        if let Some(long) = arg.strip_prefix("--") {
+            let name = long.split('=').next().unwrap_or(long);
+            if BLOCKED_LONG_RSYNC_FLAGS.iter().any(|b| format!("--{name}").starts_with(b)) {
+                bail!("rsync flag '{arg}' is not allowed in extra_rsync_args");
+            }
+        } else if let Some(short) = arg.strip_prefix('-') {
+            if short.is_empty() || short.contains(BLOCKED_SHORT_RSYNC_FLAGS) {
+                bail!("rsync flag '{arg}' is not allowed in extra_rsync_args");
+            }
+        } else {
+            bail!("extra_rsync_args entries must be options, got '{arg}'");
+        }
+    }
+    Ok(())
+}
+
+/// Build the rsync argument list for copying one file from `source` to `target`.
+///
+/// Two-phase move: rsync only copies (no `--remove-source-files`); the source
+/// is deleted by the executor after the copy has been verified.
+///
+/// With `resume_partial`, an interrupted copy is kept in [`PARTIAL_DIR`] and
+/// used as the basis of the next attempt. Local copies default to
+/// `--whole-file`, which would ignore that basis, so delta transfer is forced.
+///
+/// `--links` (already part of `-a`) is spelled out: a symlink is recreated on
+/// the target, never dereferenced into a copy of what it points to.
+pub(crate) fn build_rsync_args(opts: &RsyncOptions, source: &str, target: &str) -> Vec<String> {
+    let mut args = vec!["-avPX".to_string(), "--links".to_string()];
+    if opts.preserve_acls {
+        args.push("-A".to_string());
+    }
+    if opts.use_progress2 {
+        args.push("--info=progress2".to_string());
+    }
+    if opts.resume_partial {
+        args.push(format!("--partial-dir={PARTIAL_DIR}"));
+        args.push("--no-whole-file".to_string());
     }
+    if opts.compress {
+        args.push("--compress".to_string());
+        if let Some(level) = opts.compress_level {
+            args.push(format!("--compress-level={level}"));
+        }
+    }
+    args.extend(opts.extra_args.iter().cloned());
+    args.push(source.to_string());
+    args.push(target.to_string());
+    args
 }
 
-/// Check if rsync supports --info=progress2 (cached after first call).
-pub(crate) async fn rsync_supports_progress2() -> bool {
-    if let Some(&cached) = RSYNC_PROGRESS2.get() {
-        return cached;
+/// The full command line for one rsync copy: `rsync` and its arguments,
+/// prefixed by `ionice -c` and `nice -n` when those priorities are set.
+pub(crate) fn rsync_command(opts: &RsyncOptions, source: &str, target: &str) -> Vec<String> {
+    let mut command = Vec::new();
+    if let Some(class) = opts.ionice_class {
+        command.extend(["ionice".to_string(), "-c".to_string(), class.to_string()]);
+    }
+    if let Some(nice) = opts.nice {
+        command.extend(["nice".to_string(), "-n".to_string(), nice.to_string()]);
     }
-    let result = probe_rsync_progress2().await.unwrap_or(false);
-    *RSYNC_PROGRESS2.get_or_init(|| result)
+    command.push("rsync".to_string());
+    command.extend(build_rsync_args(opts, source, target));
+    command
 }
 
 /// Check if a file is currently open by another process via lsof.
@@ -64,3 +693,115 @@ pub(crate) async fn is_parity_check_running() -> Result<bool> {
         .context("Failed to read /proc/mdstat — cannot verify parity status")?;
     Ok(content.contains("resync =") || content.contains("check ="))
 }
+
+/// Unraid's emhttp state file describing each array disk.
+const UNRAID_DISKS_INI: &str = "/var/local/emhttp/disks.ini";
+
+/// Names of array disks that Unraid reports as spun down.
+///
+/// Array mounts sit on `/dev/mdX` devices, so querying the mount's block
+/// device would not reflect the physical spindle. emhttp's `disks.ini` tracks
+/// the real `spundown` flag per disk name instead.
+pub(crate) async fn spun_down_disks() -> Result<HashSet<String>> {
+    let content = tokio::fs::read_to_string(UNRAID_DISKS_INI)
+        .await
+        .with_context(|| format!("Failed to read {UNRAID_DISKS_INI} — spin state unknown"))?;
+    Ok(parse_spun_down_disks(&content))
+}
+
+/// Parse `disks.ini` sections (`["disk1"]`) and collect those with `spundown="1"`.
+pub(crate) fn parse_spun_down_disks(content: &str) -> HashSet<String> {
+    disks_ini_values(content, "spundown")
+        .filter(|(_, value)| *value == "1")
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Parse `disks.ini` into disk name → block device (`device="sdb"`).
+pub(crate) fn parse_disk_devices(content: &str) -> HashMap<String, String> {
+    disks_ini_values(content, "device")
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Yield `(section, value)` for every `key=` line in `disks.ini`.
+fn disks_ini_values<'a>(
+    content: &'a str,
+    key: &'a str,
+) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+    let mut section: Option<&str> = None;
+    content.lines().map(str::trim).filter_map(move |line| {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Some(name.trim_matches('"'));
+            return None;
+        }
+        let (k, value) = line.split_once('=')?;
+        (k.trim() == key).then_some((section?, value.trim().trim_matches('"')))
+    })
+}
+
+/// What `smartctl -H` reported for one device.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SmartHealth {
+    Passed,
+    /// Parked; not woken just to read its health.
+    Standby,
+    /// No verdict at all, e.g. a USB bridge that doesn't pass SMART through.
+    Unknown,
+    /// A verdict other than passing, with the reason.
+    Failing(String),
+}
+
+/// Interpret `smartctl -H` output (ATA `test result:` or SCSI `Health Status:`).
+pub(crate) fn parse_smart_health(output: &str) -> SmartHealth {
+    for line in output.lines() {
+        let verdict = line
+            .split_once("self-assessment test result:")
+            .or_else(|| line.split_once("SMART Health Status:"))
+            .map(|(_, v)| v.trim());
+        match verdict {
+            Some("PASSED" | "OK") => return SmartHealth::Passed,
+            Some(other) => return SmartHealth::Failing(format!("SMART health is {other}")),
+            None => {}
+        }
+    }
+    if output.contains("STANDBY mode") {
+        SmartHealth::Standby
+    } else {
+        SmartHealth::Unknown
+    }
+}
+
+/// Disks whose SMART health verdict isn't passing, as disk name → reason.
+///
+/// Devices come from `disks.ini`; disks without one (e.g. extra mounts) aren't
+/// checked, nor are disks that give no verdict. `-n standby` keeps spun-down
+/// disks parked.
+pub(crate) async fn smart_failing_disks(names: &[&str]) -> Result<HashMap<String, String>> {
+    let content = tokio::fs::read_to_string(UNRAID_DISKS_INI)
+        .await
+        .with_context(|| format!("Failed to read {UNRAID_DISKS_INI} — disk devices unknown"))?;
+    let devices = parse_disk_devices(&content);
+
+    let mut failing = HashMap::new();
+    for name in names {
+        let Some(device) = devices.get(*name) else {
+            continue;
+        };
+        let output = Command::new("smartctl")
+            .args(["-H", "-n", "standby", &format!("/dev/{device}")])
+            .output()
+            .await
+            .context("Failed to execute smartctl — cannot check disk health")?;
+        match parse_smart_health(&String::from_utf8_lossy(&output.stdout)) {
+            SmartHealth::Passed => {}
+            SmartHealth::Standby => info!("Skipping SMART check of parked disk {}", name),
+            SmartHealth::Unknown => warn!("No SMART verdict for {} (/dev/{})", name, device),
+            SmartHealth::Failing(reason) => {
+                failing.insert((*name).to_string(), reason);
+            }
+        }
+    }
+    Ok(failing)
+}
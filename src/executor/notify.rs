@@ -0,0 +1,92 @@
+use crate::db::PlanStatus;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Per-attempt HTTP timeout for webhook delivery.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Total delivery attempts before giving up.
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+/// Delay between delivery attempts (multiplied by the attempt number).
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Outcome of a plan execution, POSTed to the configured webhook.
+///
+/// `content` (Discord) and `message` (ntfy JSON publish) carry the same
+/// human-readable summary so the generic shape works with both services.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ExecutionReport {
+    pub plan_id: i64,
+    pub status: PlanStatus,
+    pub moves_completed: u32,
+    pub moves_failed: u32,
+    pub moves_skipped: u32,
+    pub duration_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    #[serde(flatten)]
+    report: &'a ExecutionReport,
+    content: &'a str,
+    message: &'a str,
+}
+
+impl ExecutionReport {
+    fn summary(&self) -> String {
+        let text = format!(
+            "Perfectly Balanced: plan {} {} — {} moved, {} failed, {} skipped in {:.0}s",
+            self.plan_id,
+            self.status,
+            self.moves_completed,
+            self.moves_failed,
+            self.moves_skipped,
+            self.duration_seconds
+        );
+        match self.error.as_ref().or(self.cancel_reason.as_ref()) {
+            Some(detail) => format!("{text} ({detail})"),
+            None => text,
+        }
+    }
+}
+
+/// Fire-and-forget webhook delivery. Never affects the move results:
+/// failures are logged and the spawned task simply ends.
+pub(crate) fn notify_execution(url: Option<&str>, report: ExecutionReport) {
+    let Some(url) = url.filter(|u| !u.is_empty()).map(str::to_string) else {
+        return;
+    };
+    tokio::spawn(async move {
+        if let Err(e) = deliver(&url, &report).await {
+            warn!("Webhook notification for plan {} failed: {:#}", report.plan_id, e);
+        }
+    });
+}
+
+async fn deliver(url: &str, report: &ExecutionReport) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+    let summary = report.summary();
+    let payload = WebhookPayload { report, content: &summary, message: &summary };
+
+    let mut last_err = None;
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        match client.post(url).json(&payload).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                info!("Webhook notified for plan {} (attempt {})", report.plan_id, attempt);
+                return Ok(());
+            }
+            Ok(resp) => last_err = Some(anyhow::anyhow!("HTTP {}", resp.status())),
+            Err(e) => last_err = Some(e.into()),
+        }
+        if attempt < WEBHOOK_ATTEMPTS {
+            tokio::time::sleep(WEBHOOK_RETRY_DELAY * attempt).await;
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("no delivery attempts made")))
+}
@@ -1,25 +1,44 @@
 use crate::db::{Database, MoveStatus};
 use anyhow::Result;
-use std::path::Path;
+use serde::Serialize;
 use tracing::{info, warn};
 
+/// What [`cleanup_partial_files`] did with the moves it examined.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct CleanupStats {
+    /// Moves found finished on disk and marked completed.
+    pub completed: usize,
+    /// Staged, partial or stale target files removed.
+    pub cleaned: usize,
+    /// Moves whose source and target were both gone, marked failed.
+    pub data_loss: usize,
+    /// Moves with both files present but a target not proven to be our copy,
+    /// marked failed with both left in place.
+    pub needs_review: usize,
+}
+
 /// Examine the filesystem state for each recovered move and take corrective action.
 ///
 /// Under two-phase move semantics, rsync never deletes the source — only our
-/// `verify_and_remove_source()` does. Crashes can leave us in these states:
+/// `verify_and_remove_source()` does. rsync writes to the target's
+/// [staging path](super::staging_path), which is renamed into place only after
+/// verification, so a stray staging file is always deleted first. Crashes can
+/// then leave us in these states:
 ///
 /// | Source | Target | Action                                                        |
 /// |--------|--------|---------------------------------------------------------------|
-/// | exists | exists | Size+mtime check → complete if verified, else delete target   |
-/// | exists | absent | No action, move stays Pending                                 |
+/// | exists | exists | Size+mtime check on both → complete if verified, else mark     |
+/// |        |        | Failed for review and touch neither file                      |
+/// | exists | absent | No action, move stays Pending (a partial-dir fragment is kept  |
+/// |        |        | so the next run resumes it)                                   |
 /// | absent | exists | Source removal succeeded → mark Completed                     |
 /// | absent | absent | Data loss — mark Failed                                       |
 pub(crate) async fn cleanup_partial_files(
     db: &Database,
     recovered_move_ids: &[i64],
-) -> Result<()> {
+) -> Result<CleanupStats> {
     if recovered_move_ids.is_empty() {
-        return Ok(());
+        return Ok(CleanupStats::default());
     }
 
     let move_infos = db.get_moves_path_info(recovered_move_ids)?;
@@ -27,99 +46,79 @@ pub(crate) async fn cleanup_partial_files(
     let mut completed = 0usize;
     let mut cleaned = 0usize;
     let mut data_loss = 0usize;
+    let mut needs_review = 0usize;
 
     for m in &move_infos {
-        let source = format!("{}/{}", m.source_mount, m.file_path);
-        let target = format!("{}/{}", m.target_mount, m.file_path);
+        let source = super::disk_path(&m.source_mount, &m.file_path);
+        let target = super::disk_path(&m.target_mount, &m.target_path);
+
+        let staged = super::staging_path(&target);
+        if std::fs::symlink_metadata(&staged).is_ok() {
+            if let Err(e) = tokio::fs::remove_file(&staged).await {
+                warn!("Failed to remove staged copy {} for move {}: {}", staged, m.id, e);
+            } else {
+                info!("Removed unfinished staged copy: {}", staged);
+                cleaned += 1;
+            }
+        }
 
-        let source_exists = Path::new(&source).exists();
-        let target_exists = Path::new(&target).exists();
+        // Moved symlinks are links on both sides; don't look through them.
+        let source_exists = std::fs::symlink_metadata(&source).is_ok();
+        let target_exists = std::fs::symlink_metadata(&target).is_ok();
 
         match (source_exists, target_exists) {
             (true, true) => {
-                let target_size = tokio::fs::metadata(&target)
+                // Only a copy renamed into place after verification is ours: it has
+                // the planned size and, since rsync -a keeps it, the planned mtime.
+                // Anything else may be a file that was already there (e.g. under
+                // ON_CONFLICT=overwrite), so neither side is touched.
+                let planned = m.source_mtime;
+                let target_meta = tokio::fs::symlink_metadata(&target).await.ok();
+                let target_ok = target_meta.as_ref().is_some_and(|md| {
+                    md.len() == m.file_size && super::mtime_secs(md) == planned && planned.is_some()
+                });
+                let source_ok = tokio::fs::symlink_metadata(&source)
                     .await
-                    .map(|md| md.len())
-                    .unwrap_or(0);
+                    .is_ok_and(|md| super::mtime_secs(&md) == planned);
 
-                if target_size == m.file_size {
-                    // Target matches expected size — but we need to verify source mtime
-                    // hasn't changed to detect files modified after the crash.
-                    let source_mtime_ok = match m.source_mtime {
-                        Some(planned_mtime) => {
-                            // We have the planned mtime — compare against current source
-                            match tokio::fs::metadata(&source).await {
-                                Ok(meta) => {
-                                    match meta.modified() {
-                                        Ok(current) => {
-                                            let current_epoch = current
-                                                .duration_since(std::time::UNIX_EPOCH)
-                                                .map(|d| d.as_secs() as i64)
-                                                .unwrap_or(0);
-                                            current_epoch == planned_mtime
-                                        }
-                                        Err(_) => true, // Can't read mtime, trust size match
-                                    }
-                                }
-                                Err(_) => true, // Can't stat source, trust size match
-                            }
-                        }
-                        None => true, // No planned mtime (old plan), trust size match
-                    };
-
-                    if source_mtime_ok {
-                        // Verified: target is a complete copy and source is unmodified
-                        if let Err(e) = tokio::fs::remove_file(&source).await {
-                            warn!(
-                                "Move {} recovered as completed but failed to remove source {}: {}",
-                                m.id, source, e
-                            );
-                        } else {
-                            info!(
-                                "Move {} recovered as completed (removed source): {}",
-                                m.id, m.file_path
-                            );
-                        }
-                        db.update_move_status(m.id, MoveStatus::Completed, None)?;
-                        completed += 1;
-                    } else {
-                        // Source was modified after planning — the target is stale.
-                        // Delete target, leave move as Pending for re-execution.
+                if target_ok && source_ok {
+                    // Verified: target is a complete copy and source is unmodified
+                    if let Err(e) = tokio::fs::remove_file(&source).await {
                         warn!(
-                            "Move {} source mtime changed — deleting stale target: {}",
-                            m.id, m.file_path
-                        );
-                        if let Err(e) = tokio::fs::remove_file(&target).await {
-                            warn!(
-                                "Failed to remove stale target {} for move {}: {}",
-                                target, m.id, e
-                            );
-                        } else {
-                            cleanup_empty_parents(&target).await;
-                            cleaned += 1;
-                        }
-                        // Move stays Pending (already reset by recover_stale_states)
-                    }
-                } else {
-                    // Target is partial — delete it and clean up empty dirs
-                    if let Err(e) = tokio::fs::remove_file(&target).await {
-                        warn!(
-                            "Failed to remove partial file {} for move {}: {}",
-                            target, m.id, e
+                            "Move {} recovered as completed but failed to remove source {}: {}",
+                            m.id, source, e
                         );
                     } else {
                         info!(
-                            "Removed partial file ({} bytes vs expected {}): {}",
-                            target_size, m.file_size, target
+                            "Move {} recovered as completed (removed source): {}",
+                            m.id, m.file_path
                         );
-                        cleanup_empty_parents(&target).await;
-                        cleaned += 1;
                     }
-                    // Move stays Pending (already reset by recover_stale_states)
+                    db.update_move_status(m.id, MoveStatus::Completed, None)?;
+                    completed += 1;
+                } else {
+                    let target_size = target_meta.map_or(0, |md| md.len());
+                    warn!(
+                        "Move {} left for review: target {} ({} bytes vs expected {}) \
+                         is not a verified copy of {}",
+                        m.id, target, target_size, m.file_size, source
+                    );
+                    db.update_move_status(
+                        m.id,
+                        MoveStatus::Failed,
+                        Some(
+                            "Needs review: source and an unverified target both exist after crash",
+                        ),
+                    )?;
+                    needs_review += 1;
                 }
             }
             (true, false) => {
-                // No partial file to clean up, move stays Pending
+                // Nothing at the target name; only a verified copy is renamed into
+                // place. A fragment in the partial dir is resumable, so leave it.
+                if super::partial_fragment_path(&staged).is_some_and(|p| p.exists()) {
+                    info!("Move {} has a resumable partial transfer: {}", m.id, m.file_path);
+                }
             }
             (false, true) => {
                 // Under two-phase move: source was removed by verify_and_remove_source
@@ -138,23 +137,21 @@ pub(crate) async fn cleanup_partial_files(
                     MoveStatus::Failed,
                     Some("Data loss: source and target both missing after crash"),
                 )?;
-                warn!(
-                    "Move {} data loss (both source and target missing): {}",
-                    m.id, m.file_path
-                );
+                warn!("Move {} data loss (both source and target missing): {}", m.id, m.file_path);
                 data_loss += 1;
             }
         }
     }
 
-    if completed > 0 || cleaned > 0 || data_loss > 0 {
+    if completed > 0 || cleaned > 0 || data_loss > 0 || needs_review > 0 {
         info!(
-            "Partial file cleanup: {} recovered as completed, {} partial files removed, {} data loss",
-            completed, cleaned, data_loss
+            "Partial file cleanup: {} recovered as completed, {} partial files removed, \
+             {} data loss, {} need review",
+            completed, cleaned, data_loss, needs_review
         );
     }
 
-    Ok(())
+    Ok(CleanupStats { completed, cleaned, data_loss, needs_review })
 }
 
 /// Walk up from a file path removing empty directories, stopping at mount point depth.
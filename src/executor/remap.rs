@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// Prefix rewrites applied to a file's relative path when it lands on the
+/// target disk, so balancing can also tidy the layout.
+///
+/// Configured as comma-separated `from=>to` pairs of disk-relative folders,
+/// e.g. `downloads/complete=>media,Movies (old)=>movies`. Prefixes match whole
+/// path components, and the longest matching prefix wins. Empty keeps every
+/// file at the same relative path it had on the source.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PathRemap {
+    rules: Vec<(String, String)>,
+}
+
+impl PathRemap {
+    /// The relative path a file at `file_path` on the source gets on the target.
+    pub(crate) fn target_path(&self, file_path: &str) -> String {
+        let rel = file_path.trim_start_matches('/');
+        self.rules
+            .iter()
+            .filter_map(|(from, to)| {
+                let rest = rel.strip_prefix(from.as_str())?;
+                (rest.is_empty() || rest.starts_with('/')).then_some((from.len(), to, rest))
+            })
+            .max_by_key(|(len, _, _)| *len)
+            .map_or_else(|| rel.to_string(), |(_, to, rest)| format!("{to}{rest}"))
+    }
+
+    /// A planned move's target relative path: the one recorded when it started
+    /// executing, otherwise what these rules make of its source path.
+    pub(crate) fn target_for(&self, m: &crate::db::PlannedMove) -> String {
+        m.target_path.clone().unwrap_or_else(|| self.target_path(&m.file_path))
+    }
+}
+
+/// Normalize one side of a rule to `a/b` form, rejecting anything that could
+/// climb out of the disk.
+fn rule_path(path: &str, entry: &str) -> anyhow::Result<String> {
+    let trimmed = path.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        anyhow::bail!("empty folder in path remap '{entry}'");
+    }
+    if trimmed.split('/').any(|c| c.is_empty() || c == "." || c == "..") {
+        anyhow::bail!("path remap '{entry}' must use plain folder names");
+    }
+    Ok(trimmed.to_string())
+}
+
+impl std::fmt::Display for PathRemap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries: Vec<String> =
+            self.rules.iter().map(|(from, to)| format!("{from}=>{to}")).collect();
+        f.write_str(&entries.join(","))
+    }
+}
+
+impl std::str::FromStr for PathRemap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut remap = Self::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((from, to)) = entry.split_once("=>") else {
+                anyhow::bail!("path remap '{entry}' must look like 'from=>to'");
+            };
+            let from = rule_path(from, entry)?;
+            if remap.rules.iter().any(|(f, _)| *f == from) {
+                anyhow::bail!("path remap for '{from}' is given twice");
+            }
+            remap.rules.push((from, rule_path(to, entry)?));
+        }
+        Ok(remap)
+    }
+}
+
+impl TryFrom<String> for PathRemap {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<PathRemap> for String {
+    fn from(remap: PathRemap) -> Self {
+        remap.to_string()
+    }
+}
@@ -0,0 +1,98 @@
+use crate::db::{MoveStatus, PlannedMoveDetail};
+use crate::scanner::validation::validate_path;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Render a plan's pending moves as a standalone bash script.
+///
+/// Each move mirrors the executor: create the target directory, run the same
+/// rsync invocation, then remove the source only if the copy's size matches.
+/// Moves whose paths fail the FUSE safety check are emitted as comments only.
+pub(crate) fn render_plan_script(
+    plan_id: i64,
+    moves: &[PlannedMoveDetail],
+    disk_mounts: &HashMap<i64, String>,
+    rsync: &super::RsyncOptions,
+    remap: &super::PathRemap,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "#!/bin/bash");
+    let _ = writeln!(out, "# Perfectly Balanced — plan {plan_id}");
+    let _ = writeln!(out, "#");
+    let _ =
+        writeln!(out, "# SAFETY: every path below is a direct disk path (/mnt/diskX, /mnt/cache).");
+    let _ = writeln!(
+        out,
+        "# Never rewrite these to /mnt/user or /mnt/user0 — copying through Unraid's"
+    );
+    let _ = writeln!(out, "# FUSE layer onto the same file can truncate it and lose data.");
+    let _ = writeln!(out, "#");
+    let _ = writeln!(out, "# Each source is removed only after rsync succeeds and sizes match.");
+    let _ = writeln!(out, "set -u");
+    let _ = writeln!(out);
+
+    let mut pending: Vec<&PlannedMoveDetail> =
+        moves.iter().filter(|m| m.move_info.status == MoveStatus::Pending).collect();
+    pending.sort_by_key(|m| (m.move_info.phase, m.move_info.move_order));
+
+    for detail in pending {
+        let m = &detail.move_info;
+        let (Some(src_mount), Some(tgt_mount)) =
+            (disk_mounts.get(&m.source_disk_id), disk_mounts.get(&m.target_disk_id))
+        else {
+            let _ = writeln!(out, "# SKIPPED move {}: unknown disk for {}", m.id, m.file_path);
+            continue;
+        };
+
+        let source = super::disk_path(src_mount, &m.file_path);
+        let target = super::disk_path(tgt_mount, &remap.target_for(m));
+        let _ = writeln!(
+            out,
+            "# move {} (phase {}, order {}): {} -> {}, {} bytes",
+            m.id,
+            m.phase,
+            m.move_order,
+            detail.source_disk_name,
+            detail.target_disk_name,
+            m.file_size
+        );
+
+        if let Err(e) = validate_path(&source).and_then(|()| validate_path(&target)) {
+            let _ = writeln!(out, "# SKIPPED: {}", e.to_string().replace('\n', " "));
+            let _ = writeln!(out);
+            continue;
+        }
+
+        let parent = std::path::Path::new(&target)
+            .parent()
+            .map_or_else(|| tgt_mount.clone(), |p| p.to_string_lossy().to_string());
+        // The program names stay bare so the script reads like a hand-written one.
+        let command: Vec<String> = super::rsync_command(rsync, &source, &target)
+            .iter()
+            .map(|a| {
+                if matches!(a.as_str(), "ionice" | "nice" | "rsync") {
+                    a.clone()
+                } else {
+                    shell_quote(a)
+                }
+            })
+            .collect();
+
+        let _ = writeln!(out, "mkdir -p {} && \\", shell_quote(&parent));
+        let _ = writeln!(out, "{} && \\", command.join(" "));
+        let _ = writeln!(
+            out,
+            "[ \"$(stat -c %s {src})\" = \"$(stat -c %s {tgt})\" ] && rm {src}",
+            src = shell_quote(&source),
+            tgt = shell_quote(&target)
+        );
+        let _ = writeln!(out);
+    }
+
+    out
+}
+
+/// Single-quote a string for bash, escaping embedded single quotes.
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
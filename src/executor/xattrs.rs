@@ -0,0 +1,115 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// xattrs the kernel uses to store POSIX ACLs; only copied by rsync's `-A`.
+const ACL_XATTRS: &[&str] = &["system.posix_acl_access", "system.posix_acl_default"];
+
+/// Compare the extended attributes of `source` and `target`.
+///
+/// Returns a description of the first difference, or `None` when they match.
+pub(crate) fn xattr_mismatch(
+    source: &str,
+    target: &str,
+    include_acls: bool,
+) -> Result<Option<String>> {
+    Ok(diff_xattrs(&read_xattrs(source)?, &read_xattrs(target)?, include_acls))
+}
+
+/// Describe the first name or value that differs between two xattr sets.
+///
+/// ACLs are compared only with `include_acls`, since without `-A` rsync
+/// leaves them behind on purpose.
+pub(crate) fn diff_xattrs(
+    src: &BTreeMap<String, Vec<u8>>,
+    tgt: &BTreeMap<String, Vec<u8>>,
+    include_acls: bool,
+) -> Option<String> {
+    let compared = |name: &str| include_acls || !ACL_XATTRS.contains(&name);
+    for (name, value) in src.iter().filter(|(n, _)| compared(n)) {
+        match tgt.get(name) {
+            None => return Some(format!("extended attribute {name} missing on target")),
+            Some(v) if v != value => {
+                return Some(format!("extended attribute {name} differs on target"));
+            }
+            Some(_) => {}
+        }
+    }
+    tgt.keys()
+        .find(|name| compared(name) && !src.contains_key(*name))
+        .map(|name| format!("extended attribute {name} only present on target"))
+}
+
+/// Size probes to retry when the list or a value grows before it is fetched.
+#[cfg(target_os = "linux")]
+const XATTR_FETCH_ATTEMPTS: usize = 5;
+
+/// Read every extended attribute of `path` (not following symlinks).
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)] // l*xattr() only write within the buffer length they are given
+pub(crate) fn read_xattrs(path: &str) -> Result<BTreeMap<String, Vec<u8>>> {
+    use anyhow::Context;
+    use std::ffi::CString;
+
+    let c_path = CString::new(path)?;
+    let names = fetch_sized(|buf| {
+        os_result(unsafe { libc::llistxattr(c_path.as_ptr(), buf.as_mut_ptr().cast(), buf.len()) })
+    })
+    .with_context(|| format!("listxattr failed for {path}"))?;
+
+    let mut attrs = BTreeMap::new();
+    for name in names.split(|&b| b == 0).filter(|n| !n.is_empty()) {
+        let c_name = CString::new(name)?;
+        let value = fetch_sized(|buf| {
+            os_result(unsafe {
+                libc::lgetxattr(
+                    c_path.as_ptr(),
+                    c_name.as_ptr(),
+                    buf.as_mut_ptr().cast(),
+                    buf.len(),
+                )
+            })
+        })
+        .with_context(|| format!("getxattr failed for {path}"))?;
+        attrs.insert(String::from_utf8_lossy(name).into_owned(), value);
+    }
+    Ok(attrs)
+}
+
+/// A syscall's byte count, or the `errno` it failed with.
+#[cfg(target_os = "linux")]
+fn os_result(n: libc::ssize_t) -> std::io::Result<usize> {
+    if n < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+/// Run a size-probe-then-fetch xattr call: `call` with an empty buffer returns
+/// the size, then with a buffer that big fills it. A list or value that grows
+/// in between fails the fetch with `ERANGE`, so that probes again instead of
+/// failing the whole verification.
+#[cfg(target_os = "linux")]
+pub(crate) fn fetch_sized(
+    mut call: impl FnMut(&mut [u8]) -> std::io::Result<usize>,
+) -> std::io::Result<Vec<u8>> {
+    let mut attempt = 1;
+    loop {
+        let mut buf = vec![0u8; call(&mut [])?];
+        match call(&mut buf) {
+            Ok(copied) => {
+                buf.truncate(copied);
+                return Ok(buf);
+            }
+            Err(e) if e.raw_os_error() == Some(libc::ERANGE) && attempt < XATTR_FETCH_ATTEMPTS => {
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Fallback for non-Linux platforms (development on macOS/Windows).
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn read_xattrs(_path: &str) -> Result<BTreeMap<String, Vec<u8>>> {
+    Ok(BTreeMap::new())
+}
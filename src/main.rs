@@ -10,46 +10,48 @@ mod config;
 mod db;
 mod events;
 mod executor;
+mod metrics;
 mod scanner;
+mod scheduler;
 mod state;
 
 #[cfg(test)]
 mod tests;
 
+use config::defaults::DEFAULT_LOCK_PATH;
 use config::AppConfig;
 use db::Database;
 use events::EventHub;
-pub use state::{AppState, DaemonState, DaemonStatus};
+pub use state::{AppState, DaemonState, DaemonStatus, ExecutionProgress};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "perfectly_balanced=info,tower_http=info".into()),
-        )
-        .init();
+    init_logging();
 
     info!("Perfectly Balanced v{} starting up", env!("CARGO_PKG_VERSION"));
 
-    let config = AppConfig::load()?;
+    let mut config = AppConfig::load()?;
     info!("Configuration loaded: port={}, db_path={}", config.port, config.db_path);
 
-    // Acquire exclusive file lock to prevent dual daemon instances.
-    // The lock is held for the process lifetime via _lock_guard.
-    let lock_path = std::path::Path::new(&config.db_path)
-        .parent()
-        .unwrap_or_else(|| std::path::Path::new("/tmp"))
-        .join("perfectly-balanced.lock");
-    let lock_file = std::fs::File::create(&lock_path)
-        .with_context(|| format!("Failed to create lock file: {}", lock_path.display()))?;
-    try_lock_exclusive(&lock_file, &lock_path)?;
-    let _lock_guard = lock_file; // Hold for process lifetime
+    let event_hub = EventHub::new(256);
+    let (db_path, fallback_reason) = catalog_path(&config)?;
+
+    // Acquire exclusive file lock to prevent dual daemon instances, before the
+    // catalog is opened. Held until shutdown has finished via lock_guard.
+    let lock_path = std::env::var("PB_LOCK_PATH")
+        .map_or_else(|_| std::path::PathBuf::from(DEFAULT_LOCK_PATH), std::path::PathBuf::from);
+    let lock_guard = acquire_instance_lock(&lock_path)?;
     info!("Acquired exclusive lock: {}", lock_path.display());
 
-    let db = Database::open(&config.db_path)?;
+    let wait = Duration::from_secs(config.db_open_timeout_secs);
+    let db = Database::open_with_retry(&db_path, config.db_busy_timeout_ms, wait)?;
     db.run_migrations()?;
-    info!("Database initialized at {}", config.db_path);
+    info!("Database initialized at {}", db_path);
+    if config.auto_vacuum_on_start {
+        if let Err(e) = db.vacuum() {
+            warn!("Startup vacuum failed: {:#}", e);
+        }
+    }
 
     // --- Startup recovery: fix stale states left by previous crash ---
     let recovery = db.recover_stale_states()?;
@@ -57,9 +59,17 @@ async fn main() -> Result<()> {
         executor::recovery::cleanup_partial_files(&db, &recovery.recovered_move_ids).await?;
     }
 
-    let event_hub = EventHub::new(256);
-
+    // The running state describes the catalog actually in use; the saved
+    // CATALOG_PATH is left as configured.
+    config.db_path.clone_from(&db_path);
     let state = Arc::new(AppState::new(db, config.clone(), event_hub));
+    if let Some(reason) = fallback_reason {
+        let _ = state.catalog_fallback.set(reason);
+    }
+    // Probe rsync up front so a missing binary shows in the log and /api/status.
+    state.rsync_info().await;
+
+    scheduler::spawn(Arc::clone(&state));
 
     let app = api::router(Arc::clone(&state));
 
@@ -73,7 +83,7 @@ async fn main() -> Result<()> {
     info!("Shutting down...");
 
     // 1. Cancel any running operation
-    state.request_cancel().await;
+    state.request_cancel("daemon shutting down").await;
 
     // 2. Kill any running rsync child process
     let rsync_child = state.rsync_child.lock().await.take();
@@ -94,10 +104,73 @@ async fn main() -> Result<()> {
         }
     }
 
+    drop(lock_guard);
     info!("Perfectly Balanced shut down cleanly");
     Ok(())
 }
 
+/// Pick the catalog path, waiting out a slow-to-mount flash drive.
+///
+/// Retries creating `db_path`'s directory for `db_open_timeout_secs`; if that
+/// still fails and `db_fallback_path` is set, uses that for this run and
+/// returns why alongside it, for `/api/status` to show the catalog isn't
+/// where it should be.
+fn catalog_path(config: &AppConfig) -> Result<(String, Option<String>)> {
+    let wait = Duration::from_secs(config.db_open_timeout_secs);
+    let err = match Database::create_dir_with_retry(&config.db_path, wait) {
+        Ok(()) => return Ok((config.db_path.clone(), None)),
+        Err(e) => e,
+    };
+    let Some(fallback) = &config.db_fallback_path else {
+        return Err(err);
+    };
+    let message = format!(
+        "Catalog at {} unavailable after {}s ({:#}); using fallback {}",
+        config.db_path, config.db_open_timeout_secs, err, fallback
+    );
+    warn!("{message}");
+    Database::create_dir_with_retry(fallback, Duration::ZERO)
+        .with_context(|| format!("Fallback catalog also failed: {fallback}"))?;
+    Ok((fallback.clone(), Some(message)))
+}
+
+/// Take the single-instance lock at `lock_path` and record our PID in it.
+///
+/// The file is opened without truncating so a refused second instance leaves
+/// the running daemon's PID in place and can name it in the error.
+fn acquire_instance_lock(lock_path: &std::path::Path) -> Result<std::fs::File> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path)
+        .with_context(|| format!("Failed to create lock file: {}", lock_path.display()))?;
+    try_lock_exclusive(&file, lock_path)?;
+    file.set_len(0)?;
+    writeln!(file, "{}", std::process::id())?;
+    Ok(file)
+}
+
+/// Install the global tracing subscriber.
+///
+/// `PB_LOG_FORMAT=json` emits one JSON object per line (with the current span's
+/// fields, e.g. plan_id and move_id) for log aggregators; anything else keeps
+/// the human-readable format. Chosen once at startup.
+fn init_logging() {
+    let builder = tracing_subscriber::fmt().with_env_filter(
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| "perfectly_balanced=info,tower_http=info".into()),
+    );
+    if std::env::var("PB_LOG_FORMAT").is_ok_and(|v| v.eq_ignore_ascii_case("json")) {
+        builder.json().with_current_span(true).with_span_list(false).init();
+    } else {
+        builder.init();
+    }
+}
+
 /// Try to acquire an exclusive flock on the given file.
 /// Replaces the unmaintained `fs2` crate with a direct `libc::flock` call.
 #[allow(unsafe_code)] // flock() is a safe POSIX operation; no memory unsafety
@@ -106,8 +179,13 @@ fn try_lock_exclusive(file: &std::fs::File, lock_path: &std::path::Path) -> Resu
     let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
     if ret != 0 {
         let err = std::io::Error::last_os_error();
+        let holder = std::fs::read_to_string(lock_path)
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .map_or_else(String::new, |pid| format!(" as PID {pid}"));
         anyhow::bail!(
-            "Another perfectly-balanced instance is already running (lock: {}): {}",
+            "Another perfectly-balanced instance is already running{} (lock: {}): {}",
+            holder,
             lock_path.display(),
             err
         );
@@ -0,0 +1,71 @@
+use crate::DaemonState;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Process-lifetime counters exposed at `/api/metrics` in Prometheus text format.
+///
+/// Counters are bumped by the executor and scanner as work completes; gauges
+/// that depend on the catalog (e.g. imbalance) are computed at scrape time.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    moves_completed: AtomicU64,
+    moves_failed: AtomicU64,
+    moves_skipped: AtomicU64,
+    bytes_moved: AtomicU64,
+    scan_files: AtomicU64,
+}
+
+impl Metrics {
+    pub fn record_move_completed(&self, bytes: u64) {
+        self.moves_completed.fetch_add(1, Ordering::Relaxed);
+        self.bytes_moved.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_move_failed(&self) {
+        self.moves_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_move_skipped(&self) {
+        self.moves_skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_files_scanned(&self, files: u64) {
+        self.scan_files.fetch_add(files, Ordering::Relaxed);
+    }
+
+    /// Render all metrics in Prometheus exposition format.
+    ///
+    /// `imbalance` is omitted when it cannot be computed (e.g. no disks cataloged yet).
+    pub fn render(&self, daemon_state: DaemonState, imbalance: Option<f64>) -> String {
+        let mut out = String::new();
+
+        let counters = [
+            ("pb_moves_completed_total", "Moves completed successfully", &self.moves_completed),
+            ("pb_moves_failed_total", "Moves that failed", &self.moves_failed),
+            ("pb_moves_skipped_total", "Moves skipped by a safety check", &self.moves_skipped),
+            ("pb_bytes_moved_total", "Bytes moved by completed moves", &self.bytes_moved),
+            ("pb_scan_files_total", "Files cataloged by scans", &self.scan_files),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+        }
+
+        if let Some(imbalance) = imbalance {
+            let _ =
+                writeln!(out, "# HELP pb_current_imbalance Max deviation from target utilization");
+            let _ = writeln!(out, "# TYPE pb_current_imbalance gauge");
+            let _ = writeln!(out, "pb_current_imbalance {imbalance}");
+        }
+
+        let _ = writeln!(out, "# HELP pb_daemon_state Current daemon state (1 = active)");
+        let _ = writeln!(out, "# TYPE pb_daemon_state gauge");
+        for state in DaemonState::ALL {
+            let active = u8::from(state == daemon_state);
+            let _ = writeln!(out, "pb_daemon_state{{state=\"{}\"}} {active}", state.as_str());
+        }
+
+        out
+    }
+}
@@ -1,5 +1,7 @@
+use super::validation::validate_path;
 use anyhow::{bail, Result};
 use std::path::Path;
+use tracing::warn;
 
 /// A disk discovered in the Unraid /mnt/ mount hierarchy.
 pub(crate) struct DiscoveredDisk {
@@ -8,7 +10,14 @@ pub(crate) struct DiscoveredDisk {
 }
 
 /// Discover Unraid array disks by reading /mnt/ mount points.
-pub(crate) fn discover_disks(mnt_base: &str) -> Result<Vec<DiscoveredDisk>> {
+///
+/// `extra_mount_paths` registers additional mountpoints (ZFS pools, custom-named
+/// pools) that don't follow the `diskN`/`cacheN` naming. They are named after
+/// their last path component and merged with the auto-discovered set.
+pub(crate) fn discover_disks(
+    mnt_base: &str,
+    extra_mount_paths: &[String],
+) -> Result<Vec<DiscoveredDisk>> {
     let mnt_path = Path::new(mnt_base);
     if !mnt_path.exists() {
         bail!("Mount base path does not exist: {mnt_base}");
@@ -36,6 +45,34 @@ pub(crate) fn discover_disks(mnt_base: &str) -> Result<Vec<DiscoveredDisk>> {
         }
     }
 
+    for extra in extra_mount_paths {
+        if let Some(disk) = extra_mount(extra, &disks) {
+            disks.push(disk);
+        }
+    }
+
     disks.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(disks)
 }
+
+/// Validate one configured extra mountpoint against those already discovered.
+fn extra_mount(path: &str, known: &[DiscoveredDisk]) -> Option<DiscoveredDisk> {
+    let mount_path = path.trim_end_matches('/');
+    if let Err(e) = validate_path(mount_path) {
+        warn!("Ignoring extra mount path: {}", e);
+        return None;
+    }
+    if !Path::new(mount_path).is_dir() {
+        warn!("Ignoring extra mount path {}: not a directory", mount_path);
+        return None;
+    }
+    let name = Path::new(mount_path).file_name()?.to_string_lossy().to_string();
+    if known.iter().any(|d| d.mount_path == mount_path) {
+        return None;
+    }
+    if known.iter().any(|d| d.name == name) {
+        warn!("Ignoring extra mount path {}: disk name '{}' is already in use", mount_path, name);
+        return None;
+    }
+    Some(DiscoveredDisk { name, mount_path: mount_path.to_string() })
+}
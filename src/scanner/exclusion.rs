@@ -0,0 +1,42 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Compiled set of glob patterns for files that should never be cataloged.
+///
+/// Patterns are anchored against the disk-relative path (e.g. `movies/a.mkv`,
+/// not `/mnt/disk1/movies/a.mkv`). A pattern that matches a directory also
+/// excludes everything beneath it, so `appdata` behaves like `appdata/**`.
+#[derive(Debug, Clone)]
+pub(crate) struct ExcludeMatcher {
+    set: GlobSet,
+}
+
+impl ExcludeMatcher {
+    /// Compile the given patterns once for reuse across every scanned entry.
+    pub(crate) fn new(patterns: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern)
+                .with_context(|| format!("Invalid exclude pattern '{pattern}'"))?;
+            builder.add(glob);
+        }
+        Ok(Self { set: builder.build()? })
+    }
+
+    /// A matcher that excludes nothing.
+    pub(crate) const fn empty() -> Self {
+        Self { set: GlobSet::empty() }
+    }
+
+    /// Check whether a disk-relative path (or any of its parent folders) is excluded.
+    pub(crate) fn is_excluded(&self, relative_path: &Path) -> bool {
+        if self.set.is_empty() {
+            return false;
+        }
+        relative_path
+            .ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .any(|p| self.set.is_match(p))
+    }
+}
@@ -1,8 +1,12 @@
 mod discovery;
 mod disk_space;
-mod scan;
+mod exclusion;
+pub(crate) mod mounts;
+pub(crate) mod scan;
 pub(crate) mod validation;
 
 pub(crate) use discovery::{discover_disks, DiscoveredDisk};
 pub(crate) use disk_space::get_disk_space;
-pub(crate) use scan::{scan_disk, ScanContext};
+pub(crate) use exclusion::ExcludeMatcher;
+pub(crate) use mounts::{mount_entry, mount_problem, parse_mount_table};
+pub(crate) use scan::{scan_disk, ScanContext, ScanStats, SymlinkPolicy};
@@ -0,0 +1,129 @@
+use crate::config::defaults::UNRAID_MNT_BASE;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// One mountpoint from `/proc/mounts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MountInfo {
+    pub fs_type: String,
+    pub read_only: bool,
+}
+
+/// Read `/proc/mounts`, keyed by mountpoint. Empty when it can't be read
+/// (e.g. off Linux), in which case mount presence isn't checked.
+pub(crate) fn parse_mount_table() -> HashMap<String, MountInfo> {
+    std::fs::read_to_string("/proc/mounts").map(|m| parse_mounts(&m)).unwrap_or_default()
+}
+
+/// Parse `/proc/mounts` contents: device, mountpoint, fs type, options, ...
+///
+/// Mountpoints come back with the kernel's octal escapes (`\040` for a space)
+/// decoded.
+pub(crate) fn parse_mounts(contents: &str) -> HashMap<String, MountInfo> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (mountpoint, fs_type) = (parts.get(1)?, parts.get(2)?);
+            let read_only = parts.get(3).is_some_and(|opts| opts.split(',').any(|o| o == "ro"));
+            Some((
+                unescape_octal(mountpoint),
+                MountInfo { fs_type: fs_type.to_string(), read_only },
+            ))
+        })
+        .collect()
+}
+
+/// Decode `\ooo` octal escapes, which `/proc/mounts` uses for spaces, tabs,
+/// newlines and backslashes in paths.
+fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while let Some(&byte) = bytes.get(i) {
+        let code = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| byte == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        if let Some(code) = code {
+            out.push(code);
+            i += 4;
+        } else {
+            out.push(byte);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `mount_path` in the form `/proc/mounts` lists it: canonical when it exists,
+/// otherwise without trailing slashes.
+fn normalized(mount_path: &str) -> PathBuf {
+    std::fs::canonicalize(mount_path).unwrap_or_else(|_| {
+        let trimmed = mount_path.trim_end_matches('/');
+        PathBuf::from(if trimmed.is_empty() { "/" } else { trimmed })
+    })
+}
+
+/// The `table` entry for the filesystem mounted exactly at `mount_path`, if any.
+pub(crate) fn mount_entry<'a>(
+    mount_path: &str,
+    table: &'a HashMap<String, MountInfo>,
+) -> Option<&'a MountInfo> {
+    table.get(normalized(mount_path).to_str()?)
+}
+
+/// Whether `path` is the root of a filesystem: on a different device than its
+/// parent. Catches mountpoints missing from the table, e.g. in a container.
+fn is_mountpoint(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return true;
+    };
+    match (std::fs::metadata(path), std::fs::metadata(parent)) {
+        (Ok(own), Ok(parent)) => own.dev() != parent.dev(),
+        _ => false,
+    }
+}
+
+/// Why `mount_path` can't be used right now, or `None` when it can.
+///
+/// A missing path is "not mounted". So is a plain directory directly under
+/// `/mnt`, which on Unraid is a disk whose filesystem isn't mounted over its
+/// mountpoint; other plain directories (a dataset subfolder, a dev layout
+/// under `PB_MNT_BASE`) are used as they are. With `need_write`, also checks
+/// it isn't mounted read-only and a probe file can be created and removed on it.
+pub(crate) fn mount_problem(
+    mount_path: &str,
+    table: &HashMap<String, MountInfo>,
+    need_write: bool,
+) -> Option<String> {
+    let path = normalized(mount_path);
+    let entry = mount_entry(mount_path, table);
+    let unmounted = if path.exists() {
+        entry.is_none()
+            && !is_mountpoint(&path)
+            && path.parent() == Some(Path::new(UNRAID_MNT_BASE))
+    } else {
+        entry.is_none()
+    };
+    if unmounted {
+        return Some(format!("{mount_path} is not mounted"));
+    }
+    if !need_write {
+        return None;
+    }
+    if entry.is_some_and(|m| m.read_only) {
+        return Some(format!("{mount_path} is mounted read-only"));
+    }
+    let probe = Path::new(mount_path).join(".perfectly-balanced-write-probe");
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            None
+        }
+        Err(e) => Some(format!("{mount_path} is not writable: {e}")),
+    }
+}
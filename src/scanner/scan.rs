@@ -1,15 +1,60 @@
+use super::exclusion::ExcludeMatcher;
 use super::validation::validate_path;
 use crate::db::FileInsert;
 use crate::events::{Event, EventHub};
 use anyhow::{bail, Result};
 use jwalk::{Parallelism, WalkDir};
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 /// Minimum interval between SSE progress updates (milliseconds).
 const PROGRESS_INTERVAL_MS: u64 = 500;
+/// Highest percent reported while walking; only `ScanDiskComplete` means done.
+const MAX_WALK_PERCENT: f64 = 99.0;
+
+/// What a scan does with symbolic links.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// Leave links out of the catalog, so they are never moved.
+    #[default]
+    Skip,
+    /// Catalog the link itself at its own size; a move recreates the link.
+    Catalog,
+    /// Catalog the link at the size of the file it points to, for display.
+    /// The link, not that file, is what a move would carry, so the planner
+    /// never picks it.
+    Follow,
+}
+
+impl SymlinkPolicy {
+    /// The snake_case name used in the config file and JSON API.
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Catalog => "catalog",
+            Self::Follow => "follow",
+        }
+    }
+}
+
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "catalog" => Ok(Self::Catalog),
+            "follow" => Ok(Self::Follow),
+            _ => bail!("unknown symlink policy '{s}'"),
+        }
+    }
+}
 
 /// All context needed to scan a single disk.
 pub(crate) struct ScanContext<'a> {
@@ -21,6 +66,12 @@ pub(crate) struct ScanContext<'a> {
     pub num_threads: usize,
     /// Directory to exclude from scanning (e.g. the catalog DB's parent dir).
     pub exclude_dir: Option<&'a Path>,
+    /// User-configured glob patterns for files to leave out of the catalog.
+    pub exclude_patterns: &'a ExcludeMatcher,
+    /// Disk-relative top-level folders to walk; empty walks the whole disk.
+    pub include_roots: &'a [String],
+    /// Whether symlinks are skipped, cataloged as links, or followed.
+    pub symlink_policy: SymlinkPolicy,
 }
 
 /// Statistics from scanning a single disk.
@@ -80,9 +131,7 @@ pub(crate) fn scan_disk(ctx: &ScanContext<'_>) -> Result<ScanStats> {
 fn process_dir_entry(
     entry: &jwalk::DirEntry<((), ())>,
     mount: &Path,
-    mount_path: &str,
-    disk_id: i64,
-    exclude_dir: Option<&Path>,
+    ctx: &ScanContext<'_>,
 ) -> Option<FileInsert> {
     let entry_path = entry.path();
 
@@ -90,14 +139,41 @@ fn process_dir_entry(
         return None;
     }
 
+    // Interrupted transfers parked by rsync, and copies not yet renamed into
+    // place, are not real files yet.
+    if entry_path.components().any(|c| c.as_os_str() == crate::executor::PARTIAL_DIR)
+        || entry_path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().ends_with(crate::executor::STAGING_SUFFIX))
+    {
+        return None;
+    }
+
     // Skip entries inside the excluded directory (e.g. the catalog DB dir).
-    if let Some(excl) = exclude_dir {
+    if let Some(excl) = ctx.exclude_dir {
         if entry_path.starts_with(excl) {
             return None;
         }
     }
 
-    let metadata = match entry.metadata() {
+    // Never stat through a link unless asked to; a link's target may be on
+    // another disk entirely.
+    let mut followed_link = false;
+    let metadata = match std::fs::symlink_metadata(&entry_path) {
+        Ok(m) if m.file_type().is_symlink() => match ctx.symlink_policy {
+            SymlinkPolicy::Skip => return None,
+            SymlinkPolicy::Catalog => m,
+            SymlinkPolicy::Follow => match std::fs::metadata(&entry_path) {
+                Ok(target) => {
+                    followed_link = true;
+                    target
+                }
+                Err(err) => {
+                    warn!("Cannot follow symlink {}: {}", entry_path.display(), err);
+                    return None;
+                }
+            },
+        },
         Ok(m) => m,
         Err(err) => {
             warn!("Cannot read metadata for {}: {}", entry_path.display(), err);
@@ -116,7 +192,11 @@ fn process_dir_entry(
         return None;
     }
 
-    let relative_path = entry_path.strip_prefix(mount_path).ok()?.to_string_lossy().to_string();
+    let relative = entry_path.strip_prefix(ctx.mount_path).ok()?;
+    if ctx.exclude_patterns.is_excluded(relative) {
+        return None;
+    }
+    let relative_path = relative.to_string_lossy().to_string();
 
     let mtime = metadata
         .modified()
@@ -125,62 +205,108 @@ fn process_dir_entry(
         .map(|d| d.as_secs() as i64);
 
     Some(FileInsert {
-        disk_id,
+        disk_id: ctx.disk_id,
         file_path: relative_path,
         size_bytes: metadata.len(),
         mtime,
+        inode: Some(metadata.ino()),
+        nlink: metadata.nlink(),
+        followed_link,
     })
 }
 
+/// Estimated percent of a disk walked, against the file count its previous
+/// scan found. Without a previous scan there is nothing to measure against,
+/// so it stays 0; a disk that grew since is held just short of 100.
+pub(crate) fn scan_percent(files_scanned: u64, expected_files: Option<u64>) -> f64 {
+    match expected_files {
+        Some(expected) if expected > 0 => {
+            (files_scanned as f64 / expected as f64 * 100.0).min(MAX_WALK_PERCENT)
+        }
+        _ => 0.0,
+    }
+}
+
 fn run_walk(ctx: &ScanContext<'_>, disk_name: &str) -> Result<WalkResult> {
     let mut files_scanned = 0u64;
     let mut bytes_cataloged = 0u64;
     let start = Instant::now();
     let mut last_progress = Instant::now();
     let mount = Path::new(ctx.mount_path);
+    // The last scan's total is a cheap denominator, and close enough for a progress bar.
+    let expected_files = match ctx.db.get_recent_snapshots(ctx.disk_id, 1) {
+        Ok(snapshots) => snapshots.first().map(|s| s.total_files),
+        Err(e) => {
+            warn!("No scan progress estimate for {}: {}", ctx.mount_path, e);
+            None
+        }
+    };
 
     let mut all_files: Vec<FileInsert> = Vec::new();
+    // Hardlinked inodes already counted, so shared data adds to the total once.
+    let mut linked_inodes: HashSet<u64> = HashSet::new();
 
-    let parallelism = if ctx.num_threads > 1 {
-        Parallelism::RayonNewPool(ctx.num_threads)
+    // With include roots, only those subtrees are walked at all.
+    let walk_roots: Vec<PathBuf> = if ctx.include_roots.is_empty() {
+        vec![mount.to_path_buf()]
     } else {
-        Parallelism::Serial
+        ctx.include_roots
+            .iter()
+            .map(|root| mount.join(root))
+            .filter(|root| {
+                let is_dir = root.is_dir();
+                if !is_dir {
+                    info!("Include root {} not present, skipping", root.display());
+                }
+                is_dir
+            })
+            .collect()
     };
 
-    let walker = WalkDir::new(ctx.mount_path).parallelism(parallelism).skip_hidden(false);
+    for walk_root in &walk_roots {
+        let parallelism = if ctx.num_threads > 1 {
+            Parallelism::RayonNewPool(ctx.num_threads)
+        } else {
+            Parallelism::Serial
+        };
 
-    for entry_result in walker {
-        if ctx.cancel.is_cancelled() {
-            info!("Scan cancelled for {}", ctx.mount_path);
-            bail!("Scan cancelled");
-        }
+        let walker = WalkDir::new(walk_root).parallelism(parallelism).skip_hidden(false);
 
-        let entry = match entry_result {
-            Ok(e) => e,
-            Err(err) => {
-                warn!("Error reading directory entry: {}", err);
-                continue;
+        for entry_result in walker {
+            if ctx.cancel.is_cancelled() {
+                info!("Scan cancelled for {}", ctx.mount_path);
+                bail!("Scan cancelled");
             }
-        };
 
-        let Some(insert) =
-            process_dir_entry(&entry, mount, ctx.mount_path, ctx.disk_id, ctx.exclude_dir)
-        else {
-            continue;
-        };
+            let entry = match entry_result {
+                Ok(e) => e,
+                Err(err) => {
+                    warn!("Error reading directory entry: {}", err);
+                    continue;
+                }
+            };
 
-        files_scanned += 1;
-        bytes_cataloged += insert.size_bytes;
-        all_files.push(insert);
-
-        if last_progress.elapsed().as_millis() >= u128::from(PROGRESS_INTERVAL_MS) {
-            let _ = ctx.event_hub.publish(Event::ScanProgress {
-                disk: disk_name.to_string(),
-                files_scanned,
-                bytes_cataloged,
-                percent: 0.0,
-            });
-            last_progress = Instant::now();
+            let Some(insert) = process_dir_entry(&entry, mount, ctx) else {
+                continue;
+            };
+
+            files_scanned += 1;
+            let first_link =
+                insert.nlink <= 1 || insert.inode.is_none_or(|i| linked_inodes.insert(i));
+            if first_link {
+                bytes_cataloged += insert.size_bytes;
+            }
+            all_files.push(insert);
+
+            if last_progress.elapsed().as_millis() >= u128::from(PROGRESS_INTERVAL_MS) {
+                let _ = ctx.event_hub.publish(Event::ScanProgress {
+                    disk: disk_name.to_string(),
+                    files_scanned,
+                    bytes_cataloged,
+                    percent: scan_percent(files_scanned, expected_files),
+                });
+                last_progress = Instant::now();
+            }
         }
     }
 
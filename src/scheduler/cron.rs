@@ -0,0 +1,124 @@
+use anyhow::{bail, ensure, Context, Result};
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+
+/// How far ahead `next_after` searches before giving up (covers Feb 29 schedules).
+const SEARCH_HORIZON_DAYS: i64 = 366 * 5;
+
+/// A parsed five-field cron expression: minute, hour, day-of-month, month, day-of-week.
+///
+/// Each field accepts `*`, single values, `a-b` ranges, `/n` steps and comma lists.
+/// Day-of-week runs 0-7 with both 0 and 7 meaning Sunday. As in classic cron, when
+/// both day fields are restricted a day matching either one fires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+impl CronSchedule {
+    pub(crate) fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        ensure!(
+            fields.len() == 5,
+            "cron expression must have 5 fields, got {}: '{expr}'",
+            fields.len()
+        );
+
+        let mut days_of_week = parse_field(fields[4], 0, 7).context("day-of-week")?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59).context("minute")?,
+            hours: parse_field(fields[1], 0, 23).context("hour")?,
+            days_of_month: parse_field(fields[2], 1, 31).context("day-of-month")?,
+            months: parse_field(fields[3], 1, 12).context("month")?,
+            days_of_week,
+            dom_restricted: fields[2] != "*",
+            dow_restricted: fields[4] != "*",
+        })
+    }
+
+    /// The first matching minute strictly after `after`, or `None` if the
+    /// expression can never fire (e.g. "0 0 31 2 *").
+    pub(crate) fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let horizon = after + Duration::days(SEARCH_HORIZON_DAYS);
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        while t <= horizon {
+            if !self.matches_day(t) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, t: NaiveDateTime) -> bool {
+        if !bit(self.months, t.month()) {
+            return false;
+        }
+        let dom = bit(self.days_of_month, t.day());
+        let dow = bit(self.days_of_week, t.weekday().num_days_from_sunday());
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+}
+
+const fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parse one cron field into a bitmask of the values in `min..=max` it selects.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().with_context(|| format!("invalid step '{step}'"))?;
+                ensure!(step > 0, "step must be > 0");
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, min, max)?, parse_value(b, min, max)?)
+        } else {
+            let v = parse_value(range, min, max)?;
+            // "5/15" means every 15 starting at 5, like "5-max/15".
+            (v, if part.contains('/') { max } else { v })
+        };
+        if start > end {
+            bail!("range {start}-{end} is backwards");
+        }
+
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(s: &str, min: u32, max: u32) -> Result<u32> {
+    let v: u32 = s.parse().with_context(|| format!("invalid value '{s}'"))?;
+    ensure!((min..=max).contains(&v), "value {v} out of range {min}-{max}");
+    Ok(v)
+}
@@ -0,0 +1,116 @@
+mod cron;
+
+pub(crate) use cron::CronSchedule;
+
+use crate::api::responses::ApiError;
+use crate::api::{begin_execution, begin_scan, plan_from_config, stale_catalog_warning};
+use crate::events::Event;
+use crate::{AppState, DaemonState};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Start the background scheduler if a `schedule` is configured.
+///
+/// Each time the cron expression fires, the daemon runs scan → plan → execute
+/// through the same entry points as the API, so the run shows up in status and
+/// SSE and can be stopped with `POST /api/scan/cancel` or
+/// `POST /api/plan/{id}/cancel`.
+pub(crate) fn spawn(state: Arc<AppState>) {
+    let Some(expr) = state.config().schedule.clone() else {
+        return;
+    };
+    let schedule = match CronSchedule::parse(&expr) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Scheduler disabled, invalid schedule '{}': {:#}", expr, e);
+            return;
+        }
+    };
+    info!("Scheduled balancing enabled: '{}'", expr);
+
+    tokio::spawn(async move {
+        loop {
+            let now = chrono::Local::now().naive_local();
+            let Some(next) = schedule.next_after(now) else {
+                warn!("Schedule '{}' never fires; scheduler stopping", expr);
+                return;
+            };
+            info!("Next scheduled run at {}", next);
+            tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+
+            let (stage, message) = match run_once(&state).await {
+                Ok(message) => ("finished", message),
+                Err(Skip(message)) => ("skipped", message),
+            };
+            info!("Scheduled run {}: {}", stage, message);
+            let _ =
+                state.event_hub.publish(Event::ScheduledRun { stage: stage.to_string(), message });
+        }
+    });
+}
+
+/// Why a scheduled run stopped before handing a plan to the executor.
+struct Skip(String);
+
+impl From<String> for Skip {
+    fn from(message: String) -> Self {
+        Self(message)
+    }
+}
+
+impl From<ApiError> for Skip {
+    fn from(e: ApiError) -> Self {
+        Self(e.message)
+    }
+}
+
+/// Run one scan → plan → execute cycle. Execution is started in the background
+/// and not awaited.
+async fn run_once(state: &Arc<AppState>) -> Result<String, Skip> {
+    let current = state.status.read().await.state;
+    if current != DaemonState::Idle {
+        return Err(Skip(format!("daemon is currently {current:?}")));
+    }
+    if state.config().warn_parity_check {
+        match crate::executor::is_parity_check_running().await {
+            Ok(false) => {}
+            Ok(true) => return Err(Skip("a parity check is running".to_string())),
+            Err(e) => warn!("Could not check parity status, continuing: {}", e),
+        }
+    }
+
+    let _ = state.event_hub.publish(Event::ScheduledRun {
+        stage: "started".to_string(),
+        message: "Scanning disks".to_string(),
+    });
+    let scan = begin_scan(state, state.config().scan_threads).await?;
+    let complete = scan.finished.await.unwrap_or(false);
+    if scan.token.is_cancelled() {
+        return Err(Skip("scan was cancelled".to_string()));
+    }
+    // Planning against what an unfinished scan left behind would move files by
+    // a stale catalog with nobody watching.
+    if !complete {
+        return Err(Skip("scan did not finish cleanly; see the daemon errors".to_string()));
+    }
+    if let Some(warning) = stale_catalog_warning(state) {
+        if state.config().catalog_age_strict {
+            return Err(Skip(format!("refusing to plan: {warning}")));
+        }
+        warn!("Scheduled run planning anyway: {}", warning);
+    }
+
+    let plan = plan_from_config(state, state.config().slider_alpha, None, None).await?;
+    let _ = state.event_hub.publish(Event::PlanReady {
+        plan_id: plan.plan_id,
+        total_moves: plan.total_moves as u32,
+        total_bytes: plan.total_bytes,
+        projected_imbalance: plan.projected_imbalance,
+    });
+    if plan.total_moves == 0 {
+        return Ok(format!("plan {} has no moves", plan.plan_id));
+    }
+
+    begin_execution(state, plan.plan_id).await?;
+    Ok(format!("executing plan {} ({} moves)", plan.plan_id, plan.total_moves))
+}
@@ -1,51 +1,135 @@
 use serde::Serialize;
+use std::sync::{Arc, PoisonError};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
 use crate::config::AppConfig;
 use crate::db::Database;
 use crate::events::EventHub;
+use crate::executor::RsyncInfo;
+use crate::metrics::Metrics;
 
 /// Shared application state passed to all API handlers via axum's State extractor.
 pub struct AppState {
     pub db: Database,
-    pub config: AppConfig,
+    /// The active config. Handlers take a snapshot with [`AppState::config`];
+    /// saving settings or applying a profile swaps in a new one without a restart.
+    config: std::sync::RwLock<Arc<AppConfig>>,
     pub event_hub: EventHub,
     pub status: tokio::sync::RwLock<DaemonStatus>,
+    /// Counters scraped by `/api/metrics`.
+    pub metrics: Metrics,
     /// Per-operation cancellation token, replaced on each new scan/execution.
     cancel_token: tokio::sync::Mutex<CancellationToken>,
+    /// Why the current operation was cancelled, set by the first `request_cancel`.
+    cancel_reason: tokio::sync::Mutex<Option<String>>,
+    /// Per-move token that abandons only the move being transferred.
+    skip_token: tokio::sync::Mutex<CancellationToken>,
     /// Handle to the currently running background task (scan or execution).
     pub background_task: tokio::sync::Mutex<Option<JoinHandle<()>>>,
     /// Handle to the in-flight rsync child process, for kill-on-shutdown.
     pub rsync_child: tokio::sync::Mutex<Option<tokio::process::Child>>,
+    /// Live progress of the running plan execution; `None` when not executing.
+    pub progress: tokio::sync::RwLock<Option<ExecutionProgress>>,
+    /// Installed rsync, probed once at startup (or on first use).
+    pub rsync: std::sync::OnceLock<RsyncInfo>,
+    /// Why the catalog is running from `DB_FALLBACK_PATH`, set at startup and
+    /// served by `/api/status`; unset when the configured catalog opened.
+    pub catalog_fallback: std::sync::OnceLock<String>,
 }
 
 impl AppState {
     pub fn new(db: Database, config: AppConfig, event_hub: EventHub) -> Self {
         Self {
             db,
-            config,
+            config: std::sync::RwLock::new(Arc::new(config)),
             event_hub,
             status: tokio::sync::RwLock::new(DaemonStatus::idle()),
+            metrics: Metrics::default(),
             cancel_token: tokio::sync::Mutex::new(CancellationToken::new()),
+            cancel_reason: tokio::sync::Mutex::new(None),
+            skip_token: tokio::sync::Mutex::new(CancellationToken::new()),
             background_task: tokio::sync::Mutex::new(None),
             rsync_child: tokio::sync::Mutex::new(None),
+            progress: tokio::sync::RwLock::new(None),
+            rsync: std::sync::OnceLock::new(),
+            catalog_fallback: std::sync::OnceLock::new(),
         }
     }
 
+    /// What rsync is installed, probing on first call if startup didn't.
+    pub async fn rsync_info(&self) -> &RsyncInfo {
+        if let Some(info) = self.rsync.get() {
+            return info;
+        }
+        let info = crate::executor::probe_rsync().await;
+        self.rsync.get_or_init(|| info)
+    }
+
+    /// Snapshot of the active config. An operation should take one snapshot
+    /// up front so a concurrent swap can't change its settings midway.
+    pub fn config(&self) -> Arc<AppConfig> {
+        Arc::clone(&self.config.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Make `config` the active config for every later operation.
+    pub fn replace_config(&self, config: AppConfig) {
+        *self.config.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+    }
+
     /// Create a fresh `CancellationToken` for a new operation.
     /// Returns a clone for the spawned task to monitor.
     pub async fn new_operation_token(&self) -> CancellationToken {
         let token = CancellationToken::new();
         *self.cancel_token.lock().await = token.clone();
+        *self.cancel_reason.lock().await = None;
         token
     }
 
     /// Cancel the current operation (idempotent — safe to call from both
-    /// the cancel API endpoint and the shutdown sequence).
-    pub async fn request_cancel(&self) {
+    /// the cancel API endpoint and the shutdown sequence). The first reason
+    /// given for an operation is the one kept.
+    pub async fn request_cancel(&self, reason: impl Into<String>) {
+        self.cancel_reason.lock().await.get_or_insert_with(|| reason.into());
         self.cancel_token.lock().await.cancel();
     }
+
+    /// Create a fresh skip token for the move about to start.
+    /// Returns a clone for the transfer to monitor.
+    pub async fn new_move_skip_token(&self) -> CancellationToken {
+        let token = CancellationToken::new();
+        *self.skip_token.lock().await = token.clone();
+        token
+    }
+
+    /// Abandon the current move only; the execution carries on with the next one.
+    pub async fn request_skip(&self) {
+        self.skip_token.lock().await.cancel();
+    }
+
+    /// The reason recorded by `request_cancel` for the current operation.
+    pub async fn cancel_reason(&self) -> Option<String> {
+        self.cancel_reason.lock().await.clone()
+    }
+}
+
+/// Snapshot of a running plan execution, served by `GET /api/progress`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExecutionProgress {
+    pub plan_id: i64,
+    /// The move being transferred; `None` between moves.
+    pub current_move_id: Option<i64>,
+    pub current_file: Option<String>,
+    /// Size of the current file in bytes; 0 between moves.
+    pub current_size: u64,
+    /// Percent of the current file transferred (0.0 - 100.0).
+    pub current_percent: f64,
+    /// Moves finished this run, whether completed, failed or skipped.
+    pub moves_done: u32,
+    pub moves_total: u32,
+    /// Bytes of completed moves.
+    pub bytes_done: u64,
+    pub bytes_total: u64,
 }
 
 /// The daemon's operating state, serialized to the API as a lowercase string.
@@ -58,6 +142,21 @@ pub enum DaemonState {
     Executing,
 }
 
+impl DaemonState {
+    /// Every state, in declaration order.
+    pub const ALL: [Self; 4] = [Self::Idle, Self::Scanning, Self::Planning, Self::Executing];
+
+    /// The same snake_case name used in the JSON API.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Scanning => "scanning",
+            Self::Planning => "planning",
+            Self::Executing => "executing",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct DaemonStatus {
     pub state: DaemonState,
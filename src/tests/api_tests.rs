@@ -0,0 +1,842 @@
+use crate::api::auth::bearer_matches;
+
+#[test]
+fn test_bearer_token_matching() {
+    assert!(bearer_matches(Some("Bearer s3cret"), "s3cret"), "exact token should be accepted");
+    assert!(!bearer_matches(None, "s3cret"), "missing header should be rejected");
+    assert!(!bearer_matches(Some("Bearer wrong!"), "s3cret"), "wrong token should be rejected");
+    assert!(!bearer_matches(Some("Bearer s3cre"), "s3cret"), "token prefix should be rejected");
+    assert!(!bearer_matches(Some("Basic s3cret"), "s3cret"), "other schemes should be rejected");
+}
+
+#[tokio::test]
+async fn test_ws_upgrade_requires_token() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let config = AppConfig { api_token: Some("s3cret".to_string()), ..AppConfig::default() };
+    let state = Arc::new(AppState::new(db, config, EventHub::new(16)));
+    let upgrade = |uri: &'static str, auth: Option<&'static str>| {
+        let mut request = Request::get(uri)
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==");
+        if let Some(auth) = auth {
+            request = request.header(header::AUTHORIZATION, auth);
+        }
+        crate::api::router(Arc::clone(&state)).oneshot(request.body(Body::empty()).unwrap())
+    };
+
+    let anonymous = upgrade("/api/ws", None).await.unwrap();
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED, "no token, no control socket");
+    let wrong = upgrade("/api/ws?token=guess", None).await.unwrap();
+    assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED, "a wrong query token is refused");
+
+    // Past the token check the upgrade itself fails here, as oneshot has no
+    // connection to hand over; anything but 401 means auth let it through.
+    let query = upgrade("/api/ws?token=s3cret", None).await.unwrap();
+    assert_ne!(query.status(), StatusCode::UNAUTHORIZED, "browsers pass the token as a query");
+    let bearer = upgrade("/api/ws", Some("Bearer s3cret")).await.unwrap();
+    assert_ne!(bearer.status(), StatusCode::UNAUTHORIZED, "a bearer header works too");
+}
+
+#[tokio::test]
+async fn test_plan_proceeds_when_smart_is_unavailable() {
+    use crate::config::AppConfig;
+    use crate::db::{Database, FileInsert};
+    use crate::events::EventHub;
+    use crate::AppState;
+    use std::sync::Arc;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    let files: Vec<FileInsert> = (1..=4)
+        .map(|i| FileInsert {
+            disk_id: d1,
+            file_path: format!("Movies/f{i}.mkv"),
+            size_bytes: 150,
+            mtime: Some(0),
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+    // No disks.ini here, so no disk can be checked at all.
+    let config = AppConfig {
+        check_smart: true,
+        move_size_thresholds: crate::balancer::SizeThresholds::default(),
+        min_free_headroom: 0,
+        absolute_min_free: 0,
+        ..AppConfig::default()
+    };
+    let state = Arc::new(AppState::new(db, config, EventHub::new(16)));
+
+    let result = crate::api::plan_from_config(&state, 0.5, None, None).await;
+    let plan = result.map_err(|e| e.message).unwrap();
+    assert!(plan.total_moves > 0, "planning carries on with every disk usable");
+}
+
+#[tokio::test]
+async fn test_responses_gzip_except_sse() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    let gzip_get = |uri: &str| {
+        Request::get(uri).header(header::ACCEPT_ENCODING, "gzip").body(Body::empty()).unwrap()
+    };
+
+    let summary =
+        crate::api::router(Arc::clone(&state)).oneshot(gzip_get("/api/summary")).await.unwrap();
+    assert_eq!(
+        summary.headers().get(header::CONTENT_ENCODING).map(|v| v.to_str().unwrap()),
+        Some("gzip"),
+        "JSON responses should be gzip-compressed on request"
+    );
+
+    let events = crate::api::router(state).oneshot(gzip_get("/api/events")).await.unwrap();
+    assert!(
+        events.headers().get(header::CONTENT_ENCODING).is_none(),
+        "the SSE stream must not be compressed"
+    );
+}
+
+#[tokio::test]
+async fn test_health_and_ready_probes() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    // Hold the status lock to show the liveness probe never waits on it.
+    let _status = state.status.write().await;
+
+    for uri in ["/api/health", "/api/ready"] {
+        let response = crate::api::router(Arc::clone(&state))
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{uri} should report healthy");
+    }
+}
+
+#[tokio::test]
+async fn test_error_responses_carry_codes() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::{AppState, DaemonStatus};
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+
+    let code_for = |uri: &'static str| {
+        let router = crate::api::router(Arc::clone(&state));
+        async move {
+            let response =
+                router.oneshot(Request::post(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            json["code"].as_str().map(String::from)
+        }
+    };
+
+    assert_eq!(
+        code_for("/api/plan/42/execute").await.as_deref(),
+        Some("PLAN_NOT_FOUND"),
+        "a missing plan is reported by code"
+    );
+    *state.status.write().await = DaemonStatus::scanning("test");
+    assert_eq!(
+        code_for("/api/maintenance/vacuum").await.as_deref(),
+        Some("DAEMON_BUSY"),
+        "a busy daemon is reported by code"
+    );
+}
+
+#[tokio::test]
+async fn test_progress_endpoint_reports_live_execution() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::{AppState, ExecutionProgress};
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    let fetch = || async {
+        let response = crate::api::router(Arc::clone(&state))
+            .oneshot(Request::get("/api/progress").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    assert!(fetch().await["data"].is_null(), "no progress while idle");
+
+    *state.progress.write().await = Some(ExecutionProgress {
+        plan_id: 7,
+        current_move_id: Some(3),
+        current_percent: 42.0,
+        moves_done: 2,
+        moves_total: 5,
+        ..ExecutionProgress::default()
+    });
+    let json = fetch().await;
+    assert_eq!(json["data"]["current_move_id"], 3, "current move is reported: {json}");
+    assert_eq!(json["data"]["moves_done"], 2, "aggregate counts are reported: {json}");
+}
+
+#[tokio::test]
+async fn test_settings_and_profiles_apply_without_restart() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let dir = std::env::temp_dir().join(format!("pb-live-settings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config_path = dir.join("perfectly-balanced.cfg");
+    std::fs::write(&config_path, "SCAN_THREADS=\"2\"\n\n[profile overnight]\nSCAN_THREADS=\"6\"\n")
+        .unwrap();
+    let config = AppConfig {
+        config_path: config_path.to_string_lossy().into_owned(),
+        ..AppConfig::default()
+    };
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, config, EventHub::new(16)));
+    let post = |uri: &'static str, body: &'static str| {
+        let router = crate::api::router(Arc::clone(&state));
+        async move {
+            let request = Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        }
+    };
+
+    let updated = post("/api/settings", r#"{"max_tolerance": 0.05}"#).await;
+    assert_eq!(updated["success"], true, "settings update should succeed: {updated}");
+    assert!(
+        (state.config().max_tolerance - 0.05).abs() < f64::EPSILON,
+        "new tolerance should be live for the next plan"
+    );
+
+    let applied = post("/api/settings/profile/overnight", "").await;
+    assert_eq!(applied["success"], true, "applying a known profile should succeed: {applied}");
+    assert_eq!(state.config().scan_threads, 6, "profile override should be live");
+    assert!(
+        (state.config().max_tolerance - 0.05).abs() < f64::EPSILON,
+        "profile applies on top of the saved settings"
+    );
+
+    let missing = post("/api/settings/profile/nope", "").await;
+    assert_eq!(missing["code"], "PROFILE_NOT_FOUND", "unknown profiles are reported by code");
+
+    let saved = std::fs::read_to_string(&config_path).unwrap();
+    assert!(saved.contains("SCAN_THREADS=\"2\""), "profile values are not saved as the base");
+    assert!(saved.contains("[profile overnight]"), "profiles survive a settings save");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_profile_list_redacts_secrets() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let mut config = AppConfig::default();
+    config.profiles.insert(
+        "remote".into(),
+        [
+            ("API_TOKEN", "s3cret"),
+            ("NOTIFY_WEBHOOK_URL", "https://hooks.example/abc"),
+            ("SCAN_THREADS", "6"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect(),
+    );
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, config, EventHub::new(16)));
+
+    let request = Request::get("/api/settings/profiles").body(Body::empty()).unwrap();
+    let response = crate::api::router(state).oneshot(request).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let remote = &json["data"]["profiles"]["remote"];
+    assert_eq!(remote["SCAN_THREADS"], "6", "ordinary overrides are listed: {json}");
+    assert_eq!(remote["API_TOKEN"], "<redacted>", "the token is never shown: {json}");
+    assert_eq!(remote["NOTIFY_WEBHOOK_URL"], "<redacted>", "nor the webhook URL: {json}");
+    assert!(!String::from_utf8_lossy(&bytes).contains("s3cret"));
+}
+
+#[tokio::test]
+async fn test_catalog_export_streams_quoted_csv() {
+    use crate::config::AppConfig;
+    use crate::db::{Database, FileInsert};
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let disk_id = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let file = |path: &str, mtime: Option<i64>| FileInsert {
+        disk_id,
+        file_path: path.to_string(),
+        size_bytes: 10,
+        mtime,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+    db.atomic_disk_scan(
+        disk_id,
+        &[file("Movies/plain.mkv", Some(1700000000)), file("Music/a, \"b\".flac", None)],
+    )
+    .unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    let fetch = |uri: String| {
+        let router = crate::api::router(Arc::clone(&state));
+        async move {
+            let response =
+                router.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        }
+    };
+
+    let csv = fetch(format!("/api/disks/{disk_id}/export?format=csv")).await;
+    assert_eq!(
+        csv,
+        "path,size_bytes,mtime\nMovies/plain.mkv,10,1700000000\n\"Music/a, \"\"b\"\".flac\",10,\n",
+        "CSV should have a header and quote paths with commas and quotes"
+    );
+
+    let json = fetch(format!("/api/disks/{disk_id}/export?format=json")).await;
+    let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(rows.as_array().map(Vec::len), Some(2), "JSON export should list every file");
+    assert_eq!(rows[1]["path"], "Music/a, \"b\".flac", "JSON paths are unescaped");
+}
+
+#[tokio::test]
+async fn test_execute_rejects_when_rsync_missing() {
+    use crate::config::AppConfig;
+    use crate::db::{Database, PlanStatus};
+    use crate::events::EventHub;
+    use crate::executor::RsyncInfo;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    state.rsync.set(RsyncInfo::default()).unwrap();
+
+    let response = crate::api::router(Arc::clone(&state))
+        .oneshot(Request::post(format!("/api/plan/{plan_id}/execute")).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "RSYNC_MISSING", "missing rsync is reported up front: {json}");
+    assert_eq!(
+        state.db.get_plan(plan_id).unwrap().map(|p| p.status),
+        Some(PlanStatus::Planned),
+        "the plan is left untouched"
+    );
+
+    let status_response = crate::api::router(Arc::clone(&state))
+        .oneshot(Request::get("/api/status").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = axum::body::to_bytes(status_response.into_body(), usize::MAX).await.unwrap();
+    let status: serde_json::Value = serde_json::from_slice(&status).unwrap();
+    assert!(status["data"]["rsync_version"].is_null(), "status shows rsync missing: {status}");
+}
+
+#[tokio::test]
+async fn test_undo_applies_forward_move_checks() {
+    use crate::config::AppConfig;
+    use crate::db::{Database, ExecutedMove};
+    use crate::events::{Event, EventHub};
+    use crate::executor::{mtime_secs, RsyncInfo};
+    use crate::{AppState, DaemonState};
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    // Both "disks" are directories on one filesystem, so they share a device.
+    let root = std::env::temp_dir().join(format!("pb-undo-checks-{}", std::process::id()));
+    let (src_mount, tgt_mount) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(&src_mount).unwrap();
+    std::fs::create_dir_all(&tgt_mount).unwrap();
+    std::fs::write(tgt_mount.join("film.mkv"), b"0123456789").unwrap();
+    let meta = std::fs::metadata(tgt_mount.join("film.mkv")).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    db.record_executed_move(&ExecutedMove {
+        move_id: 42,
+        source_mount: src_mount.to_string_lossy().into_owned(),
+        target_mount: tgt_mount.to_string_lossy().into_owned(),
+        file_path: "film.mkv".into(),
+        target_path: "film.mkv".into(),
+        file_size: 10,
+        target_mtime: mtime_secs(&meta),
+        executed_at: None,
+        undone_at: None,
+    })
+    .unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    state.rsync.set(RsyncInfo { version: Some("3.2.7".into()), supports_progress2: true }).unwrap();
+    let mut events = state.event_hub.subscribe();
+
+    let response = crate::api::router(Arc::clone(&state))
+        .oneshot(Request::post("/api/moves/42/undo").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["success"], true, "the undo starts: {json}");
+
+    let error = loop {
+        if let Event::MoveComplete { move_id: 42, status, error, .. } =
+            events.recv().await.unwrap().event
+        {
+            assert_eq!(status, "failed");
+            break error.unwrap();
+        }
+    };
+    assert!(error.contains("same device"), "refused like a forward move would be: {error}");
+    for _ in 0..100 {
+        if state.status.read().await.state == DaemonState::Idle {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(state.status.read().await.state, DaemonState::Idle, "the daemon is idle again");
+    assert!(tgt_mount.join("film.mkv").exists(), "the file stays where it was");
+    assert!(state.db.get_executed_move(42).unwrap().unwrap().undone_at.is_none());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_status_reports_fallback_catalog() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let dir = std::env::temp_dir().join(format!("pb-fallback-{}", std::process::id()));
+    let fallback = dir.join("catalog.db").to_string_lossy().into_owned();
+    let config = AppConfig {
+        db_path: "/dev/null/catalog.db".into(),
+        db_open_timeout_secs: 0,
+        db_fallback_path: Some(fallback.clone()),
+        ..AppConfig::default()
+    };
+    let (path, reason) = crate::catalog_path(&config).unwrap();
+    assert_eq!(path, fallback, "the fallback catalog is used");
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, config, EventHub::new(16)));
+    let status = || {
+        let router = crate::api::router(Arc::clone(&state));
+        async move {
+            let request = Request::get("/api/status").body(Body::empty()).unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        }
+    };
+    let normal = status().await;
+    assert!(normal["data"]["catalog_fallback"].is_null(), "nothing to report normally: {normal}");
+
+    state.catalog_fallback.set(reason.unwrap()).unwrap();
+    let fell_back = status().await;
+    let shown = fell_back["data"]["catalog_fallback"].as_str().unwrap_or_default();
+    assert!(
+        shown.contains("/dev/null/catalog.db"),
+        "a UI loaded later still sees why: {fell_back}"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_skip_current_targets_only_the_running_move() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::{AppState, ExecutionProgress};
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    let skip = |uri: &'static str| {
+        let router = crate::api::router(Arc::clone(&state));
+        async move {
+            let response =
+                router.oneshot(Request::post(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        }
+    };
+
+    let idle = skip("/api/plan/7/skip-current").await;
+    assert_eq!(idle["code"], "NOTHING_RUNNING", "nothing to skip while idle: {idle}");
+
+    let token = state.new_move_skip_token().await;
+    *state.progress.write().await = Some(ExecutionProgress {
+        plan_id: 7,
+        current_move_id: Some(3),
+        ..ExecutionProgress::default()
+    });
+    let other_plan = skip("/api/plan/8/skip-current").await;
+    assert_eq!(other_plan["success"], false, "another plan's move is not skipped: {other_plan}");
+    assert!(!token.is_cancelled(), "a rejected skip must not touch the running move");
+
+    let skipped = skip("/api/plan/7/skip-current").await;
+    assert_eq!(skipped["success"], true, "the running move can be skipped: {skipped}");
+    assert!(token.is_cancelled(), "the current move's skip token fires");
+    assert!(state.cancel_reason().await.is_none(), "skip must not cancel the execution");
+}
+
+#[tokio::test]
+async fn test_validate_plan_reports_without_touching_moves() {
+    use crate::config::AppConfig;
+    use crate::db::{Database, MoveStatus, PlannedMove};
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/pb-missing-1", 1000, 500, 500, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/pb-missing-2", 1000, 100, 900, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let moves: Vec<PlannedMove> = (1..=2)
+        .map(|i| PlannedMove {
+            id: 0,
+            plan_id,
+            source_disk_id: d1,
+            target_disk_id: d2,
+            file_path: format!("gone/f{i}.bin"),
+            file_size: 10,
+            move_order: i,
+            phase: 1,
+            status: MoveStatus::Pending,
+            error_message: None,
+            source_mtime: None,
+            target_path: None,
+        })
+        .collect();
+    db.insert_planned_moves(&moves).unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+
+    let validate = |id: i64| {
+        crate::api::router(Arc::clone(&state))
+            .oneshot(Request::post(format!("/api/plan/{id}/validate")).body(Body::empty()).unwrap())
+    };
+    let response = validate(plan_id).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["pending_moves"], 2, "both pending moves are checked: {json}");
+    assert_eq!(json["data"]["valid_moves"], 0, "missing sources are not valid: {json}");
+    assert_eq!(json["data"]["invalid"][0]["outcome"], "skipped", "execution would skip: {json}");
+    assert!(
+        state
+            .db
+            .get_plan_moves(plan_id)
+            .unwrap()
+            .iter()
+            .all(|m| m.move_info.status == MoveStatus::Pending
+                && m.move_info.error_message.is_none()),
+        "validation leaves move statuses alone"
+    );
+
+    let missing = validate(plan_id + 1).await.unwrap();
+    let missing = axum::body::to_bytes(missing.into_body(), usize::MAX).await.unwrap();
+    let missing: serde_json::Value = serde_json::from_slice(&missing).unwrap();
+    assert_eq!(missing["code"], "PLAN_NOT_FOUND", "unknown plans are reported: {missing}");
+}
+
+#[tokio::test]
+async fn test_compare_plans_splits_move_sets() {
+    use crate::config::AppConfig;
+    use crate::db::{Database, MoveStatus, PlannedMove};
+    use crate::events::EventHub;
+    use crate::AppState;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 800, 200, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    let d3 = db.upsert_disk("disk3", "/mnt/disk3", 1000, 100, 900, None).unwrap();
+    let mut plan_ids = Vec::new();
+    for (alpha, files) in [(0.5, [("a", d2), ("b", d2)]), (0.8, [("b", d3), ("c", d2)])] {
+        let plan_id = db.create_plan(0.1, alpha, 0.5, 0.2).unwrap();
+        let moves: Vec<PlannedMove> = files
+            .iter()
+            .zip(1..)
+            .map(|(&(path, target), order)| PlannedMove {
+                id: 0,
+                plan_id,
+                source_disk_id: d1,
+                target_disk_id: target,
+                file_path: path.to_string(),
+                file_size: 10,
+                move_order: order,
+                phase: 1,
+                status: MoveStatus::Pending,
+                error_message: None,
+                source_mtime: None,
+                target_path: None,
+            })
+            .collect();
+        db.insert_planned_moves(&moves).unwrap();
+        db.refresh_plan_totals(plan_id).unwrap();
+        plan_ids.push(plan_id);
+    }
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+
+    let uri = format!("/api/plans/compare?a={}&b={}", plan_ids[0], plan_ids[1]);
+    let response = crate::api::router(Arc::clone(&state))
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let data = &json["data"];
+    assert_eq!(data["a"]["total_moves"], 2, "plan A totals are reported: {json}");
+    assert_eq!(data["only_in_a"][0]["file_path"], "a", "a is only moved by A: {json}");
+    assert_eq!(data["only_in_b"][0]["file_path"], "c", "c is only moved by B: {json}");
+    assert_eq!(data["in_both"][0]["file_path"], "b", "b is moved by both: {json}");
+    assert_eq!(
+        (&data["in_both"][0]["target_in_a"], &data["in_both"][0]["target_in_b"]),
+        (&serde_json::json!("disk2"), &serde_json::json!("disk3")),
+        "each plan's target is shown"
+    );
+
+    let missing_uri = format!("/api/plans/compare?a={}&b=999", plan_ids[0]);
+    let missing = crate::api::router(state)
+        .oneshot(Request::get(missing_uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let missing = axum::body::to_bytes(missing.into_body(), usize::MAX).await.unwrap();
+    let missing: serde_json::Value = serde_json::from_slice(&missing).unwrap();
+    assert_eq!(missing["code"], "PLAN_NOT_FOUND", "an unknown plan is reported: {missing}");
+}
+
+#[tokio::test]
+async fn test_scan_cancel_only_cancels_scans() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::{AppState, DaemonState};
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    let cancel = || {
+        let router = crate::api::router(Arc::clone(&state));
+        async move {
+            let request = Request::post("/api/scan/cancel").body(Body::empty()).unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        }
+    };
+
+    let idle = cancel().await;
+    assert_eq!(idle["code"], "NOTHING_RUNNING", "nothing to cancel while idle: {idle}");
+
+    let token = state.new_operation_token().await;
+    state.status.write().await.state = DaemonState::Executing;
+    let executing = cancel().await;
+    assert_eq!(executing["code"], "WRONG_STATUS", "an execution is left alone: {executing}");
+    assert!(!token.is_cancelled(), "the execution keeps running");
+
+    state.status.write().await.state = DaemonState::Scanning;
+    let scanning = cancel().await;
+    assert_eq!(scanning["success"], true, "a running scan is cancelled: {scanning}");
+    assert!(token.is_cancelled(), "the scan's token fires");
+}
+
+#[tokio::test]
+async fn test_scan_reports_whether_it_finished() {
+    use crate::config::AppConfig;
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::AppState;
+    use std::sync::Arc;
+
+    let base = std::env::temp_dir().join(format!("pb-scan-result-{}", std::process::id()));
+    std::fs::create_dir_all(&base).unwrap();
+    let scan = |mnt_base: String| async move {
+        let db = Database::open_in_memory().unwrap();
+        db.run_migrations().unwrap();
+        let config = AppConfig { mnt_base, ..AppConfig::default() };
+        let state = Arc::new(AppState::new(db, config, EventHub::new(16)));
+        let task = crate::api::begin_scan(&state, 1).await.unwrap();
+        task.finished.await.unwrap()
+    };
+
+    assert!(scan(base.to_string_lossy().into_owned()).await, "an empty array scans cleanly");
+    let missing = base.join("missing").to_string_lossy().into_owned();
+    assert!(!scan(missing).await, "failed discovery is reported, not mistaken for a fresh catalog");
+
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[tokio::test]
+async fn test_recover_endpoint_settles_stale_moves() {
+    use crate::config::AppConfig;
+    use crate::db::{Database, MoveStatus, PlanStatus, PlannedMove};
+    use crate::events::EventHub;
+    use crate::{AppState, DaemonState};
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let root = std::env::temp_dir().join(format!("pb-recover-api-{}", std::process::id()));
+    let (src_mount, tgt_mount) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(&src_mount).unwrap();
+    std::fs::create_dir_all(&tgt_mount).unwrap();
+    std::fs::write(tgt_mount.join("f.mkv"), b"0123456789").unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", &src_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", &tgt_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    db.insert_planned_moves(&[PlannedMove {
+        id: 0,
+        plan_id,
+        source_disk_id: d1,
+        target_disk_id: d2,
+        file_path: "f.mkv".into(),
+        file_size: 10,
+        move_order: 1,
+        phase: 1,
+        status: MoveStatus::Pending,
+        error_message: None,
+        source_mtime: None,
+        target_path: None,
+    }])
+    .unwrap();
+    let move_id = db.get_plan_moves(plan_id).unwrap()[0].move_info.id;
+    db.update_plan_status(plan_id, PlanStatus::Executing).unwrap();
+    db.update_move_status(move_id, MoveStatus::InProgress, None).unwrap();
+    let state = Arc::new(AppState::new(db, AppConfig::default(), EventHub::new(16)));
+    let recover = || {
+        let router = crate::api::router(Arc::clone(&state));
+        async move {
+            let request = Request::post("/api/maintenance/recover").body(Body::empty()).unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        }
+    };
+
+    state.status.write().await.state = DaemonState::Scanning;
+    let busy = recover().await;
+    assert_eq!(busy["code"], "DAEMON_BUSY", "recovery waits for an idle daemon: {busy}");
+
+    state.status.write().await.state = DaemonState::Idle;
+    let done = recover().await;
+    assert_eq!(
+        done["data"],
+        serde_json::json!({"completed": 1, "cleaned": 0, "data_loss": 0, "needs_review": 0}),
+        "the copied move is settled as completed: {done}"
+    );
+    let plan = state.db.get_plan(plan_id).unwrap().unwrap();
+    assert_eq!(plan.status, PlanStatus::Failed, "the stuck plan no longer claims to execute");
+    let status = state.db.get_plan_moves(plan_id).unwrap()[0].move_info.status;
+    assert_eq!(status, MoveStatus::Completed);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
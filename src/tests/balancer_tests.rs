@@ -1,3 +1,11 @@
+use crate::balancer::types::BalanceResult;
+use crate::balancer::{
+    generate_drain_plan, generate_plan, generate_rollback_plan, BalanceMetric, CandidateOrder,
+    NoMovesReason, PlanOptions, SizeThresholds, TargetStrategy, TooFewDisks,
+};
+use crate::db::{Database, FileInsert, MoveStatus, PlanStatus, PlannedMove};
+use std::collections::HashSet;
+
 #[test]
 fn test_disk_classification() {
     let target = 0.50;
@@ -6,9 +14,859 @@ fn test_disk_classification() {
     // 80% utilized -> over
     assert!(0.80 > target + tolerance, "80% should be over the target+tolerance band");
     // 55% utilized -> above average
-    assert!(0.55 > target && 0.55 <= target + tolerance, "55% should be above average but within tolerance");
+    assert!(
+        0.55 > target && 0.55 <= target + tolerance,
+        "55% should be above average but within tolerance"
+    );
     // 45% utilized -> below average
-    assert!(0.45 < target && 0.45 >= target - tolerance, "45% should be below average but within tolerance");
+    assert!(
+        0.45 < target && 0.45 >= target - tolerance,
+        "45% should be below average but within tolerance"
+    );
     // 30% utilized -> under
     assert!(0.30 < target - tolerance, "30% should be under the target-tolerance band");
 }
+
+/// Build an in-memory catalog: disk1 is 90% full, disk2 and disk3 are 10% full.
+/// disk1 holds two season folders of four 100-byte episodes each.
+fn seeded_db() -> Database {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    db.upsert_disk("disk3", "/mnt/disk3", 1000, 100, 900, None).unwrap();
+
+    let files: Vec<FileInsert> = (1..=2)
+        .flat_map(|season| {
+            (1..=4).map(move |ep| FileInsert {
+                disk_id: d1,
+                file_path: format!("Show/Season {season}/e{ep}.mkv"),
+                size_bytes: 100,
+                mtime: Some(0),
+                inode: None,
+                nlink: 1,
+                followed_link: false,
+            })
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+    db
+}
+
+fn test_opts() -> PlanOptions {
+    PlanOptions {
+        slider_alpha: 0.5,
+        max_tolerance: 0.15,
+        min_free_headroom: 0,
+        absolute_min_free: 0,
+        excluded_disk_ids: Vec::new(),
+        keep_folders_together: false,
+        include_roots: Vec::new(),
+        pinned_paths: Vec::new(),
+        never_target_disks: Vec::new(),
+        refuse_fs_mismatch: false,
+        spun_down_disks: HashSet::new(),
+        balance_metric: BalanceMetric::Utilization,
+        target_strategy: TargetStrategy::MostHeadroom,
+        candidate_order: CandidateOrder::LargestFirst,
+        target_utilization_override: None,
+        size_thresholds: SizeThresholds::default(),
+        max_moves: None,
+        min_file_age_days: None,
+        move_undated_files: false,
+    }
+}
+
+#[test]
+fn test_keep_folders_together_targets_one_disk_per_folder() {
+    let db = seeded_db();
+    let opts = PlanOptions { keep_folders_together: true, ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    let moves = db.get_plan_moves(result.plan_id).unwrap();
+    assert!(!moves.is_empty(), "an unbalanced array should produce moves");
+
+    for season in ["Show/Season 1/", "Show/Season 2/"] {
+        let targets: HashSet<i64> = moves
+            .iter()
+            .filter(|m| m.move_info.file_path.starts_with(season))
+            .map(|m| m.move_info.target_disk_id)
+            .collect();
+        assert!(targets.len() <= 1, "{season} was split across disks: {targets:?}");
+    }
+}
+
+#[test]
+fn test_oversized_folder_is_split_across_fewest_disks() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 2000, 1800, 200, None).unwrap();
+    for name in ["disk2", "disk3", "disk4"] {
+        db.upsert_disk(name, &format!("/mnt/{name}"), 1000, 100, 900, None).unwrap();
+    }
+    let files: Vec<FileInsert> = (1..=6)
+        .map(|i| ("Big", i))
+        .chain((1..=3).map(|i| ("Other", i)))
+        .map(|(folder, i)| FileInsert {
+            disk_id: d1,
+            file_path: format!("{folder}/f{i}.mkv"),
+            size_bytes: 200,
+            mtime: Some(0),
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+
+    let opts = PlanOptions { keep_folders_together: true, ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    let moves = db.get_plan_moves(result.plan_id).unwrap();
+    let big: Vec<_> = moves.iter().filter(|m| m.move_info.file_path.starts_with("Big/")).collect();
+    assert_eq!(big.len(), 6, "the 1200-byte folder moves even though no disk has 1200 free");
+    let targets: HashSet<i64> = big.iter().map(|m| m.move_info.target_disk_id).collect();
+    assert_eq!(targets.len(), 2, "two 900-byte disks are enough: {targets:?}");
+    assert_eq!(result.split_folders.len(), 1, "the split is reported: {:?}", result.split_folders);
+    assert!(result.split_folders[0].starts_with("Big on disk1"), "{:?}", result.split_folders);
+}
+
+#[test]
+fn test_no_moves_reason_already_balanced() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    db.upsert_disk("disk1", "/mnt/disk1", 1000, 500, 500, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 500, 500, None).unwrap();
+    let result = generate_plan(&db, &test_opts()).unwrap();
+    assert_eq!(result.total_moves, 0, "balanced array should need no moves");
+    assert_eq!(result.no_moves_reason, Some(NoMovesReason::AlreadyBalanced));
+}
+
+#[test]
+fn test_no_moves_reason_all_pinned() {
+    let db = seeded_db();
+    let opts = PlanOptions { pinned_paths: vec!["Show".to_string()], ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    assert_eq!(result.total_moves, 0, "every file is pinned");
+    assert_eq!(
+        result.no_moves_reason,
+        Some(NoMovesReason::AllFiltered),
+        "pinned files are not the same as an empty disk"
+    );
+
+    let empty = Database::open_in_memory().unwrap();
+    empty.run_migrations().unwrap();
+    empty.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    empty.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    let uncataloged = generate_plan(&empty, &test_opts()).unwrap();
+    assert_eq!(uncataloged.no_moves_reason, Some(NoMovesReason::NoCandidates), "nothing cataloged");
+}
+
+#[test]
+fn test_too_few_disks_names_the_included_ones() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    db.set_disk_included(d2, false).unwrap();
+
+    let err = generate_plan(&db, &test_opts()).unwrap_err();
+    let too_few = err.downcast_ref::<TooFewDisks>().unwrap();
+    assert_eq!(too_few.included, vec!["disk1".to_string()], "only disk1 is usable");
+    assert!(
+        err.to_string().ends_with("only disk1 is included; include at least 1 more"),
+        "message should guide the user: {err}"
+    );
+}
+
+#[test]
+fn test_spun_down_target_loses_close_tie() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    // disk2 has slightly more room than disk3 but is parked.
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 90, 910, None).unwrap();
+    let d3 = db.upsert_disk("disk3", "/mnt/disk3", 1000, 100, 900, None).unwrap();
+    let file = FileInsert {
+        disk_id: d1,
+        file_path: "big.mkv".into(),
+        size_bytes: 200,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+    db.atomic_disk_scan(d1, &[file]).unwrap();
+
+    let opts = PlanOptions { spun_down_disks: HashSet::from(["disk2".to_string()]), ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    let moves = db.get_plan_moves(result.plan_id).unwrap();
+    assert_eq!(moves.len(), 1, "the single large file should be moved");
+    assert_eq!(moves[0].move_info.target_disk_id, d3, "spinning disk3 should win the near-tie");
+}
+
+#[test]
+fn test_rollback_reverses_only_completed_moves_still_in_place() {
+    let root = std::env::temp_dir().join(format!("pb-rollback-{}", std::process::id()));
+    let (mnt1, mnt2) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(mnt2.join("Show")).unwrap();
+    std::fs::create_dir_all(&mnt1).unwrap();
+    std::fs::write(mnt2.join("Show/e1.mkv"), [0u8; 100]).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", mnt1.to_str().unwrap(), 1000, 500, 500, None).unwrap();
+    let d2 = db.upsert_disk("disk2", mnt2.to_str().unwrap(), 1000, 500, 500, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.4).unwrap();
+    let planned = |order: i32, path: &str| PlannedMove {
+        id: 0,
+        plan_id,
+        source_disk_id: d1,
+        target_disk_id: d2,
+        file_path: path.to_string(),
+        file_size: 100,
+        move_order: order,
+        phase: 1,
+        status: MoveStatus::Pending,
+        error_message: None,
+        source_mtime: None,
+        target_path: None,
+    };
+    // e1 completed and is still on disk2; e2 completed but has since been deleted;
+    // e3 failed and must never be reversed.
+    db.insert_planned_moves(&[
+        planned(1, "Show/e1.mkv"),
+        planned(2, "Show/e2.mkv"),
+        planned(3, "Show/e3.mkv"),
+    ])
+    .unwrap();
+    for m in db.get_plan_moves(plan_id).unwrap() {
+        let status =
+            if m.move_info.move_order == 3 { MoveStatus::Failed } else { MoveStatus::Completed };
+        db.update_move_status(m.move_info.id, status, None).unwrap();
+    }
+    db.update_plan_status(plan_id, PlanStatus::Completed).unwrap();
+
+    let result = generate_rollback_plan(&db, plan_id).unwrap();
+    let moves = db.get_plan_moves(result.plan_id).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(moves.len(), 1, "only the completed move still in place should be reversed");
+    assert_eq!(moves[0].move_info.file_path, "Show/e1.mkv");
+    assert_eq!(moves[0].move_info.source_disk_id, d2, "rollback should move off the old target");
+    assert_eq!(moves[0].move_info.target_disk_id, d1, "rollback should return to the old source");
+    assert!(moves[0].move_info.source_mtime.is_some(), "rollback should record the current mtime");
+}
+
+#[test]
+fn test_pinned_paths_are_never_candidates() {
+    let db = seeded_db();
+    // The absolute entry pins a nested folder; "Show/Season" is only a string
+    // prefix of the season folders, so it must not pin anything.
+    let opts = PlanOptions {
+        pinned_paths: vec!["/mnt/disk1/Show/Season 1".to_string(), "Show/Season".to_string()],
+        ..test_opts()
+    };
+    let result = generate_plan(&db, &opts).unwrap();
+    let moves = db.get_plan_moves(result.plan_id).unwrap();
+    assert!(
+        moves.iter().all(|m| !m.move_info.file_path.starts_with("Show/Season 1/")),
+        "files under a pinned folder must stay put"
+    );
+    assert!(
+        moves.iter().any(|m| m.move_info.file_path.starts_with("Show/Season 2/")),
+        "files outside the pinned folder should still be balanced"
+    );
+}
+
+/// disk1 (1 KB, 90%) must shed files; disk2 is large and at 30%, disk3 small and
+/// at 10%. disk2 has the most bytes of headroom, disk3 the lowest utilization.
+fn strategy_targets(strategy: TargetStrategy) -> Vec<i64> {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 20000, 6000, 14000, None).unwrap();
+    db.upsert_disk("disk3", "/mnt/disk3", 1000, 100, 900, None).unwrap();
+    let files: Vec<FileInsert> = (1..=8)
+        .map(|n| FileInsert {
+            disk_id: d1,
+            file_path: format!("data/f{n}.bin"),
+            size_bytes: 100,
+            mtime: Some(0),
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+
+    let opts = PlanOptions { target_strategy: strategy, ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    db.get_plan_moves(result.plan_id).unwrap().iter().map(|m| m.move_info.target_disk_id).collect()
+}
+
+#[test]
+fn test_target_strategies_place_moves_differently() {
+    let (disk2, disk3) = (2, 3);
+
+    let headroom = strategy_targets(TargetStrategy::MostHeadroom);
+    assert_eq!(
+        headroom.first(),
+        Some(&disk2),
+        "most headroom starts with the big disk: {headroom:?}"
+    );
+
+    let emptiest = strategy_targets(TargetStrategy::Emptiest);
+    assert_eq!(
+        emptiest.get(..3),
+        Some(&[disk3, disk3, disk3][..]),
+        "emptiest fills disk3 to target first: {emptiest:?}"
+    );
+    assert!(
+        emptiest.iter().skip_while(|&&d| d == disk3).all(|&d| d == disk2),
+        "emptiest never returns to a filled disk: {emptiest:?}"
+    );
+
+    let round_robin = strategy_targets(TargetStrategy::RoundRobin);
+    assert_eq!(
+        round_robin.get(..2),
+        Some(&[disk2, disk3][..]),
+        "round robin alternates targets: {round_robin:?}"
+    );
+}
+
+/// disk1 must shed one 100-byte file; f1 has no mtime, f2 is old, f3 is new.
+fn first_moved_by(order: CandidateOrder) -> String {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 600, 400, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 400, 600, None).unwrap();
+    let files: Vec<FileInsert> = [("f1", None), ("f2", Some(1_000)), ("f3", Some(2_000))]
+        .into_iter()
+        .map(|(name, mtime)| FileInsert {
+            disk_id: d1,
+            file_path: format!("data/{name}.bin"),
+            size_bytes: 100,
+            mtime,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+
+    let opts = PlanOptions { slider_alpha: 1.0, candidate_order: order, ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    let moves = db.get_plan_moves(result.plan_id).unwrap();
+    moves.first().unwrap().move_info.file_path.clone()
+}
+
+#[test]
+fn test_candidate_order_by_mtime() {
+    assert_eq!(
+        first_moved_by(CandidateOrder::OldestFirst),
+        "data/f1.bin",
+        "a file without an mtime counts as oldest"
+    );
+    assert_eq!(
+        first_moved_by(CandidateOrder::NewestFirst),
+        "data/f3.bin",
+        "newest first moves the most recent file"
+    );
+}
+
+/// Plan disk1 (80% of 1000) against disk2 (20%) with the given file sizes on disk1.
+fn plan_with_sizes(sizes: &[u64], strategy: TargetStrategy) -> BalanceResult {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 800, 200, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 200, 800, None).unwrap();
+    let files: Vec<FileInsert> = sizes
+        .iter()
+        .enumerate()
+        .map(|(n, &size)| FileInsert {
+            disk_id: d1,
+            file_path: format!("data/f{n}.bin"),
+            size_bytes: size,
+            mtime: None,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+    generate_plan(&db, &PlanOptions { target_strategy: strategy, ..test_opts() }).unwrap()
+}
+
+#[test]
+fn test_min_bytes_moves_less_than_largest_first() {
+    // disk1 must shed 225 bytes to reach the 57.5% upper band.
+    let sizes = [200, 150, 120, 90, 60];
+    let greedy = plan_with_sizes(&sizes, TargetStrategy::MostHeadroom);
+    let min = plan_with_sizes(&sizes, TargetStrategy::MinBytes);
+    assert_eq!(greedy.total_bytes, 350, "largest first takes 200 then 150");
+    assert_eq!(min.total_bytes, 260, "min bytes tops up 200 with the 60 byte file");
+    assert!(
+        (min.projected_imbalance - greedy.projected_imbalance).abs() <= 0.1,
+        "both plans reach the tolerance band"
+    );
+}
+
+#[test]
+fn test_min_bytes_is_never_worse_than_greedy() {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for round in 0..25 {
+        let sizes: Vec<u64> = (0..12)
+            .map(|_| {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                10 + (seed >> 33) % 141
+            })
+            .collect();
+        let greedy = plan_with_sizes(&sizes, TargetStrategy::MostHeadroom);
+        let min = plan_with_sizes(&sizes, TargetStrategy::MinBytes);
+        assert!(
+            min.total_bytes <= greedy.total_bytes,
+            "round {round}: min bytes moved {} vs greedy {} for {sizes:?}",
+            min.total_bytes,
+            greedy.total_bytes
+        );
+        let after = |r: &BalanceResult| {
+            r.disk_projections.iter().find(|p| p.disk_id == 1).unwrap().after_util
+        };
+        assert!(
+            after(&min) <= 0.575 + 1e-9,
+            "round {round}: min bytes still brings disk1 into the band for {sizes:?}"
+        );
+    }
+}
+
+#[test]
+fn test_hardlinked_files_are_never_candidates() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    let file = |path: &str, size: u64, nlink: u64| FileInsert {
+        disk_id: d1,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: Some(size),
+        nlink,
+        followed_link: false,
+    };
+    db.atomic_disk_scan(d1, &[file("media/linked.mkv", 300, 2), file("media/plain.mkv", 200, 1)])
+        .unwrap();
+
+    let result = generate_plan(&db, &test_opts()).unwrap();
+    let paths: Vec<String> = db
+        .get_plan_moves(result.plan_id)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.move_info.file_path)
+        .collect();
+    assert_eq!(paths, vec!["media/plain.mkv".to_string()], "only the unlinked file may move");
+}
+
+#[test]
+fn test_followed_links_are_never_candidates() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    let file = |path: &str, size: u64, followed_link: bool| FileInsert {
+        disk_id: d1,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: Some(size),
+        nlink: 1,
+        followed_link,
+    };
+    db.atomic_disk_scan(
+        d1,
+        &[file("media/link.mkv", 300, true), file("media/plain.mkv", 200, false)],
+    )
+    .unwrap();
+
+    let result = generate_plan(&db, &test_opts()).unwrap();
+    let paths: Vec<String> = db
+        .get_plan_moves(result.plan_id)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.move_info.file_path)
+        .collect();
+    assert_eq!(paths, vec!["media/plain.mkv".to_string()], "a followed link is never planned");
+    assert_eq!(result.total_bytes, 200, "nor counted in the bytes to move");
+}
+
+#[test]
+fn test_absolute_min_free_rejects_targets() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, None).unwrap();
+    let file = FileInsert {
+        disk_id: d1,
+        file_path: "big.mkv".into(),
+        size_bytes: 200,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+    db.atomic_disk_scan(d1, &[file]).unwrap();
+
+    // 900 free - 200 = 700 would remain, under the 750 floor.
+    let opts = PlanOptions { absolute_min_free: 750, ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    assert_eq!(result.total_moves, 0, "the floor must block the only target");
+    assert_eq!(result.no_moves_reason, Some(NoMovesReason::NoTargetFits), "reported as no fit");
+}
+
+#[test]
+fn test_target_utilization_override_replaces_average() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 400, 600, None).unwrap();
+    let files: Vec<FileInsert> = (1..=10)
+        .map(|n| FileInsert {
+            disk_id: d1,
+            file_path: format!("data/f{n}.bin"),
+            size_bytes: 50,
+            mtime: None,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+
+    // The array average is 65%; aim lower so disk2 keeps growth room.
+    let opts = PlanOptions { target_utilization_override: Some(0.5), ..test_opts() };
+    let result = generate_plan(&db, &opts).unwrap();
+    assert!((result.target_utilization - 0.5).abs() < f64::EPSILON, "the override is the target");
+    let disk2 = result.disk_projections.iter().find(|p| p.disk_id == d2).unwrap();
+    assert!(
+        disk2.after_util <= 0.575 + 1e-9,
+        "disk2 should stay within tolerance of the override, got {}",
+        disk2.after_util
+    );
+}
+
+#[test]
+fn test_free_bytes_metric_equalizes_free_space_across_sizes() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let small = db.upsert_disk("disk1", "/mnt/disk1", 1000, 200, 800, None).unwrap();
+    let big = db.upsert_disk("disk2", "/mnt/disk2", 4000, 3600, 400, None).unwrap();
+    let files: Vec<FileInsert> = (1..=30)
+        .map(|n| FileInsert {
+            disk_id: big,
+            file_path: format!("data/f{n}.bin"),
+            size_bytes: 20,
+            mtime: None,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(big, &files).unwrap();
+
+    // 1200 bytes free across two disks: each should end near 600 free.
+    let free_after = |metric: BalanceMetric| {
+        let opts = PlanOptions { balance_metric: metric, slider_alpha: 0.9, ..test_opts() };
+        let result = generate_plan(&db, &opts).unwrap();
+        let free = |id: i64| {
+            let p = result.disk_projections.iter().find(|p| p.disk_id == id).unwrap();
+            let total = if id == small { 1000.0 } else { 4000.0 };
+            (total * (1.0 - p.after_util)).round() as i64
+        };
+        let (small_free, big_free) = (free(small), free(big));
+        (result, small_free, big_free)
+    };
+
+    let (result, small_free, big_free) = free_after(BalanceMetric::FreeBytes);
+    assert!(
+        (small_free - 600).abs() <= 75 && (big_free - 600).abs() <= 75,
+        "both disks should end near 600 free, got {small_free} and {big_free}"
+    );
+    assert!(
+        (result.initial_imbalance - 0.2).abs() < 1e-9,
+        "imbalance is measured against the free-bytes target, got {}",
+        result.initial_imbalance
+    );
+
+    let (_, util_small_free, util_big_free) = free_after(BalanceMetric::Utilization);
+    assert!(
+        (util_big_free - util_small_free).abs() > (big_free - small_free).abs(),
+        "equal utilization leaves the big disk with more free space"
+    );
+}
+
+#[test]
+fn test_drain_moves_every_file_off_the_disk() {
+    let db = seeded_db();
+    let opts = PlanOptions { keep_folders_together: true, ..test_opts() };
+    let result = generate_drain_plan(&db, &opts, 1).unwrap();
+    let moves = db.get_plan_moves(result.plan_id).unwrap();
+    assert_eq!(moves.len(), 8, "every file on disk1 should be moved");
+    assert!(
+        moves.iter().all(|m| m.move_info.source_disk_id == 1 && m.move_info.target_disk_id != 1),
+        "all moves should leave disk1"
+    );
+    let targets: HashSet<i64> = moves.iter().map(|m| m.move_info.target_disk_id).collect();
+    assert_eq!(targets.len(), 2, "the two seasons should spread over both remaining disks");
+}
+
+#[test]
+fn test_drain_reports_shortfall_without_creating_plan() {
+    let db = seeded_db();
+    let opts = PlanOptions { absolute_min_free: 600, ..test_opts() };
+    let err = generate_drain_plan(&db, &opts, 1).unwrap_err().to_string();
+    assert!(err.contains("200 more bytes needed"), "unexpected error: {err}");
+    assert!(db.get_plan(1).unwrap().is_none(), "a failed drain should not create a plan");
+}
+
+#[test]
+fn test_plan_reports_per_disk_projections() {
+    let db = seeded_db();
+    let result = generate_plan(&db, &test_opts()).unwrap();
+    let source = result.disk_projections.iter().find(|p| p.name == "disk1").unwrap();
+    assert!((source.before_util - 0.9).abs() < 1e-9, "disk1 starts 90% full");
+    assert!(source.after_util < source.before_util, "disk1 should end up emptier");
+    assert_eq!(source.bytes_out, result.total_bytes, "every planned byte leaves disk1");
+    let bytes_in: u64 = result.disk_projections.iter().map(|p| p.bytes_in).sum();
+    assert_eq!(bytes_in, result.total_bytes, "every planned byte lands on another disk");
+}
+
+#[test]
+fn test_size_thresholds_keep_sidecars_in_place() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 800, 200, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 200, 800, None).unwrap();
+    let files: Vec<FileInsert> = [
+        ("Movies/A/film.mkv", 200),
+        ("Movies/A/film.NFO", 100),
+        ("Movies/B/other.mkv", 150),
+        ("notes/todo.txt", 40),
+    ]
+    .into_iter()
+    .map(|(path, size)| FileInsert {
+        disk_id: d1,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    })
+    .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+
+    let opts = PlanOptions {
+        slider_alpha: 1.0,
+        size_thresholds: ".mkv:0,.nfo|.srt:never,default:50".parse().unwrap(),
+        ..test_opts()
+    };
+    let result = generate_plan(&db, &opts).unwrap();
+    let moved: Vec<String> = db
+        .get_plan_moves(result.plan_id)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.move_info.file_path)
+        .collect();
+    assert!(moved.contains(&"Movies/A/film.mkv".to_string()), "mkv files balance: {moved:?}");
+    assert!(
+        moved.iter().all(|p| p.starts_with("Movies/") && !p.contains("NFO")),
+        "never-move and below-default files stay put: {moved:?}"
+    );
+}
+
+#[test]
+fn test_max_moves_caps_plan_short_of_balance() {
+    let sizes = [100, 90, 80, 70, 60];
+    let full = plan_with_sizes(&sizes, TargetStrategy::MostHeadroom);
+    assert!(full.total_moves > 1 && !full.capped, "an uncapped plan needs several moves");
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 800, 200, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 200, 800, None).unwrap();
+    let files: Vec<FileInsert> = sizes
+        .iter()
+        .enumerate()
+        .map(|(n, &size)| FileInsert {
+            disk_id: d1,
+            file_path: format!("data/f{n}.bin"),
+            size_bytes: size,
+            mtime: None,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+
+    let capped = generate_plan(&db, &PlanOptions { max_moves: Some(1), ..test_opts() }).unwrap();
+    assert_eq!(capped.total_moves, 1, "planning stops at the cap");
+    assert!(capped.capped, "the summary notes the plan was capped");
+    assert!(
+        capped.projected_imbalance > full.projected_imbalance,
+        "the projection reflects the partial plan"
+    );
+
+    let roomy = generate_plan(&db, &PlanOptions { max_moves: Some(100), ..test_opts() }).unwrap();
+    assert_eq!(roomy.total_moves, full.total_moves, "a loose cap changes nothing");
+    assert!(!roomy.capped, "reaching balance under the cap is not capped");
+}
+
+#[test]
+fn test_min_file_age_leaves_recent_files_in_place() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 800, 200, None).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 200, 800, None).unwrap();
+    let now = chrono::Utc::now().timestamp();
+    let files: Vec<FileInsert> = [
+        ("downloads/new.mkv", 200, Some(now - 86_400)),
+        ("media/old.mkv", 150, Some(now - 30 * 86_400)),
+        ("media/undated.mkv", 120, None),
+    ]
+    .into_iter()
+    .map(|(path, size, mtime)| FileInsert {
+        disk_id: d1,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    })
+    .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+
+    let moved_paths = |opts: &PlanOptions| -> Vec<String> {
+        let result = generate_plan(&db, opts).unwrap();
+        db.get_plan_moves(result.plan_id)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.move_info.file_path)
+            .collect()
+    };
+    let aged = PlanOptions { slider_alpha: 1.0, min_file_age_days: Some(7), ..test_opts() };
+    assert_eq!(moved_paths(&aged), ["media/old.mkv"], "only files older than a week move");
+
+    let undated = PlanOptions { move_undated_files: true, ..aged };
+    let moved = moved_paths(&undated);
+    assert!(
+        moved.contains(&"media/undated.mkv".to_string()),
+        "undated files may opt in: {moved:?}"
+    );
+    assert!(
+        !moved.contains(&"downloads/new.mkv".to_string()),
+        "recent files still stay: {moved:?}"
+    );
+}
+
+#[test]
+fn test_never_target_disks_shed_but_never_receive() {
+    // (disk, used bytes); each used disk holds files of 50 bytes.
+    let plan_targets = |layout: &[(&str, u64)]| -> Vec<(String, String)> {
+        let db = Database::open_in_memory().unwrap();
+        db.run_migrations().unwrap();
+        for &(name, used) in layout {
+            let id = db
+                .upsert_disk(name, &format!("/mnt/{name}"), 1000, used, 1000 - used, None)
+                .unwrap();
+            let files: Vec<FileInsert> = (0..used / 50)
+                .map(|n| FileInsert {
+                    disk_id: id,
+                    file_path: format!("data/f{n}.bin"),
+                    size_bytes: 50,
+                    mtime: None,
+                    inode: None,
+                    nlink: 1,
+                    followed_link: false,
+                })
+                .collect();
+            db.atomic_disk_scan(id, &files).unwrap();
+        }
+        let opts = PlanOptions {
+            slider_alpha: 1.0,
+            never_target_disks: vec!["cache".to_string()],
+            ..test_opts()
+        };
+        let result = generate_plan(&db, &opts).unwrap();
+        db.get_plan_moves(result.plan_id)
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.source_disk_name, m.target_disk_name))
+            .collect()
+    };
+
+    let filling = plan_targets(&[("disk1", 800), ("disk2", 300), ("cache", 0)]);
+    assert!(!filling.is_empty(), "disk1 still sheds files");
+    assert!(filling.iter().all(|(_, t)| t == "disk2"), "nothing lands on cache: {filling:?}");
+
+    let draining = plan_targets(&[("disk1", 200), ("disk2", 200), ("cache", 900)]);
+    assert!(draining.iter().any(|(s, _)| s == "cache"), "cache is still a source: {draining:?}");
+}
+
+#[test]
+fn test_fs_mismatch_warns_and_strict_mode_refuses() {
+    use crate::balancer::filesystems::{fs_caveat, fs_mismatch_warnings};
+
+    assert_eq!(fs_caveat(Some("xfs"), Some("XFS")), None, "same type, any case");
+    assert_eq!(fs_caveat(Some("xfs"), None), None, "unknown filesystems aren't flagged");
+    assert!(fs_caveat(Some("btrfs"), Some("xfs")).is_some(), "btrfs features don't carry over");
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let src = db.upsert_disk("disk1", "/mnt/disk1", 1000, 900, 100, Some("btrfs")).unwrap();
+    db.upsert_disk("disk2", "/mnt/disk2", 1000, 100, 900, Some("xfs")).unwrap();
+    db.upsert_disk("disk3", "/mnt/disk3", 1000, 100, 900, Some("btrfs")).unwrap();
+    let files: Vec<FileInsert> = (1..=8)
+        .map(|n| FileInsert {
+            disk_id: src,
+            file_path: format!("data/f{n}.bin"),
+            size_bytes: 100,
+            mtime: None,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(src, &files).unwrap();
+
+    let loose = generate_plan(&db, &test_opts()).unwrap();
+    let warnings = fs_mismatch_warnings(
+        &db.get_plan_flows(loose.plan_id).unwrap(),
+        &db.get_all_disks().unwrap(),
+    );
+    assert_eq!(warnings.len(), 1, "only the btrfs → xfs pair is flagged: {warnings:?}");
+    assert!(warnings[0].starts_with("disk1 (btrfs) → disk2 (xfs)"), "names the pair: {warnings:?}");
+
+    let strict = PlanOptions { refuse_fs_mismatch: true, ..test_opts() };
+    let result = generate_plan(&db, &strict).unwrap();
+    let targets: HashSet<String> = db
+        .get_plan_moves(result.plan_id)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.target_disk_name)
+        .collect();
+    assert_eq!(targets, HashSet::from(["disk3".to_string()]), "strict mode keeps moves on btrfs");
+}
+
+#[test]
+fn test_balance_score_known_layouts() {
+    use crate::balancer::analysis::balance_score;
+
+    let score = |utils: &[f64], target: f64| balance_score(utils.iter().map(|&u| (u, target)));
+    assert_eq!(score(&[0.5, 0.5, 0.5], 0.5), 100, "every disk at target is perfect");
+    assert_eq!(score(&[0.4, 0.5, 0.6, 0.5], 0.5), 85, "small spread scores high");
+    assert_eq!(score(&[0.3, 0.7], 0.5), 60, "two disks 20 points off");
+    assert_eq!(score(&[0.0, 1.0], 0.5), 0, "one empty and one full disk is worst");
+    assert_eq!(score(&[0.7, 0.9, 0.8], 0.8), 90, "deviation scales by the room at the target");
+    assert_eq!(score(&[], 0.5), 100, "no disks means nothing to balance");
+}
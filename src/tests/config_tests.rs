@@ -45,3 +45,123 @@ fn test_catalog_path_empty_keeps_default() {
     config.parse_ini(r#"CATALOG_PATH="""#);
     assert_eq!(config.db_path, default_path);
 }
+
+#[test]
+fn test_move_timeout_zero_disables() {
+    let mut config = AppConfig::default();
+    config.parse_ini(r#"MOVE_TIMEOUT_SECS="600""#);
+    assert_eq!(config.move_timeout_secs, Some(600), "MOVE_TIMEOUT_SECS should be parsed");
+    config.parse_ini(r#"MOVE_TIMEOUT_SECS="0""#);
+    assert_eq!(config.move_timeout_secs, None, "0 should disable the move timeout");
+}
+
+#[test]
+fn test_open_file_policy_round_trips() {
+    use crate::executor::OpenFilePolicy;
+
+    let mut config = AppConfig::default();
+    assert_eq!(config.open_file_policy, OpenFilePolicy::Skip, "skipping is the default");
+    config.parse_ini(r#"OPEN_FILE_POLICY="wait:5:30""#);
+    let wait = OpenFilePolicy::Wait { retries: 5, delay_secs: 30 };
+    assert_eq!(config.open_file_policy, wait, "explicit retries and delay");
+    assert_eq!(wait.to_string().parse::<OpenFilePolicy>().unwrap(), wait, "Display round-trips");
+    config.parse_ini(r#"OPEN_FILE_POLICY="wait""#);
+    assert!(
+        matches!(config.open_file_policy, OpenFilePolicy::Wait { retries: 3, delay_secs: 10 }),
+        "bare wait uses the defaults"
+    );
+}
+
+#[test]
+fn test_profiles_apply_on_top_of_base() {
+    let mut config = AppConfig::default();
+    config.parse_ini(
+        r#"
+SCAN_THREADS="2"
+MAX_TOLERANCE="0.15"
+
+[profile overnight]
+SCAN_THREADS="6"
+
+[profile broken]
+SLIDER_ALPHA="2.5"
+
+[profile default]
+"#,
+    );
+    assert_eq!(config.scan_threads, 2, "profile lines must not change the base config");
+    assert_eq!(config.profiles.len(), 3, "empty profile sections should still be listed");
+
+    let overnight = config.with_profile("overnight").unwrap();
+    assert_eq!(overnight.scan_threads, 6, "profile override should apply");
+    assert!((overnight.max_tolerance - 0.15).abs() < f64::EPSILON, "unset keys keep base values");
+    assert_eq!(overnight.active_profile.as_deref(), Some("overnight"));
+
+    assert!(config.with_profile("broken").is_err(), "invalid profile values must be rejected");
+    assert!(config.with_profile("missing").is_err(), "unknown profiles must be rejected");
+}
+
+#[test]
+fn test_reload_saved_keeps_configured_catalog() {
+    let dir = std::env::temp_dir().join(format!("pb-reload-catalog-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config_path = dir.join("perfectly-balanced.cfg");
+    std::fs::write(&config_path, "CATALOG_PATH=\"/mnt/cache/pb/catalog.db\"\n").unwrap();
+    // Running on the fallback catalog after the configured one was unavailable.
+    let running = AppConfig {
+        config_path: config_path.to_string_lossy().into_owned(),
+        db_path: "/tmp/pb-fallback/catalog.db".to_string(),
+        ..AppConfig::default()
+    };
+    let saved = running.reload_saved().unwrap();
+    assert_eq!(
+        saved.db_path, "/mnt/cache/pb/catalog.db",
+        "saving settings must not write the fallback over CATALOG_PATH"
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_move_size_thresholds_round_trip() {
+    use crate::balancer::SizeThresholds;
+
+    let mut config = AppConfig::default();
+    config.parse_ini(r#"MOVE_SIZE_THRESHOLDS=".MKV:0, .nfo|.srt:never, default:50MB""#);
+    let thresholds = &config.move_size_thresholds;
+    assert!(thresholds.allows("mkv", 1), "extensions are matched case-insensitively");
+    assert!(!thresholds.allows("srt", u64::MAX), "never-move types are excluded at any size");
+    assert!(!thresholds.allows("txt", 50 * 1024 * 1024 - 1), "default applies to other types");
+    assert!(thresholds.allows("txt", 50 * 1024 * 1024), "default is a minimum, inclusive");
+    assert_eq!(thresholds.to_string(), ".mkv:0,.nfo:never,.srt:never,default:50MB");
+    assert_eq!(thresholds.to_string().parse::<SizeThresholds>().unwrap(), *thresholds);
+
+    assert!(SizeThresholds::default().allows("nfo", 0), "no thresholds lets everything move");
+    assert!("mkv=0".parse::<SizeThresholds>().is_err(), "malformed entries are rejected");
+}
+
+#[test]
+fn test_parallel_disk_scans_bounds() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.parallel_disk_scans, 1, "disks scan one at a time by default");
+    config.parse_ini(r#"PARALLEL_DISK_SCANS="4""#);
+    assert_eq!(config.parallel_disk_scans, 4, "PARALLEL_DISK_SCANS should be parsed");
+    assert!(config.validate().is_ok(), "4 concurrent disk scans is allowed");
+    config.parallel_disk_scans = 0;
+    assert!(config.validate().is_err(), "at least one disk must scan at a time");
+}
+
+#[test]
+fn test_cancel_mode_parses() {
+    use crate::executor::CancelMode;
+
+    let mut config = AppConfig::default();
+    assert_eq!(config.cancel_mode, CancelMode::Immediate, "cancel kills rsync by default");
+    config.parse_ini(r#"CANCEL_MODE="graceful""#);
+    assert_eq!(config.cancel_mode, CancelMode::Graceful, "graceful is accepted");
+    assert_eq!(
+        CancelMode::Graceful.as_str().parse::<CancelMode>().unwrap(),
+        CancelMode::Graceful,
+        "as_str round-trips"
+    );
+    assert!("later".parse::<CancelMode>().is_err(), "unknown modes are rejected");
+}
@@ -1,4 +1,5 @@
-use crate::db::Database;
+use crate::api::responses::DiskGrowth;
+use crate::db::{Database, FileInsert, MoveStatus, PlanStatus, PlannedMove};
 
 #[test]
 fn test_open_and_migrate() {
@@ -14,3 +15,488 @@ fn test_open_and_migrate() {
         .unwrap();
     assert_eq!(count, 1, "disks table should exist after migration");
 }
+
+#[test]
+fn test_scan_rebuilds_file_type_stats() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let disk_id = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let file = |path: &str, size: u64| FileInsert {
+        disk_id,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+    let files = [
+        file("Movies/a.b/Film.MKV", 400),
+        file("Movies/other.mkv", 100),
+        file("iso/disc.v2.iso", 120),
+        file("docs/README", 5),
+        file(".bashrc", 1),
+    ];
+    db.atomic_disk_scan(disk_id, &files).unwrap();
+
+    let stats = db.get_file_type_stats(disk_id).unwrap();
+    let summary: Vec<(&str, u64, u64)> =
+        stats.iter().map(|s| (s.extension.as_str(), s.file_count, s.total_bytes)).collect();
+    assert_eq!(summary, [("mkv", 2, 500), ("iso", 1, 120), ("none", 2, 6)]);
+
+    // A rescan replaces the previous breakdown rather than adding to it.
+    db.atomic_disk_scan(disk_id, &files[..1]).unwrap();
+    let rescanned = db.get_file_type_stats(disk_id).unwrap();
+    assert_eq!(rescanned.len(), 1, "stale extensions should be dropped on rescan");
+}
+
+#[test]
+fn test_largest_files_respects_limit_and_disk() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 0, 1000, None).unwrap();
+    let file = |disk_id: i64, path: &str, size: u64| FileInsert {
+        disk_id,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+    db.atomic_disk_scan(d1, &[file(d1, "a", 10), file(d1, "b", 30), file(d1, "c", 20)]).unwrap();
+    db.atomic_disk_scan(d2, &[file(d2, "d", 25)]).unwrap();
+
+    let on_disk: Vec<String> =
+        db.get_largest_files_on_disk(d1, 2).unwrap().into_iter().map(|f| f.file_path).collect();
+    assert_eq!(on_disk, ["b", "c"], "per-disk query returns only that disk, largest first");
+
+    let global: Vec<String> =
+        db.get_largest_files(3).unwrap().into_iter().map(|f| f.file_path).collect();
+    assert_eq!(global, ["b", "d", "c"], "global query ranks across disks");
+}
+
+#[test]
+fn test_curate_pending_moves() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let moves: Vec<PlannedMove> = (1..=4)
+        .map(|i| PlannedMove {
+            id: 0,
+            plan_id,
+            source_disk_id: d1,
+            target_disk_id: d2,
+            file_path: format!("f{i}"),
+            file_size: 10 * i as u64,
+            move_order: i,
+            phase: 1,
+            status: MoveStatus::Pending,
+            error_message: None,
+            source_mtime: None,
+            target_path: None,
+        })
+        .collect();
+    db.insert_planned_moves(&moves).unwrap();
+    let ids: Vec<i64> =
+        db.get_plan_moves(plan_id).unwrap().iter().map(|m| m.move_info.id).collect();
+    db.update_move_status(ids[0], MoveStatus::Completed, None).unwrap();
+
+    assert!(!db.delete_pending_move(plan_id, ids[0]).unwrap(), "completed moves can't be removed");
+    assert!(db.delete_pending_move(plan_id, ids[1]).unwrap(), "pending move should be removed");
+    db.refresh_plan_totals(plan_id).unwrap();
+    let plan = db.get_plan(plan_id).unwrap().unwrap();
+    assert_eq!((plan.total_moves, plan.total_bytes_to_move), (3, 80), "totals follow the edit");
+
+    assert!(db.reorder_pending_moves(plan_id, &[ids[3], ids[0]]).is_err(), "only pending moves");
+    db.reorder_pending_moves(plan_id, &[ids[3], ids[2]]).unwrap();
+    let order: Vec<String> =
+        db.get_plan_moves(plan_id).unwrap().into_iter().map(|m| m.move_info.file_path).collect();
+    assert_eq!(order, ["f1", "f4", "f3"], "completed move keeps its slot, pending ones swap");
+}
+
+#[test]
+fn test_scan_snapshots_report_growth() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let disk_id = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let file = |path: &str, size: u64| FileInsert {
+        disk_id,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+
+    db.atomic_disk_scan(disk_id, &[file("a.mkv", 100), file("b.iso", 50)]).unwrap();
+    let first = db.get_recent_snapshots(disk_id, 2).unwrap();
+    let baseline = DiskGrowth::between(&first[0], first.get(1));
+    assert_eq!(baseline.bytes_delta, None, "first scan is only a baseline");
+
+    db.atomic_disk_scan(disk_id, &[file("a.mkv", 100), file("c.mkv", 300)]).unwrap();
+    let latest = db.get_recent_snapshots(disk_id, 2).unwrap();
+    let growth = DiskGrowth::between(&latest[0], latest.get(1));
+    assert_eq!(growth.bytes_delta, Some(250), "net growth across the disk");
+    assert_eq!(growth.file_count_delta, Some(0), "one file added, one removed");
+    assert_eq!((growth.bytes_added, growth.bytes_removed), (Some(300), Some(50)));
+    assert_eq!(growth.extension_deltas[0].extension, "mkv", "largest change first");
+}
+
+#[test]
+fn test_recent_throughput_averages_history() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    assert_eq!(db.recent_throughput(5).unwrap(), None, "no history yet");
+
+    db.record_execution(1, 3, 1_000, 10.0).unwrap();
+    db.record_execution(2, 0, 0, 4.0).unwrap();
+    db.record_execution(3, 1, 3_000, 10.0).unwrap();
+    let rate = db.recent_throughput(5).unwrap().unwrap();
+    assert!((rate - 200.0).abs() < f64::EPSILON, "byte-weighted 4000 B / 20 s, got {rate}");
+}
+
+#[test]
+fn test_cancel_plan_records_reason() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    db.cancel_plan(plan_id, Some("cancelled by user: watching a movie")).unwrap();
+
+    let plan = db.get_plan(plan_id).unwrap().unwrap();
+    assert_eq!(plan.status, PlanStatus::Cancelled, "plan should be cancelled");
+    assert_eq!(
+        plan.cancel_reason.as_deref(),
+        Some("cancelled by user: watching a movie"),
+        "reason should be stored on the plan"
+    );
+}
+
+#[test]
+fn test_list_folder_groups_children() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let disk_id = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let file = |path: &str, size: u64| FileInsert {
+        disk_id,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+    let files = [
+        file("movies/a/a.mkv", 10),
+        file("movies/a/a.srt", 1),
+        file("movies/b.mkv", 20),
+        file("movies-old/c.mkv", 5),
+        file("notes.txt", 2),
+    ];
+    db.atomic_disk_scan(disk_id, &files).unwrap();
+
+    let (root, root_total) = db.list_folder(disk_id, "", 10, 0).unwrap();
+    let names: Vec<(&str, bool, u64)> =
+        root.iter().map(|e| (e.name.as_str(), e.is_dir, e.size_bytes)).collect();
+    assert_eq!(
+        names,
+        vec![("movies", true, 31), ("movies-old", true, 5), ("notes.txt", false, 2)],
+        "root lists folders first with aggregated sizes"
+    );
+    assert_eq!(root_total, 3, "three children at the root");
+
+    let (movies, movies_total) = db.list_folder(disk_id, "movies", 1, 1).unwrap();
+    assert_eq!(movies_total, 2, "movies-old must not leak into movies");
+    assert_eq!(movies.len(), 1, "page size respected");
+    assert_eq!(movies[0].name, "b.mkv", "second entry after the subfolder");
+}
+
+#[test]
+fn test_vacuum_reclaims_deleted_pages() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let disk_id = db.upsert_disk("disk1", "/mnt/disk1", 1000, 500, 500, None).unwrap();
+    let files: Vec<FileInsert> = (0..2000)
+        .map(|i| FileInsert {
+            disk_id,
+            file_path: format!("media/some/long/folder/name/file-{i:05}.mkv"),
+            size_bytes: 1,
+            mtime: None,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(disk_id, &files).unwrap();
+    db.atomic_disk_scan(disk_id, &[]).unwrap();
+
+    let (before, after) = db.vacuum().unwrap();
+    assert!(after < before, "vacuum should shrink the file ({before} -> {after})");
+}
+
+#[test]
+fn test_disk_age_from_updated_at() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let disk_id = db.upsert_disk("disk1", "/mnt/disk1", 1000, 500, 500, None).unwrap();
+    let disk = db.get_disk(disk_id).unwrap().unwrap();
+    let later = chrono::Utc::now() + chrono::Duration::hours(2);
+    let age = disk.age_secs(later).unwrap();
+    assert!((7199..=7201).contains(&age), "freshly scanned disk should be ~2h old then, got {age}");
+}
+
+#[test]
+fn test_find_duplicates_groups_by_name_and_size_across_disks() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let d4 = db.upsert_disk("disk4", "/mnt/disk4", 1000, 0, 1000, None).unwrap();
+    let file = |disk_id: i64, path: &str, size: u64| FileInsert {
+        disk_id,
+        file_path: path.to_string(),
+        size_bytes: size,
+        mtime: None,
+        inode: None,
+        nlink: 1,
+        followed_link: false,
+    };
+    db.atomic_disk_scan(
+        d1,
+        &[
+            file(d1, "Movies/A/movie.mkv", 500),
+            file(d1, "Movies/B/other.mkv", 300),
+            file(d1, "poster.jpg", 2),
+        ],
+    )
+    .unwrap();
+    db.atomic_disk_scan(
+        d4,
+        &[
+            file(d4, "Backup/movie.mkv", 500),
+            file(d4, "Movies/B/other.mkv", 301),
+            file(d4, "poster.jpg", 2),
+        ],
+    )
+    .unwrap();
+
+    let groups = db.find_duplicates(10, 100).unwrap();
+    assert_eq!(groups.len(), 1, "only same-name, same-size files above min_size: {groups:?}");
+    let group = &groups[0];
+    assert_eq!((group.file_name.as_str(), group.size_bytes), ("movie.mkv", 500));
+    let places: Vec<(&str, &str)> =
+        group.locations.iter().map(|l| (l.disk_name.as_str(), l.file_path.as_str())).collect();
+    assert_eq!(places, [("disk1", "Movies/A/movie.mkv"), ("disk4", "Backup/movie.mkv")]);
+
+    assert_eq!(db.find_duplicates(0, 100).unwrap().len(), 2, "min_size 0 includes small files");
+}
+
+#[test]
+fn test_open_sets_busy_timeout_and_checkpoints() {
+    let dir = std::env::temp_dir().join(format!("pb-busy-{}", std::process::id()));
+    let path = dir.join("catalog.db");
+    let db = Database::open(&path.to_string_lossy(), 1234).unwrap();
+    db.run_migrations().unwrap();
+    let timeout: i64 =
+        db.conn().unwrap().query_row("PRAGMA busy_timeout", [], |r| r.get(0)).unwrap();
+    assert_eq!(timeout, 1234, "queries wait on locks instead of failing at once");
+
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let files: Vec<FileInsert> = (0..100)
+        .map(|n| FileInsert {
+            disk_id: d1,
+            file_path: format!("f{n}"),
+            size_bytes: 1,
+            mtime: None,
+            inode: None,
+            nlink: 1,
+            followed_link: false,
+        })
+        .collect();
+    db.atomic_disk_scan(d1, &files).unwrap();
+    db.checkpoint().unwrap();
+    assert_eq!(
+        db.get_all_files_on_disk_by_size(d1).unwrap().len(),
+        100,
+        "scan survives checkpoint"
+    );
+
+    drop(db);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_open_with_retry_gives_up_after_wait() {
+    // A regular file as the parent directory can never be created.
+    let blocker = std::env::temp_dir().join(format!("pb-retry-{}", std::process::id()));
+    std::fs::write(&blocker, b"").unwrap();
+    let path = blocker.join("catalog.db");
+
+    let started = std::time::Instant::now();
+    let result = Database::open_with_retry(
+        path.to_str().unwrap(),
+        1000,
+        std::time::Duration::from_millis(300),
+    );
+    assert!(result.is_err(), "an uncreatable directory should still fail");
+    assert!(started.elapsed() >= std::time::Duration::from_millis(300), "should retry until wait");
+
+    std::fs::remove_file(&blocker).unwrap();
+    let db = Database::open_with_retry(
+        path.with_file_name("pb-retry-ok.db").to_str().unwrap(),
+        1000,
+        std::time::Duration::ZERO,
+    );
+    assert!(db.is_ok(), "a reachable path opens on the first try");
+    drop(db);
+    std::fs::remove_dir_all(&blocker).unwrap();
+}
+
+#[test]
+fn test_bulk_include_reports_unknown_ids() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 0, 1000, None).unwrap();
+    db.set_disk_included(d2, false).unwrap();
+
+    let unknown = db.set_disks_included_bulk(&[d2, 99], &[d1]).unwrap();
+    assert_eq!(unknown, vec![99], "missing ids are reported, not fatal");
+    let included = |id: i64| db.get_disk(id).unwrap().unwrap().included;
+    assert!(included(d2), "disk2 should be included");
+    assert!(!included(d1), "disk1 should be excluded");
+}
+
+#[test]
+fn test_plan_flows_group_by_disk_pair() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 0, 1000, None).unwrap();
+    let d3 = db.upsert_disk("disk3", "/mnt/disk3", 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let moves: Vec<PlannedMove> = [(d1, d2, 10), (d1, d3, 50), (d1, d2, 30)]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (source, target, size))| PlannedMove {
+            id: 0,
+            plan_id,
+            source_disk_id: source,
+            target_disk_id: target,
+            file_path: format!("f{i}"),
+            file_size: size,
+            move_order: i as i32 + 1,
+            phase: 1,
+            status: MoveStatus::Pending,
+            error_message: None,
+            source_mtime: None,
+            target_path: None,
+        })
+        .collect();
+    db.insert_planned_moves(&moves).unwrap();
+
+    let flows = db.get_plan_flows(plan_id).unwrap();
+    let pairs: Vec<(i64, i64, u64, u64)> =
+        flows.iter().map(|f| (f.source_disk_id, f.target_disk_id, f.moves, f.bytes)).collect();
+    assert_eq!(pairs, [(d1, d3, 1, 50), (d1, d2, 2, 40)], "one row per pair, heaviest first");
+}
+
+#[test]
+fn test_full_target_skips_only_its_pending_moves() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 0, 1000, None).unwrap();
+    let d3 = db.upsert_disk("disk3", "/mnt/disk3", 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let moves: Vec<PlannedMove> = [d2, d2, d3, d2]
+        .into_iter()
+        .enumerate()
+        .map(|(i, target)| PlannedMove {
+            id: 0,
+            plan_id,
+            source_disk_id: d1,
+            target_disk_id: target,
+            file_path: format!("f{i}"),
+            file_size: 10,
+            move_order: i as i32 + 1,
+            phase: i as i32 + 1,
+            status: MoveStatus::Pending,
+            error_message: None,
+            source_mtime: None,
+            target_path: None,
+        })
+        .collect();
+    db.insert_planned_moves(&moves).unwrap();
+    let first = db.get_plan_moves(plan_id).unwrap()[0].move_info.id;
+    db.update_move_status(first, MoveStatus::Completed, None).unwrap();
+
+    let skipped = db.skip_pending_moves_to_disk(plan_id, d2, "Target full").unwrap();
+    assert_eq!(skipped, 2, "only disk2's pending moves, across every phase");
+    let statuses: Vec<MoveStatus> =
+        db.get_plan_moves(plan_id).unwrap().iter().map(|m| m.move_info.status).collect();
+    assert_eq!(
+        statuses,
+        [MoveStatus::Completed, MoveStatus::Skipped, MoveStatus::Pending, MoveStatus::Skipped]
+    );
+}
+
+#[test]
+fn test_plan_moves_page_filters_by_status() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", "/mnt/disk1", 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", "/mnt/disk2", 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let moves: Vec<PlannedMove> = (1..=10)
+        .map(|i| PlannedMove {
+            id: 0,
+            plan_id,
+            source_disk_id: d1,
+            target_disk_id: d2,
+            file_path: format!("f{i}"),
+            file_size: 10,
+            move_order: i,
+            phase: 1,
+            status: MoveStatus::Pending,
+            error_message: None,
+            source_mtime: None,
+            target_path: None,
+        })
+        .collect();
+    db.insert_planned_moves(&moves).unwrap();
+    let all = db.get_plan_moves(plan_id).unwrap();
+    for m in all.iter().filter(|m| m.move_info.move_order % 2 == 0) {
+        db.update_move_status(m.move_info.id, MoveStatus::Failed, Some("boom")).unwrap();
+    }
+
+    let (page, total) = db.get_plan_moves_page(plan_id, Some(MoveStatus::Failed), 2, 1).unwrap();
+    assert_eq!(total, 5, "total counts every failed move, not just the page");
+    let paths: Vec<&str> = page.iter().map(|m| m.move_info.file_path.as_str()).collect();
+    assert_eq!(paths, ["f4", "f6"], "second and third failed moves in execution order");
+
+    let (_, everything) = db.get_plan_moves_page(plan_id, None, 100, 0).unwrap();
+    assert_eq!(everything, 10, "no filter counts every move");
+}
+
+#[test]
+fn test_plan_labels_and_tag_filter() {
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let first = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let second = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let tags = vec!["retire".to_string(), "disk5".to_string()];
+    db.set_plan_labels(first, Some("pre-disk5-retirement"), &tags).unwrap();
+
+    let plan = db.get_plan(first).unwrap().unwrap();
+    assert_eq!(plan.name.as_deref(), Some("pre-disk5-retirement"), "name is stored");
+    assert_eq!(plan.tags, ["disk5", "retire"], "tags come back sorted");
+
+    let all: Vec<i64> = db.list_plans(None).unwrap().iter().map(|p| p.id).collect();
+    assert_eq!(all, [second, first], "newest plan first");
+    let tagged: Vec<i64> = db.list_plans(Some("disk5")).unwrap().iter().map(|p| p.id).collect();
+    assert_eq!(tagged, [first], "only the tagged plan matches");
+}
@@ -0,0 +1,47 @@
+use crate::events::{DiskScanSummary, Event, EventHub};
+
+fn error_event(n: u32) -> Event {
+    Event::DaemonError { message: format!("e{n}") }
+}
+
+#[test]
+fn test_replay_returns_only_missed_events() {
+    let hub = EventHub::new(8);
+    for n in 1..=3 {
+        let _ = hub.publish(error_event(n));
+    }
+    let (missed, _rx) = hub.subscribe_since(1);
+    let ids: Vec<u64> = missed.iter().map(|e| e.id).collect();
+    assert_eq!(ids, [2, 3], "events after the last seen id should be replayed");
+}
+
+#[test]
+fn test_replay_buffer_is_capped() {
+    let hub = EventHub::new(4);
+    for n in 1..=10 {
+        let _ = hub.publish(error_event(n));
+    }
+    let (missed, _rx) = hub.subscribe_since(0);
+    let ids: Vec<u64> = missed.iter().map(|e| e.id).collect();
+    assert_eq!(ids, [7, 8, 9, 10], "a long-gone client only gets what is still buffered");
+}
+
+#[test]
+fn test_scan_complete_carries_per_disk_breakdown() {
+    let event = Event::ScanComplete {
+        total_disks: 2,
+        total_files: 3,
+        total_bytes: 30,
+        duration_seconds: 1.0,
+        per_disk: vec![
+            DiskScanSummary { disk: "disk1".to_string(), files: 1, bytes: 10 },
+            DiskScanSummary { disk: "disk2".to_string(), files: 2, bytes: 20 },
+        ],
+    };
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(
+        json["data"]["per_disk"][1],
+        serde_json::json!({"disk": "disk2", "files": 2, "bytes": 20}),
+        "each disk's totals should be in the final event"
+    );
+}
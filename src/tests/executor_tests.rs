@@ -0,0 +1,738 @@
+use crate::db::{MoveStatus, PlannedMove, PlannedMoveDetail};
+use crate::executor::script::render_plan_script;
+use crate::executor::{
+    build_rsync_args, copy_size_mismatch, parse_disk_devices, parse_rsync_progress,
+    parse_rsync_version, parse_smart_health, parse_spun_down_disks, partial_fragment_path,
+    rsync_command, staging_path, validate_extra_rsync_args, PathRemap, ProgressThrottle,
+    RsyncOptions, SmartHealth,
+};
+use std::collections::HashMap;
+
+#[test]
+fn test_parse_spun_down_disks_ini() {
+    let ini = r#"
+["parity"]
+name="parity"
+spundown="0"
+["disk1"]
+name="disk1"
+spundown="1"
+["disk2"]
+name="disk2"
+spundown="0"
+"#;
+    let spun_down = parse_spun_down_disks(ini);
+    assert!(spun_down.contains("disk1"), "disk1 is spun down");
+    assert!(!spun_down.contains("disk2"), "disk2 is spinning");
+    assert_eq!(spun_down.len(), 1, "only disk1 should be reported");
+}
+
+#[test]
+fn test_plan_script_quotes_paths_and_mirrors_rsync_args() {
+    let detail = PlannedMoveDetail {
+        move_info: PlannedMove {
+            id: 7,
+            plan_id: 1,
+            source_disk_id: 1,
+            target_disk_id: 2,
+            file_path: "movies/Bob's Film.mkv".to_string(),
+            file_size: 42,
+            move_order: 1,
+            phase: 1,
+            status: MoveStatus::Pending,
+            error_message: None,
+            source_mtime: None,
+            target_path: None,
+        },
+        source_disk_name: "disk1".to_string(),
+        target_disk_name: "disk2".to_string(),
+    };
+    let mounts = HashMap::from([(1, "/mnt/disk1".to_string()), (2, "/mnt/disk2".to_string())]);
+
+    let rsync = RsyncOptions { use_progress2: true, ..RsyncOptions::default() };
+    let script = render_plan_script(1, &[detail], &mounts, &rsync, &PathRemap::default());
+    assert!(script.starts_with("#!/bin/bash\n"), "script needs a shebang");
+    assert!(
+        script.contains(
+            "rsync '-avPX' '--links' '--info=progress2' '/mnt/disk1/movies/Bob'\\''s Film.mkv'"
+        ),
+        "rsync line should match executor args with safe quoting:\n{script}"
+    );
+    assert!(script.contains("rm '/mnt/disk1/movies/Bob'\\''s Film.mkv'"), "source removal missing");
+}
+
+#[test]
+fn test_resume_partial_uses_partial_dir() {
+    let resume = RsyncOptions { resume_partial: true, ..RsyncOptions::default() };
+    let args = build_rsync_args(&resume, "/mnt/disk1/a/f.mkv", "/mnt/disk2/a/f.mkv");
+    assert!(
+        args.contains(&"--partial-dir=.pb-partial".to_string()),
+        "partial dir missing: {args:?}"
+    );
+    assert!(args.contains(&"--no-whole-file".to_string()), "delta transfer needed to resume");
+    assert_eq!(args.last().map(String::as_str), Some("/mnt/disk2/a/f.mkv"), "target goes last");
+
+    let plain =
+        build_rsync_args(&RsyncOptions::default(), "/mnt/disk1/a/f.mkv", "/mnt/disk2/a/f.mkv");
+    assert!(!plain.iter().any(|a| a.starts_with("--partial-dir")), "resume off keeps plain args");
+
+    assert_eq!(
+        partial_fragment_path("/mnt/disk2/a/f.mkv"),
+        Some(std::path::PathBuf::from("/mnt/disk2/a/.pb-partial/f.mkv"))
+    );
+}
+
+#[test]
+fn test_extra_rsync_args_blocklist() {
+    let ok = |args: &[&str]| {
+        validate_extra_rsync_args(&args.iter().map(|a| (*a).to_string()).collect::<Vec<_>>())
+            .is_ok()
+    };
+    assert!(ok(&["--numeric-ids", "--sparse", "-H"]), "harmless flags should be accepted");
+    assert!(!ok(&["--delete"]), "--delete must be rejected");
+    assert!(!ok(&["--delete-after"]), "--delete variants must be rejected");
+    assert!(!ok(&["--remove-source-files"]), "--remove* must be rejected");
+    assert!(!ok(&["--link-dest=/mnt/disk3"]), "destination overrides must be rejected");
+    assert!(!ok(&["-avn"]), "blocked short flags are caught inside clusters");
+    assert!(!ok(&["--copy-links"]) && !ok(&["-L"]), "symlinks must never be dereferenced");
+    assert!(!ok(&["/mnt/disk3/"]), "bare paths would add a destination");
+    assert!(
+        !ok(&["--append"]) && !ok(&["--append-verify"]),
+        "appending onto a leftover fragment must be rejected"
+    );
+    assert!(
+        !ok(&["--relative"]) && !ok(&["-aR"]),
+        "the copy must land at the staged name, not a relative path"
+    );
+    assert!(!ok(&["--existing"]) && !ok(&["--ignore-existing"]), "rsync must never skip the copy");
+
+    let opts = RsyncOptions { extra_args: vec!["--sparse".to_string()], ..RsyncOptions::default() };
+    let args = build_rsync_args(&opts, "/mnt/disk1/f", "/mnt/disk2/f");
+    assert_eq!(args[args.len() - 3], "--sparse", "extra flags go right before source and target");
+}
+
+#[test]
+fn test_estimate_falls_back_to_assumed_throughput() {
+    use crate::executor::estimate::{estimate_seconds, EstimateBasis};
+
+    let (secs, basis) = estimate_seconds(1_000, 2, None, 100);
+    assert_eq!(basis, EstimateBasis::Assumed, "no history uses the configured rate");
+    assert!(secs > 10.0, "copy time plus per-move overhead, got {secs}");
+
+    let (measured_secs, measured_basis) = estimate_seconds(1_000, 2, Some(50.0), 100);
+    assert_eq!(measured_basis, EstimateBasis::Measured, "history wins when present");
+    assert!((measured_secs - 20.0).abs() < f64::EPSILON, "1000 B at 50 B/s, got {measured_secs}");
+}
+
+#[test]
+fn test_remaining_seconds_uses_rate_so_far() {
+    use crate::executor::estimate::remaining_seconds;
+
+    assert_eq!(remaining_seconds(0, 1_000, 30.0), None, "no rate before any bytes move");
+    let eta = remaining_seconds(250, 1_000, 10.0).unwrap();
+    assert!((eta - 30.0).abs() < f64::EPSILON, "750 B left at 25 B/s, got {eta}");
+    assert_eq!(remaining_seconds(1_000, 1_000, 10.0), Some(0.0), "nothing left once done");
+}
+
+#[test]
+fn test_target_conflict_detects_different_file() {
+    use crate::executor::target_conflict;
+
+    let dir = std::env::temp_dir().join(format!("pb-conflict-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("source.mkv");
+    std::fs::write(&source, b"movie").unwrap();
+    let source_meta = std::fs::metadata(&source).unwrap();
+
+    let free = dir.join("free.mkv");
+    let occupied = dir.join("occupied.mkv");
+    std::fs::write(&occupied, b"another film").unwrap();
+
+    let free_result = target_conflict(&source_meta, free.to_str().unwrap());
+    let occupied_result = target_conflict(&source_meta, occupied.to_str().unwrap());
+    let same_result = target_conflict(&source_meta, source.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(free_result.is_none(), "a missing target is not a conflict");
+    assert!(occupied_result.is_some(), "a different file at the target is a conflict");
+    assert!(same_result.is_none(), "an identical copy is not a conflict");
+}
+
+#[test]
+fn test_rsync_disk_full_classification() {
+    use crate::executor::rsync_disk_full;
+    let enospc =
+        "rsync: [receiver] write failed on \"/mnt/disk2/f.mkv\": No space left on device (28)";
+    assert!(rsync_disk_full(11, enospc, None, 10), "ENOSPC in stderr is decisive");
+    assert!(rsync_disk_full(12, "connection unexpectedly closed", Some(5), 10));
+    assert!(
+        !rsync_disk_full(12, "connection unexpectedly closed", Some(50), 10),
+        "a stream error with room to spare is something else"
+    );
+    assert!(
+        !rsync_disk_full(23, "some files vanished", Some(5), 10),
+        "other exit codes need ENOSPC"
+    );
+}
+
+#[test]
+fn test_stderr_tail_keeps_last_lines() {
+    use crate::executor::StderrTail;
+    let mut tail = StderrTail::default();
+    assert_eq!(tail.summary(), None, "no stderr, no summary");
+    for i in 1..=7 {
+        tail.push(&format!("rsync: warning {i}\n"));
+        tail.push("   ");
+    }
+    tail.push("rsync error: some files/attrs were not transferred (code 23)");
+    assert_eq!(
+        tail.summary().as_deref(),
+        Some(
+            "rsync: warning 4; rsync: warning 5; rsync: warning 6; rsync: warning 7; \
+             rsync error: some files/attrs were not transferred (code 23)"
+        ),
+        "only the last five non-empty lines are kept"
+    );
+}
+
+#[test]
+fn test_audit_line_is_one_escaped_row() {
+    use crate::executor::audit::audit_line;
+    let line = audit_line(
+        "2026-01-02T03:04:05Z",
+        MoveStatus::Failed,
+        1024,
+        ("/mnt/disk1/Movies/a\tb.mkv", "/mnt/disk2/Movies/a\tb.mkv"),
+        Some("rsync exited with code 23:\n\tsome files vanished"),
+    );
+    assert_eq!(
+        line,
+        "2026-01-02T03:04:05Z\tfailed\t1024\t/mnt/disk1/Movies/a\\tb.mkv\t\
+         /mnt/disk2/Movies/a\\tb.mkv\trsync exited with code 23:\\n\\tsome files vanished\n",
+        "fields are tab-separated and embedded tabs and newlines escaped"
+    );
+    let ok = audit_line("t", MoveStatus::Completed, 1, ("/a", "/b"), None);
+    assert_eq!(ok, "t\tcompleted\t1\t/a\t/b\t\n", "no reason leaves the last field empty");
+}
+
+#[tokio::test]
+async fn test_audit_log_writes_each_line_at_once() {
+    use crate::executor::audit::AuditLog;
+    let path = std::env::temp_dir().join(format!("pb-audit-{}.log", std::process::id()));
+    let mounts = HashMap::from([(1, "/mnt/disk1".to_string()), (2, "/mnt/disk2".to_string())]);
+    let mut audit = AuditLog::open(Some(&path.to_string_lossy()), mounts).await;
+    let m = PlannedMove {
+        id: 1,
+        plan_id: 1,
+        source_disk_id: 1,
+        target_disk_id: 2,
+        file_path: "Movies/a.mkv".into(),
+        file_size: 10,
+        move_order: 1,
+        phase: 1,
+        status: MoveStatus::Pending,
+        error_message: None,
+        source_mtime: None,
+        target_path: None,
+    };
+
+    audit.record(&m, "Movies/a.mkv", MoveStatus::Completed, None).await;
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        written.ends_with("\tcompleted\t10\t/mnt/disk1/Movies/a.mkv\t/mnt/disk2/Movies/a.mkv\t\n"),
+        "the line is on disk before the log is dropped: {written:?}"
+    );
+    drop(audit);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_same_device_catches_doubly_mounted_disk() {
+    use crate::executor::same_device;
+    let root = std::env::temp_dir().join(format!("pb-same-dev-{}", std::process::id()));
+    let (a, b) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(&a).unwrap();
+    std::fs::create_dir_all(&b).unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    assert!(same_device(a, b), "two folders on one filesystem share a device");
+    assert!(!same_device(a, "/proc"), "separate filesystems differ");
+    assert!(!same_device(a, "/nonexistent/pb-disk"), "an unreadable mount isn't flagged here");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_copy_size_mismatch_catches_truncated_target() {
+    assert_eq!(copy_size_mismatch(100, Some(100), 100), None, "matching sizes should verify");
+    let truncated = copy_size_mismatch(100, Some(100), 60).unwrap();
+    assert!(truncated.contains("60 != expected 100"), "unexpected message: {truncated}");
+    let grown = copy_size_mismatch(100, Some(120), 100).unwrap();
+    assert!(grown.contains("source size 120"), "unexpected message: {grown}");
+    assert!(copy_size_mismatch(100, None, 60).is_some(), "the planned size is always checked");
+    assert_eq!(copy_size_mismatch(100, None, 100), None, "source size only with verify_size");
+}
+
+#[tokio::test]
+async fn test_truncated_copy_keeps_source_without_verify_size() {
+    use crate::executor::{verify_and_remove_source, CopyChecks};
+    let root = std::env::temp_dir().join(format!("pb-verify-off-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let source = root.join("source.bin");
+    let target = root.join("target.bin");
+    std::fs::write(&source, vec![0u8; 100]).unwrap();
+    let (source, target) = (source.to_str().unwrap(), target.to_str().unwrap());
+    std::fs::write(staging_path(target), vec![0u8; 60]).unwrap();
+    let pre_rsync = std::fs::symlink_metadata(source).unwrap();
+    let checks = CopyChecks {
+        expected_size: 100,
+        verify_size: false,
+        verify_xattrs: false,
+        preserve_acls: false,
+    };
+
+    let err = verify_and_remove_source(source, target, &pre_rsync, &checks).await.unwrap_err();
+    assert!(err.to_string().contains("60 != expected 100"), "unexpected error: {err}");
+    assert!(std::path::Path::new(source).exists(), "the source must survive a short copy");
+    assert!(!std::path::Path::new(target).exists(), "the short copy is never put in place");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_compress_flags_keep_progress_and_paths() {
+    let opts = RsyncOptions {
+        use_progress2: true,
+        compress: true,
+        compress_level: Some(3),
+        ..RsyncOptions::default()
+    };
+    let args = build_rsync_args(&opts, "/mnt/disk1/f", "/mnt/disk2/f");
+    assert_eq!(
+        args,
+        [
+            "-avPX",
+            "--links",
+            "--info=progress2",
+            "--compress",
+            "--compress-level=3",
+            "/mnt/disk1/f",
+            "/mnt/disk2/f"
+        ],
+        "compression flags should sit between progress and the paths"
+    );
+}
+
+#[test]
+fn test_parse_rsync_progress_lines() {
+    let mid = parse_rsync_progress("      1,234,567  45%   12.34MB/s    0:00:05").unwrap();
+    assert!((mid.percent - 45.0).abs() < f64::EPSILON, "percent: {mid:?}");
+    assert_eq!((mid.speed.as_str(), mid.eta.as_str()), ("12.34MB/s", "0:00:05"));
+
+    let done = parse_rsync_progress("  1,234,567 100%  110.52MB/s    0:00:10 (xfr#1, to-chk=0/1)")
+        .unwrap();
+    assert!((done.percent - 100.0).abs() < f64::EPSILON, "xfr# trailer should still parse");
+    assert_eq!(done.speed, "110.52MB/s", "trailer line speed");
+
+    let human = parse_rsync_progress("          1.23G  17%  987.65kB/s    1:02:03").unwrap();
+    assert_eq!((human.speed.as_str(), human.eta.as_str()), ("987.65kB/s", "1:02:03"));
+
+    let bare = parse_rsync_progress("         32,768   3%").unwrap();
+    assert!((bare.percent - 3.0).abs() < f64::EPSILON, "percent without speed/eta");
+    assert!(bare.speed.is_empty() && bare.eta.is_empty(), "missing fields stay empty");
+
+    let with_name = parse_rsync_progress("Movies/50% off.mkv\n      4,096  10%  1.00MB/s  0:00:09");
+    assert_eq!(with_name.map(|p| p.eta), Some("0:00:09".to_string()), "file name line ignored");
+
+    assert_eq!(
+        parse_rsync_progress("Movies/50% off.mkv"),
+        None,
+        "a % in a file name is not progress"
+    );
+    assert_eq!(parse_rsync_progress("sending incremental file list"), None, "chatter is skipped");
+}
+
+#[test]
+fn test_odd_file_names_keep_paths_and_progress_intact() {
+    use crate::executor::disk_path;
+
+    for name in ["Movies/100% Real (2020).mkv", "a/tab\there.mkv", "a/new\nline.mkv", "a/ü ñ.mkv"]
+    {
+        assert_eq!(disk_path("/mnt/disk1", name), format!("/mnt/disk1/{name}"), "{name:?}");
+    }
+    assert_eq!(
+        disk_path("/mnt/disk1/", "/etc/passwd"),
+        "/mnt/disk1/etc/passwd",
+        "a leading slash never replaces the mount"
+    );
+
+    let same_line =
+        parse_rsync_progress("Movies/12 34% off.mkv      4,096  10%  1.00MB/s  0:00:09");
+    assert_eq!(same_line.map(|p| p.percent), Some(10.0), "the trailing row wins over the name");
+    assert_eq!(
+        parse_rsync_progress("Movies/1,024  50% 1.00MB/s 0:00:01 remastered.mkv"),
+        None,
+        "a name that merely contains a row is not progress"
+    );
+    assert_eq!(
+        parse_rsync_progress("weird\\#012name.mkv"),
+        None,
+        "rsync-escaped control characters are just a name"
+    );
+}
+
+#[test]
+fn test_smart_health_verdicts() {
+    let ata = "=== START OF READ SMART DATA SECTION ===\n\
+               SMART overall-health self-assessment test result: PASSED\n";
+    assert_eq!(parse_smart_health(ata), SmartHealth::Passed, "ATA pass");
+    let scsi = "SMART Health Status: OK\n";
+    assert_eq!(parse_smart_health(scsi), SmartHealth::Passed, "SCSI pass");
+    let failed = "SMART overall-health self-assessment test result: FAILED!\n";
+    assert_eq!(
+        parse_smart_health(failed),
+        SmartHealth::Failing("SMART health is FAILED!".to_string()),
+        "a failing verdict excludes the disk"
+    );
+    let parked = "Device is in STANDBY mode, exit(2)\n";
+    assert_eq!(parse_smart_health(parked), SmartHealth::Standby, "parked disks aren't judged");
+    assert_eq!(parse_smart_health(""), SmartHealth::Unknown, "no verdict is unknown, not failing");
+    let usb = "Read Device Identity failed: Unknown USB bridge\n";
+    assert_eq!(
+        parse_smart_health(usb),
+        SmartHealth::Unknown,
+        "bridges without SMART aren't judged"
+    );
+
+    let devices = parse_disk_devices("[\"disk1\"]\ndevice=\"sdb\"\n[\"disk2\"]\ndevice=\"\"\n");
+    assert_eq!(devices.get("disk1").map(String::as_str), Some("sdb"), "device read per section");
+    assert!(!devices.contains_key("disk2"), "empty device slots are dropped");
+}
+
+#[test]
+fn test_undo_refuses_changed_or_displaced_files() {
+    use crate::db::{Database, ExecutedMove};
+    use crate::executor::{mtime_secs, undo_blocker};
+
+    let root = std::env::temp_dir().join(format!("pb-undo-{}", std::process::id()));
+    let (src_mount, tgt_mount) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(&src_mount).unwrap();
+    std::fs::create_dir_all(&tgt_mount).unwrap();
+    std::fs::write(tgt_mount.join("film.mkv"), b"0123456789").unwrap();
+    let meta = std::fs::metadata(tgt_mount.join("film.mkv")).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    db.record_executed_move(&ExecutedMove {
+        move_id: 42,
+        source_mount: src_mount.to_string_lossy().into_owned(),
+        target_mount: tgt_mount.to_string_lossy().into_owned(),
+        file_path: "film.mkv".into(),
+        target_path: "film.mkv".into(),
+        file_size: 10,
+        target_mtime: mtime_secs(&meta),
+        executed_at: None,
+        undone_at: None,
+    })
+    .unwrap();
+    let entry = db.get_executed_move(42).unwrap().unwrap();
+    assert_eq!(undo_blocker(&entry), None, "an untouched move can be undone");
+
+    std::fs::write(src_mount.join("film.mkv"), b"new").unwrap();
+    assert!(undo_blocker(&entry).is_some(), "a new file at the source path blocks undo");
+    std::fs::remove_file(src_mount.join("film.mkv")).unwrap();
+
+    std::fs::write(tgt_mount.join("film.mkv"), b"truncated").unwrap();
+    assert_eq!(
+        undo_blocker(&entry).as_deref(),
+        Some("File changed since it was moved"),
+        "a modified file must not be moved back"
+    );
+
+    db.mark_move_undone(42).unwrap();
+    let undone = db.get_executed_move(42).unwrap().unwrap();
+    assert_eq!(undo_blocker(&undone).as_deref(), Some("Move was already undone"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_progress_throttle_coalesces_rows() {
+    let start = std::time::Instant::now();
+    let at = |ms| start + std::time::Duration::from_millis(ms);
+    let mut throttle = ProgressThrottle::default();
+    assert!(throttle.should_publish(10.0, at(0)), "the first row is always sent");
+    assert!(!throttle.should_publish(10.5, at(100)), "a small step inside the interval is dropped");
+    assert!(throttle.should_publish(11.0, at(150)), "a full percent step is sent early");
+    assert!(!throttle.should_publish(11.0, at(400)), "no change inside the interval is dropped");
+    assert!(throttle.should_publish(11.2, at(700)), "the interval elapsing sends a row");
+    assert!(throttle.should_publish(100.0, at(701)), "100% is always sent");
+}
+
+#[test]
+fn test_parse_rsync_version() {
+    let output = "rsync  version 3.2.7  protocol version 31\nCopyright (C) 1996-2022\n";
+    assert_eq!(parse_rsync_version(output), Some((3, 2, 7)), "standard banner should parse");
+    assert_eq!(
+        parse_rsync_version("rsync  version v3.1.0  protocol version 31"),
+        Some((3, 1, 0)),
+        "a leading v is accepted"
+    );
+    assert_eq!(parse_rsync_version("openrsync: protocol version 29"), None, "no version found");
+}
+
+#[tokio::test]
+async fn test_recovery_removes_stray_staged_copy() {
+    use crate::db::Database;
+    use crate::executor::recovery::cleanup_partial_files;
+
+    let root = std::env::temp_dir().join(format!("pb-staged-{}", std::process::id()));
+    let (src_mount, tgt_mount) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(src_mount.join("a")).unwrap();
+    std::fs::create_dir_all(tgt_mount.join("a")).unwrap();
+    std::fs::write(src_mount.join("a/f.mkv"), b"0123456789").unwrap();
+    let target = tgt_mount.join("a/f.mkv").to_string_lossy().into_owned();
+    let staged = staging_path(&target);
+    assert!(staged.ends_with("/a/f.mkv.pb-tmp"), "staging name sits beside the target: {staged}");
+    std::fs::write(&staged, b"01234").unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", &src_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", &tgt_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    db.insert_planned_moves(&[PlannedMove {
+        id: 0,
+        plan_id,
+        source_disk_id: d1,
+        target_disk_id: d2,
+        file_path: "a/f.mkv".into(),
+        file_size: 10,
+        move_order: 1,
+        phase: 1,
+        status: MoveStatus::Pending,
+        error_message: None,
+        source_mtime: None,
+        target_path: None,
+    }])
+    .unwrap();
+    let move_id = db.get_plan_moves(plan_id).unwrap()[0].move_info.id;
+
+    cleanup_partial_files(&db, &[move_id]).await.unwrap();
+    assert!(!std::path::Path::new(&staged).exists(), "the unfinished copy is deleted");
+    assert!(!std::path::Path::new(&target).exists(), "nothing appears at the real path");
+    assert!(src_mount.join("a/f.mkv").exists(), "the source is untouched");
+    let status = db.get_plan_moves(plan_id).unwrap()[0].move_info.status;
+    assert_eq!(status, MoveStatus::Pending, "the move is retried next run");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_recovery_keeps_preexisting_same_size_target() {
+    use crate::db::Database;
+    use crate::executor::recovery::cleanup_partial_files;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let root = std::env::temp_dir().join(format!("pb-preexisting-{}", std::process::id()));
+    let (src_mount, tgt_mount) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(&src_mount).unwrap();
+    std::fs::create_dir_all(&tgt_mount).unwrap();
+    let set_mtime = |path: &std::path::Path, secs: u64| {
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+    };
+    for name in ["ours.mkv", "theirs.mkv"] {
+        std::fs::write(src_mount.join(name), b"0123456789").unwrap();
+        set_mtime(&src_mount.join(name), 1_000_000);
+    }
+    // A copy we renamed into place keeps the source's mtime; a file that
+    // happened to be there already does not.
+    std::fs::write(tgt_mount.join("ours.mkv"), b"0123456789").unwrap();
+    set_mtime(&tgt_mount.join("ours.mkv"), 1_000_000);
+    std::fs::write(tgt_mount.join("theirs.mkv"), b"abcdefghij").unwrap();
+    set_mtime(&tgt_mount.join("theirs.mkv"), 2_000_000);
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", &src_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", &tgt_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    let planned = |order: i32, name: &str| PlannedMove {
+        id: 0,
+        plan_id,
+        source_disk_id: d1,
+        target_disk_id: d2,
+        file_path: name.into(),
+        file_size: 10,
+        move_order: order,
+        phase: 1,
+        status: MoveStatus::Pending,
+        error_message: None,
+        source_mtime: Some(1_000_000),
+        target_path: None,
+    };
+    db.insert_planned_moves(&[planned(1, "ours.mkv"), planned(2, "theirs.mkv")]).unwrap();
+    let ids: Vec<i64> =
+        db.get_plan_moves(plan_id).unwrap().iter().map(|m| m.move_info.id).collect();
+
+    let stats = cleanup_partial_files(&db, &ids).await.unwrap();
+    assert_eq!((stats.completed, stats.needs_review), (1, 1), "{stats:?}");
+    let moves = db.get_plan_moves(plan_id).unwrap();
+    assert_eq!(moves[0].move_info.status, MoveStatus::Completed, "our copy is settled");
+    assert!(!src_mount.join("ours.mkv").exists(), "and its source removed");
+    assert_eq!(moves[1].move_info.status, MoveStatus::Failed, "an unproven target needs review");
+    assert_eq!(std::fs::read(src_mount.join("theirs.mkv")).unwrap(), b"0123456789");
+    assert_eq!(std::fs::read(tgt_mount.join("theirs.mkv")).unwrap(), b"abcdefghij");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_path_remap_rewrites_whole_folder_prefixes() {
+    let remap: PathRemap = "downloads/complete=>media, downloads=>inbox/".parse().unwrap();
+    assert_eq!(remap.target_path("downloads/complete/tv/ep1.mkv"), "media/tv/ep1.mkv");
+    assert_eq!(remap.target_path("downloads/x.iso"), "inbox/x.iso", "shorter prefix still applies");
+    assert_eq!(
+        remap.target_path("downloads-old/x.iso"),
+        "downloads-old/x.iso",
+        "prefixes only match whole folder names"
+    );
+    assert_eq!(remap.to_string().parse::<PathRemap>().unwrap(), remap);
+    assert!("media=>../user".parse::<PathRemap>().is_err(), "rules can't climb out of the disk");
+    assert!("media".parse::<PathRemap>().is_err(), "a rule needs a target");
+}
+
+#[tokio::test]
+async fn test_recovery_follows_remapped_target() {
+    use crate::db::Database;
+    use crate::executor::recovery::cleanup_partial_files;
+
+    let root = std::env::temp_dir().join(format!("pb-remap-{}", std::process::id()));
+    let (src_mount, tgt_mount) = (root.join("disk1"), root.join("disk2"));
+    std::fs::create_dir_all(&src_mount).unwrap();
+    std::fs::create_dir_all(tgt_mount.join("media")).unwrap();
+    std::fs::write(tgt_mount.join("media/f.mkv"), b"0123456789").unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let d1 = db.upsert_disk("disk1", &src_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let d2 = db.upsert_disk("disk2", &tgt_mount.to_string_lossy(), 1000, 0, 1000, None).unwrap();
+    let plan_id = db.create_plan(0.1, 0.5, 0.5, 0.2).unwrap();
+    db.insert_planned_moves(&[PlannedMove {
+        id: 0,
+        plan_id,
+        source_disk_id: d1,
+        target_disk_id: d2,
+        file_path: "downloads/f.mkv".into(),
+        file_size: 10,
+        move_order: 1,
+        phase: 1,
+        status: MoveStatus::Pending,
+        error_message: None,
+        source_mtime: None,
+        target_path: None,
+    }])
+    .unwrap();
+    let move_id = db.get_plan_moves(plan_id).unwrap()[0].move_info.id;
+    db.set_move_target_path(move_id, "media/f.mkv").unwrap();
+
+    cleanup_partial_files(&db, &[move_id]).await.unwrap();
+    let m = db.get_plan_moves(plan_id).unwrap().remove(0).move_info;
+    assert_eq!(m.status, MoveStatus::Completed, "the copy at the rewritten path counts");
+    assert_eq!(m.destination_path(), "media/f.mkv");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_rsync_command_wraps_with_priorities() {
+    let plain = rsync_command(&RsyncOptions::default(), "/mnt/disk1/f", "/mnt/disk2/f");
+    assert_eq!(plain.first().map(String::as_str), Some("rsync"), "no wrappers by default");
+
+    let low = RsyncOptions { nice: Some(19), ionice_class: Some(3), ..RsyncOptions::default() };
+    let wrapped = rsync_command(&low, "/mnt/disk1/f", "/mnt/disk2/f");
+    assert_eq!(
+        wrapped[..7],
+        ["ionice", "-c", "3", "nice", "-n", "19", "rsync"],
+        "ionice wraps nice, which wraps rsync: {wrapped:?}"
+    );
+    assert_eq!(wrapped.last().map(String::as_str), Some("/mnt/disk2/f"), "target still goes last");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_xattr_fetch_retries_a_value_that_grew() {
+    use crate::executor::xattrs::fetch_sized;
+
+    // The value is 3 bytes when probed, 5 by the time it is fetched.
+    let mut value: &[u8] = b"abc";
+    let mut grown = false;
+    let fetched = fetch_sized(|buf| {
+        if buf.is_empty() {
+            return Ok(value.len());
+        }
+        if !grown {
+            grown = true;
+            value = b"abcde";
+        }
+        if buf.len() < value.len() {
+            return Err(std::io::Error::from_raw_os_error(libc::ERANGE));
+        }
+        buf[..value.len()].copy_from_slice(value);
+        Ok(value.len())
+    })
+    .unwrap();
+    assert_eq!(fetched, b"abcde", "the fetch probes again instead of failing");
+
+    let denied = fetch_sized(|_| Err(std::io::Error::from_raw_os_error(libc::EACCES)));
+    assert!(denied.is_err(), "other errors still fail");
+}
+
+#[test]
+fn test_xattr_diff_and_acl_flag() {
+    use crate::executor::xattrs::diff_xattrs;
+    use std::collections::BTreeMap;
+
+    let acls = RsyncOptions { preserve_acls: true, ..RsyncOptions::default() };
+    let args = build_rsync_args(&acls, "/mnt/disk1/a/f.mkv", "/mnt/disk2/a/f.mkv");
+    assert!(args.contains(&"-A".to_string()), "preserve_acls should add -A: {args:?}");
+
+    let mut src: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    src.insert("user.comment".to_string(), b"film".to_vec());
+    src.insert("system.posix_acl_access".to_string(), vec![2, 0, 0, 0]);
+    let mut tgt = src.clone();
+    assert_eq!(diff_xattrs(&src, &tgt, true), None, "identical sets match");
+
+    tgt.remove("system.posix_acl_access");
+    assert_eq!(diff_xattrs(&src, &tgt, false), None, "ACLs ignored without -A");
+    assert!(
+        diff_xattrs(&src, &tgt, true).is_some_and(|m| m.contains("posix_acl_access")),
+        "a dropped ACL fails the move when ACLs are copied"
+    );
+
+    tgt.insert("user.comment".to_string(), b"other".to_vec());
+    assert!(
+        diff_xattrs(&src, &tgt, false).is_some_and(|m| m.contains("differs")),
+        "a changed value is reported"
+    );
+}
+
+#[tokio::test]
+async fn test_prune_empty_parents_keeps_share_and_non_empty_dirs() {
+    use crate::executor::prune_empty_parents;
+
+    let mount = std::env::temp_dir().join(format!("pb-prune-{}", std::process::id()));
+    std::fs::create_dir_all(mount.join("Movies/A/Extras")).unwrap();
+    std::fs::create_dir_all(mount.join("Movies/B")).unwrap();
+    std::fs::write(mount.join("Movies/B/keep.mkv"), b"x").unwrap();
+    let mount_str = mount.to_string_lossy().into_owned();
+
+    let removed = prune_empty_parents(&mount_str, "Movies/A/Extras/f.mkv").await;
+    assert_eq!(removed, 2, "Extras and A were emptied");
+    assert!(mount.join("Movies").is_dir(), "the share folder stays");
+
+    let kept = prune_empty_parents(&mount_str, "Movies/B/moved.mkv").await;
+    assert_eq!(kept, 0, "a directory with files is never removed");
+    assert!(mount.join("Movies/B/keep.mkv").exists(), "remaining file untouched");
+
+    std::fs::remove_dir_all(&mount).unwrap();
+}
@@ -0,0 +1,20 @@
+use crate::metrics::Metrics;
+use crate::DaemonState;
+
+#[test]
+fn test_metrics_render_prometheus_text() {
+    let metrics = Metrics::default();
+    metrics.record_move_completed(1024);
+    metrics.record_move_failed();
+
+    let out = metrics.render(DaemonState::Executing, Some(0.25));
+    assert!(out.contains("pb_moves_completed_total 1\n"), "completed counter missing:\n{out}");
+    assert!(out.contains("pb_bytes_moved_total 1024\n"), "bytes counter missing:\n{out}");
+    assert!(out.contains("pb_moves_failed_total 1\n"), "failed counter missing:\n{out}");
+    assert!(out.contains("pb_current_imbalance 0.25\n"), "imbalance gauge missing:\n{out}");
+    assert!(
+        out.contains("pb_daemon_state{state=\"executing\"} 1\n"),
+        "active state should be 1:\n{out}"
+    );
+    assert!(out.contains("pb_daemon_state{state=\"idle\"} 0\n"), "inactive state should be 0");
+}
@@ -1,4 +1,9 @@
+mod api_tests;
 mod balancer_tests;
 mod config_tests;
 mod db_tests;
+mod events_tests;
+mod executor_tests;
+mod metrics_tests;
 mod scanner_tests;
+mod scheduler_tests;
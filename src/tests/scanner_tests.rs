@@ -1,4 +1,7 @@
+use crate::scanner::mounts::{parse_mounts, MountInfo};
 use crate::scanner::validation::validate_path;
+use crate::scanner::{discover_disks, mount_problem, ExcludeMatcher};
+use std::path::Path;
 
 #[test]
 fn test_validate_path_rejects_fuse() {
@@ -14,3 +17,239 @@ fn test_validate_path_allows_direct_disks() {
     assert!(validate_path("/mnt/disk25/data/file.txt").is_ok(), "disk25 path should be valid");
     assert!(validate_path("/mnt/cache/appdata/").is_ok(), "cache path should be valid");
 }
+
+#[test]
+fn test_exclude_patterns_anchor_on_relative_path() {
+    let patterns = vec!["*.tmp".to_string(), ".Recycle.Bin/**".to_string()];
+    let matcher = ExcludeMatcher::new(&patterns).unwrap();
+
+    assert!(matcher.is_excluded(Path::new("downloads/part.tmp")), "*.tmp should be excluded");
+    assert!(
+        matcher.is_excluded(Path::new(".Recycle.Bin/movies/old.mkv")),
+        ".Recycle.Bin contents should be excluded"
+    );
+    assert!(!matcher.is_excluded(Path::new("movies/film.mkv")), "normal media should be kept");
+    assert!(
+        !matcher.is_excluded(Path::new("movies/.Recycle.Bin.mkv")),
+        "patterns are anchored, not substring matches"
+    );
+}
+
+#[test]
+fn test_exclude_pattern_on_folder_covers_children() {
+    let matcher = ExcludeMatcher::new(&["appdata".to_string()]).unwrap();
+    assert!(
+        matcher.is_excluded(Path::new("appdata/plex/db.sqlite")),
+        "folder match excludes children"
+    );
+    assert!(!matcher.is_excluded(Path::new("media/appdata.txt")), "unrelated paths are kept");
+}
+
+#[test]
+fn test_discover_disks_merges_extra_mounts() {
+    let base = std::env::temp_dir().join(format!("pb-discover-{}", std::process::id()));
+    for dir in ["disk1", "disk2", "tank", "fastpool", "appdata"] {
+        std::fs::create_dir_all(base.join(dir)).unwrap();
+    }
+    let base_str = base.to_str().unwrap();
+    let extra = vec![
+        format!("{base_str}/tank"),
+        format!("{base_str}/fastpool/"),
+        // Already auto-discovered; must not be listed twice.
+        format!("{base_str}/disk1"),
+        format!("{base_str}/missing"),
+        "/mnt/user/tank".to_string(),
+    ];
+
+    let disks = discover_disks(base_str, &extra).unwrap();
+    std::fs::remove_dir_all(&base).unwrap();
+
+    let names: Vec<&str> = disks.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["disk1", "disk2", "fastpool", "tank"], "extra mounts merge by mount path");
+    assert!(
+        disks.iter().all(|d| !d.mount_path.ends_with('/')),
+        "mount paths are normalized without a trailing slash"
+    );
+}
+
+#[test]
+fn test_scan_walks_only_include_roots() {
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::scanner::{scan_disk, ScanContext, SymlinkPolicy};
+
+    let mount = std::env::temp_dir().join(format!("pb-include-{}", std::process::id()));
+    for (dir, file) in [("media/tv", "ep1.mkv"), ("backups", "full.tar"), ("", "root.txt")] {
+        std::fs::create_dir_all(mount.join(dir)).unwrap();
+        std::fs::write(mount.join(dir).join(file), b"data").unwrap();
+    }
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let mount_str = mount.to_str().unwrap();
+    let disk_id = db.upsert_disk("disk1", mount_str, 1000, 0, 1000, None).unwrap();
+    let exclude = ExcludeMatcher::new(&[]).unwrap();
+    let roots = vec!["media".to_string(), "missing".to_string()];
+    let ctx = ScanContext {
+        db: &db,
+        disk_id,
+        mount_path: mount_str,
+        event_hub: &EventHub::new(16),
+        cancel: tokio_util::sync::CancellationToken::new(),
+        num_threads: 1,
+        exclude_dir: None,
+        exclude_patterns: &exclude,
+        include_roots: &roots,
+        symlink_policy: SymlinkPolicy::Skip,
+    };
+    let stats = scan_disk(&ctx).unwrap();
+    let files: Vec<String> = db
+        .get_all_files_on_disk_by_size(disk_id)
+        .unwrap()
+        .into_iter()
+        .map(|f| f.file_path)
+        .collect();
+    std::fs::remove_dir_all(&mount).unwrap();
+
+    assert_eq!(stats.files_scanned, 1, "only the media tree is walked");
+    assert_eq!(files, vec!["media/tv/ep1.mkv".to_string()], "backups and root files are ignored");
+}
+
+#[test]
+fn test_symlink_policy_controls_cataloging() {
+    use crate::db::Database;
+    use crate::events::EventHub;
+    use crate::scanner::{scan_disk, ScanContext, SymlinkPolicy};
+
+    let mount = std::env::temp_dir().join(format!("pb-symlinks-{}", std::process::id()));
+    std::fs::create_dir_all(&mount).unwrap();
+    std::fs::write(mount.join("film.mkv"), vec![0u8; 4096]).unwrap();
+    std::os::unix::fs::symlink("film.mkv", mount.join("link.mkv")).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    db.run_migrations().unwrap();
+    let mount_str = mount.to_str().unwrap();
+    let disk_id = db.upsert_disk("disk1", mount_str, 10_000, 0, 10_000, None).unwrap();
+    let exclude = ExcludeMatcher::new(&[]).unwrap();
+    let catalog = |symlink_policy: SymlinkPolicy| {
+        let ctx = ScanContext {
+            db: &db,
+            disk_id,
+            mount_path: mount_str,
+            event_hub: &EventHub::new(16),
+            cancel: tokio_util::sync::CancellationToken::new(),
+            num_threads: 1,
+            exclude_dir: None,
+            exclude_patterns: &exclude,
+            include_roots: &[],
+            symlink_policy,
+        };
+        scan_disk(&ctx).unwrap();
+        let mut files: Vec<(String, u64)> = db
+            .get_all_files_on_disk_by_size(disk_id)
+            .unwrap()
+            .into_iter()
+            .map(|f| (f.file_path, f.size_bytes))
+            .collect();
+        files.sort();
+        files
+    };
+    let skipped = catalog(SymlinkPolicy::Skip);
+    let linked = catalog(SymlinkPolicy::Catalog);
+    let followed = catalog(SymlinkPolicy::Follow);
+    std::fs::remove_dir_all(&mount).unwrap();
+
+    let film = ("film.mkv".to_string(), 4096);
+    assert_eq!(skipped, std::slice::from_ref(&film), "skip leaves the link out");
+    assert_eq!(
+        linked,
+        [film.clone(), ("link.mkv".to_string(), 8)],
+        "catalog records the link at its own size"
+    );
+    assert_eq!(followed, [film, ("link.mkv".to_string(), 4096)], "follow uses the target's size");
+}
+
+#[test]
+fn test_mount_health_checks() {
+    let table = parse_mounts(
+        "/dev/md1p1 /mnt/disk1 xfs rw,noatime 0 0\n\
+         /dev/md2p1 /mnt/disk2 xfs ro,noatime 0 0\n",
+    );
+    assert_eq!(
+        table.get("/mnt/disk2"),
+        Some(&MountInfo { fs_type: "xfs".to_string(), read_only: true }),
+        "ro option is detected"
+    );
+    assert_eq!(mount_problem("/mnt/disk1", &table, false), None, "a mounted disk can be scanned");
+    assert_eq!(
+        mount_problem("/mnt/disk3", &table, false).as_deref(),
+        Some("/mnt/disk3 is not mounted"),
+        "an unmounted disk is reported"
+    );
+    assert_eq!(
+        mount_problem("/mnt/disk2", &table, true).as_deref(),
+        Some("/mnt/disk2 is mounted read-only"),
+        "a read-only disk can't be written"
+    );
+
+    let dir = std::env::temp_dir().join(format!("pb-mount-probe-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mount = dir.to_str().unwrap();
+    let writable = parse_mounts(&format!("tmpfs {mount} tmpfs rw 0 0"));
+    assert_eq!(
+        mount_problem(mount, &writable, true),
+        None,
+        "the probe file is created and removed"
+    );
+    assert_eq!(
+        std::fs::read_dir(&dir).unwrap().count(),
+        0,
+        "the probe file must not be left behind"
+    );
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn test_escaped_mountpoints_are_decoded() {
+    let table = parse_mounts(
+        "/dev/sdb1 /mnt/disks/My\\040Backup xfs rw 0 0\n\
+         /dev/sdc1 /mnt/disks/tab\\011and\\134slash xfs ro 0 0\n",
+    );
+    assert!(table.contains_key("/mnt/disks/My Backup"), "\\040 is a space: {table:?}");
+    assert!(table.contains_key("/mnt/disks/tab\tand\\slash"), "tabs and backslashes: {table:?}");
+    assert_eq!(
+        mount_problem("/mnt/disks/My Backup/", &table, false),
+        None,
+        "a trailing slash still finds the mount"
+    );
+}
+
+#[test]
+fn test_plain_directory_disk_is_usable() {
+    let dir = std::env::temp_dir().join(format!("pb-plain-disk-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.to_str().unwrap();
+    let table = parse_mounts("/dev/md1p1 /mnt/disk1 xfs rw 0 0\n");
+    assert_eq!(
+        mount_problem(path, &table, true),
+        None,
+        "a directory outside /mnt (dev layout, dataset subfolder) needn't be a mountpoint"
+    );
+    assert_eq!(
+        mount_problem(&format!("{path}/missing"), &table, false).as_deref(),
+        Some(format!("{path}/missing is not mounted").as_str()),
+        "a path that doesn't exist can't be used"
+    );
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn test_scan_percent_uses_previous_total() {
+    use crate::scanner::scan::scan_percent;
+    assert!(scan_percent(500, None).abs() < f64::EPSILON, "a first scan has no estimate");
+    assert!((scan_percent(250, Some(1000)) - 25.0).abs() < 1e-9, "quarter of last scan's files");
+    assert!(
+        (scan_percent(1500, Some(1000)) - 99.0).abs() < 1e-9,
+        "a disk that grew stays short of done until the scan completes"
+    );
+}
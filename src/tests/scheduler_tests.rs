@@ -0,0 +1,55 @@
+use crate::scheduler::CronSchedule;
+use chrono::NaiveDateTime;
+
+fn at(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+#[test]
+fn test_cron_next_fire_times() {
+    let nightly = CronSchedule::parse("0 3 * * *").unwrap();
+    assert_eq!(
+        nightly.next_after(at("2024-05-01 02:59")),
+        Some(at("2024-05-01 03:00")),
+        "same day"
+    );
+    assert_eq!(
+        nightly.next_after(at("2024-05-01 03:00")),
+        Some(at("2024-05-02 03:00")),
+        "strictly after the current minute"
+    );
+
+    // 2024-05-04 is a Saturday; the next weekday 22:30 is Monday.
+    let weekdays = CronSchedule::parse("30 22 * * 1-5").unwrap();
+    assert_eq!(
+        weekdays.next_after(at("2024-05-04 00:00")),
+        Some(at("2024-05-06 22:30")),
+        "skip weekend"
+    );
+
+    let quarter_hourly = CronSchedule::parse("*/15 * * * *").unwrap();
+    assert_eq!(
+        quarter_hourly.next_after(at("2024-05-01 10:16")),
+        Some(at("2024-05-01 10:30")),
+        "step"
+    );
+
+    let sundays = CronSchedule::parse("0 0 * * 7").unwrap();
+    assert_eq!(
+        sundays.next_after(at("2024-05-01 00:00")),
+        Some(at("2024-05-05 00:00")),
+        "7 is Sunday"
+    );
+
+    let never = CronSchedule::parse("0 0 31 2 *").unwrap();
+    assert_eq!(never.next_after(at("2024-05-01 00:00")), None, "Feb 31 never fires");
+}
+
+#[test]
+fn test_cron_rejects_invalid_expressions() {
+    for expr in
+        ["", "0 3 * *", "60 * * * *", "* 24 * * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"]
+    {
+        assert!(CronSchedule::parse(expr).is_err(), "'{expr}' should be rejected");
+    }
+}